@@ -0,0 +1,113 @@
+//! `serve` 和 `rpc` 共用的请求处理：从 JSON 字段创建、修改待办事项，解析筛选参数
+//!
+//! 出错时返回说明问题所在字段的错误信息，由调用方转换为各自协议的校验错误。
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde_json::{Map, Value};
+
+use crate::fields::{self, FieldDecls};
+use crate::filter::Filter;
+use crate::format::end_of_day;
+use crate::todo::Todo;
+
+/// 可以通过请求修改的字段，`fields` 是自定义字段的对象，如 `{"points": 3, "customer": null}`
+pub const EDITABLE_FIELDS: &[&str] = &["title", "description", "priority", "due_date", "tags", "reminders", "fields"];
+
+/// 筛选参数的名称，与 `list` 命令的选项相同
+pub const FILTER_OPTIONS: &[&str] = &["all", "priority", "tag", "due", "overdue", "search", "sort", "collate"];
+
+/// 根据请求中的字段创建在 `created_at` 的待办事项，必须提供 `title`
+pub fn new_todo(mut fields: Map<String, Value>, created_at: DateTime<Local>, decls: &FieldDecls) -> Result<Todo, String> {
+    let title = match fields.remove("title") {
+        Some(Value::String(title)) if !title.trim().is_empty() => title,
+        Some(_) => return Err("字段 title 必须是非空字符串".to_string()),
+        None => return Err("缺少字段 title".to_string()),
+    };
+    let mut todo = Todo::new_at(title, None, created_at);
+    apply_fields(&mut todo, fields, decls)?;
+    todo.set_modified_at(None);
+    Ok(todo)
+}
+
+/// 将请求中的字段应用到待办事项，`null` 表示清除描述、截止日期、单独设置的提醒时间或自定义字段
+///
+/// 自定义字段按 `decls` 中的声明校验，与命令行的 `--set` 相同。
+pub fn apply_fields(todo: &mut Todo, fields: Map<String, Value>, decls: &FieldDecls) -> Result<(), String> {
+    for (key, value) in fields {
+        match (key.as_str(), value) {
+            ("title", Value::String(title)) if !title.trim().is_empty() => todo.set_title(title),
+            ("description", Value::String(desc)) => todo.set_description(Some(desc)),
+            ("description", Value::Null) => todo.set_description(None),
+            ("priority", Value::String(priority)) => todo.set_priority(priority.to_lowercase().parse()?),
+            ("due_date", Value::String(due)) => todo.set_due_date(Some(parse_due_date(&due)?)),
+            ("due_date", Value::Null) => todo.set_due_date(None),
+            ("tags", Value::Array(tags)) => {
+                let tags = tags
+                    .into_iter()
+                    .map(|tag| match tag {
+                        Value::String(tag) if !tag.trim().is_empty() => Ok(tag),
+                        _ => Err("字段 tags 必须是非空字符串的数组".to_string()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                todo.set_tags(tags);
+            }
+            ("reminders", Value::Array(offsets)) => {
+                let offsets = offsets
+                    .into_iter()
+                    .map(|offset| match offset {
+                        Value::String(offset) => offset.parse(),
+                        _ => Err("字段 reminders 必须是提醒时间字符串的数组，如 [\"1d\", \"2h\"]".to_string()),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                todo.set_reminders(Some(offsets));
+            }
+            ("reminders", Value::Null) => todo.set_reminders(None),
+            ("fields", Value::Object(custom)) => {
+                for (name, value) in custom {
+                    let value = match value {
+                        Value::String(s) => s,
+                        Value::Number(n) => n.to_string(),
+                        Value::Null => String::new(),
+                        other => return Err(format!("自定义字段 {} 的值无效: {}", name, other)),
+                    };
+                    fields::assign(todo, decls, &name, &value)?;
+                }
+            }
+            (key, value) if EDITABLE_FIELDS.contains(&key) => {
+                return Err(format!("字段 {} 的值无效: {}", key, value));
+            }
+            (key, _) => return Err(format!("未知或只读的字段: {}", key)),
+        }
+    }
+    Ok(())
+}
+
+/// 截止日期可以是 `YYYY-MM-DD`（当天结束时截止）或 RFC 3339 时间
+pub fn parse_due_date(s: &str) -> Result<DateTime<Local>, String> {
+    let due = match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        Ok(date) => end_of_day(date),
+        Err(_) => DateTime::parse_from_rfc3339(s).ok().map(|d| d.with_timezone(&Local)),
+    };
+    due.ok_or_else(|| format!("无效的截止日期: {}，应为 YYYY-MM-DD 或 RFC 3339 时间", s))
+}
+
+/// 设置一个筛选参数，开关类参数接受 `true`/`false`/`1`/`0`，空值视为 `true`
+pub fn set_filter_option(filter: &mut Filter, key: &str, value: &str) -> Result<(), String> {
+    let flag = || match value {
+        "" | "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(format!("参数 {} 的值无效: {}", key, value)),
+    };
+    match key {
+        "all" => filter.include_completed = flag()?,
+        "due" => filter.with_due_date = flag()?,
+        "overdue" => filter.overdue = flag()?,
+        "priority" => filter.priority = Some(value.to_lowercase().parse()?),
+        "tag" => filter.tag = Some(value.to_string()),
+        "search" => filter.search = Some(value.to_string()),
+        "sort" => filter.sort = Some(value.parse()?),
+        "collate" => filter.collation = value.parse()?,
+        _ => return Err(format!("未知的筛选参数: {}", key)),
+    }
+    Ok(())
+}
@@ -1,4 +1,15 @@
-use clap::{App, AppSettings, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use crate::collate;
+use crate::export;
+use crate::import;
+use crate::remind;
+use crate::tags;
+use crate::config::{self, Config};
+use crate::fields::{self, Condition};
+use crate::filter::{Filter, SortKey};
+use crate::urgency::UrgencyWeights;
+use crate::view;
 
 /// 解析命令行参数
 pub fn build_cli() -> App<'static, 'static> {
@@ -7,6 +18,52 @@ pub fn build_cli() -> App<'static, 'static> {
         .author("Rust学习者")
         .about("一个简单的命令行待办事项管理器")
         .setting(AppSettings::SubcommandRequiredElseHelp)
+        // 未知的子命令交给 PATH 中的插件
+        .setting(AppSettings::AllowExternalSubcommands)
+        .arg(
+            Arg::with_name("file")
+                .long("file")
+                .help("数据文件路径（默认为配置项 file，未设置时为 ~/.todo.json，todo.txt 存储为 ~/todo.txt）")
+                .takes_value(true)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .help("配置文件路径（默认为 ~/.config/todo/config.toml）")
+                .takes_value(true)
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("no-hooks")
+                .long("no-hooks")
+                .help("不运行配置目录下 hooks 目录中的钩子")
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("storage")
+                .long("storage")
+                .help("数据文件的存储格式 (json, todotxt)")
+                .takes_value(true)
+                .possible_values(&["json", "todotxt"])
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("key-file")
+                .long("key-file")
+                .help("加密数据文件使用的密钥文件（也可以通过 TODO_PASSPHRASE 环境变量提供密码）")
+                .takes_value(true)
+                .global(true)
+        )
+        .arg(
+            // export 和 merge 有自己的 --output，因此这个选项不是全局的，需要写在子命令之前
+            Arg::with_name("output")
+                .long("output")
+                .help("错误信息的格式，json 时以 JSON 对象输出到标准错误（写在子命令之前）")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+        )
         .subcommand(
             SubCommand::with_name("add")
                 .about("添加一个新的待办事项")
@@ -40,9 +97,17 @@ pub fn build_cli() -> App<'static, 'static> {
                     Arg::with_name("tags")
                         .short("t")
                         .long("tags")
-                        .help("待办事项的标签，用逗号分隔")
+                        .help("待办事项的标签，用逗号分隔，用 / 表示层级，如 工作/后端")
                         .takes_value(true)
-                ),
+                )
+                .arg(
+                    Arg::with_name("remind")
+                        .long("remind")
+                        .help("单独设置截止日期前的提醒时间，用逗号分隔，如 1d,2h；none 表示不提醒")
+                        .takes_value(true)
+                )
+                .arg(set_arg())
+                .arg(no_context_arg()),
         )
         .subcommand(
             SubCommand::with_name("list")
@@ -53,40 +118,56 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("all")
                         .help("显示所有待办事项，包括已完成的")
                 )
+                .args(&filter_args())
+                .arg(sort_arg())
+                .arg(collate_arg())
+                .arg(overdue_arg().short("o"))
+                .arg(no_context_arg())
                 .arg(
-                    Arg::with_name("priority")
-                        .short("p")
-                        .long("priority")
-                        .help("按优先级筛选 (low, medium, high)")
+                    Arg::with_name("style")
+                        .long("style")
+                        .help("显示方式，默认为配置项 list.style")
                         .takes_value(true)
-                        .possible_values(&["low", "medium", "high"])
+                        .possible_values(config::LIST_STYLES)
                 )
                 .arg(
-                    Arg::with_name("tag")
-                        .short("t")
-                        .long("tag")
-                        .help("按标签筛选")
+                    Arg::with_name("columns")
+                        .long("columns")
+                        .help("以表格显示，并指定列及顺序，如 id,title,due；默认为配置项 list.table_columns")
                         .takes_value(true)
-                )
+                        .validator(validate_table_columns)
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("next")
+                .about("显示最紧急的一个未完成待办事项，支持与 list 相同的筛选选项")
+                .args(&filter_args())
+                .arg(no_context_arg())
+                .arg(overdue_arg().short("o")),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("在标题、标签和描述中搜索，按相关度排序；支持拼音、首字母和拼写错误")
                 .arg(
-                    Arg::with_name("due")
-                        .short("d")
-                        .long("due")
-                        .help("显示有截止日期的待办事项")
+                    Arg::with_name("query")
+                        .help("搜索的内容，如 周报、gzbg、budget")
+                        .required(true)
+                        .multiple(true)
+                        .index(1),
                 )
                 .arg(
-                    Arg::with_name("overdue")
-                        .short("o")
-                        .long("overdue")
-                        .help("显示已过期的待办事项")
+                    Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("包括已完成的待办事项")
                 )
                 .arg(
-                    Arg::with_name("sort")
-                        .short("s")
-                        .long("sort")
-                        .help("排序方式 (priority, date, title)")
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .help("最多显示的结果数量")
                         .takes_value(true)
-                        .possible_values(&["priority", "date", "title"])
+                        .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|_| format!("无效的数量: {}", n)))
                 ),
         )
         .subcommand(
@@ -167,27 +248,559 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("remove-tag")
                         .help("移除标签")
                         .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("remind")
+                        .long("remind")
+                        .help("新的提醒时间，如 1d,2h；none 表示不提醒，default 表示使用 remind 命令的默认设置")
+                        .takes_value(true)
+                )
+                .arg(set_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("note")
+                .about("为待办事项追加一条带时间的注释，也可以修改或删除已有的注释")
+                .arg(
+                    Arg::with_name("id")
+                        .help("待办事项的ID")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("text")
+                        .help("注释的内容")
+                        .required_unless("delete")
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("edit")
+                        .short("e")
+                        .long("edit")
+                        .help("修改指定编号的注释（编号见 show 命令），保留原来的时间")
+                        .takes_value(true)
+                        .value_name("编号")
+                        .validator(validate_note_number),
+                )
+                .arg(
+                    Arg::with_name("delete")
+                        .long("delete")
+                        .help("删除指定编号的注释")
+                        .takes_value(true)
+                        .value_name("编号")
+                        .conflicts_with_all(&["edit", "text"])
+                        .validator(validate_note_number),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("显示一个待办事项的全部信息，注释按时间顺序列出")
+                .arg(
+                    Arg::with_name("id")
+                        .help("待办事项的ID")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tags")
+                .about("列出所有标签及使用它们的待办事项数")
+                .arg(
+                    Arg::with_name("tree")
+                        .long("tree")
+                        .help("按层级显示为树，上级标签的数量包含其下级标签"),
+                )
+                .arg(collate_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("view")
+                .about("使用保存的视图运行 list，也可以保存、删除和列出视图")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::TrailingVarArg)
+                .setting(AppSettings::AllowLeadingHyphen)
+                .arg(Arg::with_name("name").help("视图的名称").index(1))
+                .arg(
+                    Arg::with_name("args")
+                        .help("附加的 list 选项")
+                        .multiple(true)
+                        .allow_hyphen_values(true)
+                        .index(2),
+                )
+                .subcommand(
+                    SubCommand::with_name("save")
+                        .about("保存视图，如 view save morning --priority high --sort date")
+                        .setting(AppSettings::TrailingVarArg)
+                        .setting(AppSettings::AllowLeadingHyphen)
+                        .arg(Arg::with_name("name").help("视图的名称").required(true).index(1).validator(validate_view_name))
+                        .arg(list_args_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("删除视图")
+                        .arg(Arg::with_name("name").help("视图的名称").required(true).index(1)),
+                )
+                .subcommand(SubCommand::with_name("list").about("列出保存的视图")),
+        )
+        .subcommand(
+            SubCommand::with_name("context")
+                .about("切换上下文：list 和 next 只显示符合上下文筛选条件的事项，add 自动加上上下文的标签")
+                .arg(
+                    Arg::with_name("name")
+                        .help("切换到的上下文，none 表示取消；不指定时显示当前的上下文")
+                        .index(1),
+                )
+                .subcommand(
+                    SubCommand::with_name("define")
+                        .about("定义上下文，如 context define work --tag 工作；默认标签取自 --tag")
+                        .setting(AppSettings::TrailingVarArg)
+                        .setting(AppSettings::AllowLeadingHyphen)
+                        .arg(Arg::with_name("name").help("上下文的名称").required(true).index(1).validator(validate_view_name))
+                        .arg(
+                            Arg::with_name("tags")
+                                .long("default-tags")
+                                .help("add 时自动加上的标签，用逗号分隔，需写在名称之前；未指定时使用筛选条件中的 --tag")
+                                .takes_value(true),
+                        )
+                        .arg(list_args_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("删除上下文")
+                        .arg(Arg::with_name("name").help("上下文的名称").required(true).index(1)),
+                )
+                .subcommand(SubCommand::with_name("list").about("列出定义的上下文")),
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("查看和修改配置文件中的设置")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("get")
+                        .about("显示设置项的当前值")
+                        .arg(Arg::with_name("key").help("设置项，如 defaults.priority").required(true).index(1)),
+                )
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("修改设置项，如 config set defaults.priority high")
+                        .arg(Arg::with_name("key").help("设置项").required(true).index(1))
+                        .arg(Arg::with_name("value").help("新的值").required(true).empty_values(true).index(2)),
+                )
+                .subcommand(
+                    SubCommand::with_name("unset")
+                        .about("删除设置项，恢复为默认值")
+                        .arg(Arg::with_name("key").help("设置项").required(true).index(1)),
+                )
+                .subcommand(SubCommand::with_name("list").about("列出所有设置项及其当前值")),
+        )
+        .subcommand(
+            SubCommand::with_name("tag")
+                .about("管理标签：重命名、合并及设置颜色和说明，下级标签随之移动")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("rename")
+                        .about("重命名标签，新名称已被使用时请改用 merge")
+                        .arg(Arg::with_name("old").help("原标签").required(true).index(1))
+                        .arg(Arg::with_name("new").help("新标签").required(true).index(2)),
+                )
+                .subcommand(
+                    SubCommand::with_name("merge")
+                        .about("将第一个标签合并到第二个标签中")
+                        .arg(Arg::with_name("from").help("被合并的标签").required(true).index(1))
+                        .arg(Arg::with_name("into").help("合并到的标签").required(true).index(2)),
+                )
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("设置标签的颜色和说明，值为空时清除")
+                        .arg(Arg::with_name("tag").help("标签").required(true).index(1))
+                        .arg(
+                            Arg::with_name("color")
+                                .long("color")
+                                .help("显示颜色 (black, red, green, yellow, blue, magenta, cyan, white 或 #rrggbb)")
+                                .takes_value(true)
+                                .empty_values(true)
+                                .validator(|c| if c.is_empty() { Ok(()) } else { tags::validate_color(&c) }),
+                        )
+                        .arg(
+                            Arg::with_name("description")
+                                .long("description")
+                                .help("标签的说明")
+                                .takes_value(true)
+                                .empty_values(true),
+                        ),
                 ),
         )
         .subcommand(
             SubCommand::with_name("export")
-                .about("导出待办事项")
+                .about("导出待办事项，支持与 list 相同的筛选和排序选项")
                 .arg(
                     Arg::with_name("format")
                         .short("f")
                         .long("format")
-                        .help("导出格式 (json, csv, html)")
+                        .help("导出格式 (json, csv, html, todotxt, ics, markdown, org, taskwarrior)，默认为配置项 defaults.export_format")
                         .takes_value(true)
-                        .possible_values(&["json", "csv", "html"])
-                        .default_value("json")
+                        .possible_values(export::FORMATS)
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("输出文件路径，`-` 表示输出到标准输出")
+                        .takes_value(true)
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("group-by")
+                        .short("g")
+                        .long("group-by")
+                        .help("Markdown/Org 清单的分组方式 (priority, tag)")
+                        .takes_value(true)
+                        .possible_values(&["priority", "tag"])
+                        .default_value("priority")
+                )
+                .arg(
+                    Arg::with_name("fields")
+                        .long("fields")
+                        .help("CSV/HTML/JSON 导出的字段及顺序，用逗号分隔 (id, uid, title, description, priority, status, created, completed, due, tags, annotations, field.<自定义字段>)")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("pending")
+                        .long("pending")
+                        .help("只导出未完成的待办事项")
+                )
+                .args(&filter_args())
+                .arg(sort_arg())
+                .arg(collate_arg())
+                .arg(overdue_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("encrypt")
+                .about("加密数据文件，之后的读写都需要密码或密钥文件")
+        )
+        .subcommand(
+            SubCommand::with_name("decrypt")
+                .about("将加密的数据文件转换回明文")
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("三方合并数据文件的两个副本，可用作 git 的合并驱动")
+                .after_help(
+                    "用作 git 合并驱动时，在 .gitattributes 中添加：\n    \
+                     .todo.json merge=todo\n\
+                     并在 git 配置中添加：\n    \
+                     [merge \"todo\"]\n        \
+                     name = todo_manager 三方合并\n        \
+                     driver = todo_manager merge --driver %O %A %B",
+                )
+                .arg(
+                    Arg::with_name("base")
+                        .help("共同祖先版本的数据文件")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("ours")
+                        .help("我方版本的数据文件")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("theirs")
+                        .help("对方版本的数据文件")
+                        .required(true)
+                        .index(3),
                 )
                 .arg(
                     Arg::with_name("output")
                         .short("o")
                         .long("output")
-                        .help("输出文件路径")
+                        .help("合并结果的输出路径，默认输出到标准输出")
+                        .takes_value(true)
+                        .conflicts_with("driver")
+                )
+                .arg(
+                    Arg::with_name("report")
+                        .short("r")
+                        .long("report")
+                        .help("冲突报告的输出路径，默认输出到标准错误")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("driver")
+                        .long("driver")
+                        .help("git 合并驱动模式：将合并结果写回我方文件")
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("启动 JSON REST API 服务，供浏览器扩展和仪表盘共享待办事项")
+                .arg(
+                    Arg::with_name("bind")
+                        .short("b")
+                        .long("bind")
+                        .help("监听地址，端口为 0 时由系统分配")
+                        .takes_value(true)
+                        .default_value("127.0.0.1:8080")
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rpc")
+                .about("通过标准输入输出提供 JSON-RPC 2.0 接口，供编辑器插件使用")
+        )
+        .subcommand(
+            SubCommand::with_name("remind")
+                .about("在截止日期前发出提醒，已发出的提醒不会重复")
+                .arg(
+                    Arg::with_name("daemon")
+                        .long("daemon")
+                        .help("持续运行并定期检查，否则检查一次后退出（适合由 cron 调用）")
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .help("守护进程检查的间隔秒数")
+                        .takes_value(true)
+                        .default_value("60")
+                )
+                .arg(
+                    Arg::with_name("offsets")
+                        .long("offsets")
+                        .help("默认的提醒时间，用逗号分隔，如 1d,1h；可以用 add/edit 的 --remind 单独设置")
+                        .takes_value(true)
+                        .default_value("1d,1h")
+                )
+                .arg(
+                    Arg::with_name("notify")
+                        .short("n")
+                        .long("notify")
+                        .help("提醒的发送方式 (stdout, desktop, command, webhook)")
+                        .takes_value(true)
+                        .possible_values(remind::NOTIFIERS)
+                        .default_value("stdout")
+                )
+                .arg(
+                    Arg::with_name("command")
+                        .long("command")
+                        .help("command 方式运行的 shell 命令，或 desktop 方式使用的通知程序")
+                        .takes_value(true)
+                        .required_if("notify", "command")
+                )
+                .arg(
+                    Arg::with_name("webhook")
+                        .long("webhook")
+                        .help("webhook 方式 POST 提醒的地址")
+                        .takes_value(true)
+                        .required_if("notify", "webhook")
+                )
+                .after_help(
+                    "command 方式通过环境变量传递提醒内容：TODO_ID、TODO_UID、TODO_TITLE、\
+                     TODO_DUE、TODO_OFFSET、TODO_MESSAGE。例如：\n    \
+                     todo_manager remind --daemon --notify command --command 'echo \"$TODO_MESSAGE\" | wall'"
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("与外部服务双向同步待办事项")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("caldav")
+                        .about("与 CalDAV 日历集合同步，每个待办事项对应一个 VTODO 资源")
+                        .arg(
+                            Arg::with_name("url")
+                                .long("url")
+                                .help("CalDAV 服务器上日历集合所在的地址")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("calendar")
+                                .long("calendar")
+                                .help("日历集合的名称")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("user")
+                                .short("u")
+                                .long("user")
+                                .help("用户名，密码从 TODO_CALDAV_PASSWORD 环境变量读取或在终端中输入")
+                                .takes_value(true)
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("从文件导入待办事项")
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .help("导入格式 (json, todotxt, ics, markdown, org, taskwarrior)，Markdown/Org 清单按标题合并")
+                        .takes_value(true)
+                        .possible_values(import::FORMATS)
+                        .default_value("json")
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("输入文件路径")
                         .takes_value(true)
                         .required(true)
                 ),
         )
-}
\ No newline at end of file
+}
+
+/// `list` 和 `export` 共用的筛选与排序参数
+fn filter_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("priority")
+            .short("p")
+            .long("priority")
+            .help("按优先级筛选 (low, medium, high)")
+            .takes_value(true)
+            .possible_values(&["low", "medium", "high"]),
+        Arg::with_name("tag")
+            .short("t")
+            .long("tag")
+            .help("按标签筛选，同时匹配它的下级标签")
+            .takes_value(true),
+        Arg::with_name("due")
+            .short("d")
+            .long("due")
+            .help("只保留有截止日期的待办事项"),
+        Arg::with_name("weight")
+            .short("w")
+            .long("weight")
+            .help("调整紧急度的权重，如 due=8、tag.工作=2，可以多次指定 (priority.high, priority.medium, priority.low, due, age, tag.<标签>)")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|pair| UrgencyWeights::default().set_pair(&pair)),
+        Arg::with_name("field")
+            .long("field")
+            .help("按自定义字段筛选，如 points>=3、customer=ACME、estimate（有值即可），可以多次指定")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|s| s.parse::<Condition>().map(|_| ())),
+        Arg::with_name("search")
+            .long("search")
+            .help("只保留标题、描述或注释中包含该文本的待办事项，不区分大小写")
+            .takes_value(true),
+    ]
+}
+
+/// `--sort` 参数，`next` 总是按紧急度选择，因此不在 [`filter_args`] 中
+fn sort_arg() -> Arg<'static, 'static> {
+    Arg::with_name("sort")
+        .short("s")
+        .long("sort")
+        .help("排序方式 (priority, date, title, urgency, field.<自定义字段>)")
+        .takes_value(true)
+        .validator(|s| s.parse::<SortKey>().map(|_| ()))
+}
+
+/// `--collate` 参数，按标题排序和列出标签时使用的排序规则
+fn collate_arg() -> Arg<'static, 'static> {
+    Arg::with_name("collate")
+        .long("collate")
+        .help("标题和标签的排序规则 (pinyin, stroke, unicode, bytes)，默认为配置项 defaults.collate")
+        .takes_value(true)
+        .possible_values(collate::COLLATIONS)
+}
+
+/// `--overdue` 参数，`export` 中 `-o` 已用于输出路径，因此短选项由调用方决定
+fn overdue_arg() -> Arg<'static, 'static> {
+    Arg::with_name("overdue")
+        .long("overdue")
+        .help("只保留已过期的待办事项")
+}
+
+/// `--no-context` 参数，忽略当前的上下文
+fn no_context_arg() -> Arg<'static, 'static> {
+    Arg::with_name("no-context")
+        .long("no-context")
+        .help("忽略当前的上下文")
+}
+
+/// `--set` 参数，设置自定义字段
+fn set_arg() -> Arg<'static, 'static> {
+    Arg::with_name("set")
+        .long("set")
+        .help("设置配置文件中声明的自定义字段，如 points=3；值为空时清除该字段，可以多次指定")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .validator(|s| fields::parse_assignment(&s).map(|_| ()))
+}
+
+/// 从子命令参数中解析筛选条件，`--all` 决定是否包含已完成的事项
+pub fn parse_filter(matches: &ArgMatches, config: &Config) -> Filter {
+    Filter {
+        include_completed: matches.is_present("all"),
+        priority: matches.value_of("priority").and_then(|p| p.parse().ok()),
+        tag: matches.value_of("tag").map(|t| t.to_string()),
+        with_due_date: matches.is_present("due"),
+        overdue: matches.is_present("overdue"),
+        sort: matches.value_of("sort").and_then(|s| s.parse().ok()),
+        collation: matches.value_of("collate").and_then(|c| c.parse().ok()).unwrap_or_else(|| config.collation()),
+        default_sort: config.default_sort(),
+        weights: parse_weights(matches),
+        // 已由参数的 validator 校验
+        conditions: matches
+            .values_of("field")
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.parse().ok())
+            .collect(),
+        declarations: config.fields.clone(),
+        search: matches.value_of("search").map(|s| s.to_string()),
+        context: None,
+    }
+}
+
+/// 视图和上下文中保存的 list 选项
+fn list_args_arg() -> Arg<'static, 'static> {
+    Arg::with_name("args")
+        .help("与 list 相同的筛选和排序选项")
+        .multiple(true)
+        .allow_hyphen_values(true)
+        .index(2)
+}
+
+fn validate_table_columns(list: String) -> Result<(), String> {
+    let columns: Vec<String> = list.split(',').map(|c| c.trim().to_string()).collect();
+    config::validate_columns(&columns, config::TABLE_COLUMNS)
+}
+
+fn validate_view_name(name: String) -> Result<(), String> {
+    view::validate_name(&name)
+}
+
+/// 按 `list` 的选项解析一组参数，用于校验和应用视图、上下文中保存的选项
+pub fn parse_list_args<'a>(args: impl IntoIterator<Item = &'a str>) -> Result<ArgMatches<'static>, String> {
+    let argv = ["todo", "list"].into_iter().chain(args);
+    let matches = build_cli().get_matches_from_safe(argv).map_err(|e| e.message)?;
+    match matches.subcommand() {
+        ("list", Some(sub_matches)) => Ok(sub_matches.clone()),
+        _ => unreachable!(),
+    }
+}
+
+/// 注释的编号从 1 开始
+fn validate_note_number(s: String) -> Result<(), String> {
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("无效的注释编号: {}", s)),
+    }
+}
+
+/// 从子命令参数中解析紧急度权重，未指定的使用默认值
+fn parse_weights(matches: &ArgMatches) -> UrgencyWeights {
+    let mut weights = UrgencyWeights::default();
+    for pair in matches.values_of("weight").into_iter().flatten() {
+        // 已由参数的 validator 校验
+        let _ = weights.set_pair(pair);
+    }
+    weights
+}
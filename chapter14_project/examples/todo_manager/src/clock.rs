@@ -0,0 +1,108 @@
+//! 时钟：需要当前时间的地方都从传入的 [`Clock`] 取得，便于测试和重现
+//!
+//! 存储（[`TodoStorage`](crate::storage::TodoStorage)）保存一个时钟，并交给加载和添加的事项，
+//! 事项用它记录完成和修改时间；筛选和提醒等需要当前时间的函数以参数接收时钟。
+//! 命令行程序启动时根据环境变量 `TODO_NOW` 创建时钟，
+//! 如 `TODO_NOW="2026-10-19 09:00" todo list` 会把 9 点当作当前时间计算是否过期和紧急度，
+//! 新建的事项也以这个时间作为创建时间。测试中可以使用 [`FixedClock`]，并通过 [`FixedClock::advance`] 推进时间。
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// 覆盖当前时间的环境变量
+pub const NOW_VAR: &str = "TODO_NOW";
+
+/// 当前时间的来源
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// 固定在某个时间的时钟，只有调用 [`FixedClock::set`] 或 [`FixedClock::advance`] 时才会改变
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Local>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Local>) -> Self {
+        FixedClock { now: Mutex::new(now) }
+    }
+
+    pub fn set(&self, now: DateTime<Local>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// 将时间向后推进
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// 根据环境变量 `TODO_NOW` 创建时钟，未设置时为系统时钟
+pub fn from_env() -> Result<Arc<dyn Clock>, String> {
+    match std::env::var(NOW_VAR) {
+        Ok(value) => {
+            let now = parse_time(&value)
+                .ok_or_else(|| format!("无效的 {}: {}（应为 RFC 3339 或 YYYY-MM-DD[ HH:MM[:SS]]）", NOW_VAR, value))?;
+            Ok(Arc::new(FixedClock::new(now)))
+        }
+        Err(_) => Ok(Arc::new(SystemClock)),
+    }
+}
+
+/// 解析 RFC 3339 时间或本地时间 `YYYY-MM-DD[ HH:MM[:SS]]`，只有日期时为当天零点
+pub fn parse_time(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local));
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::Todo;
+
+    #[test]
+    fn test_fixed_clock() {
+        let start = parse_time("2026-10-19 09:00").unwrap();
+        let clock = Arc::new(FixedClock::new(start));
+
+        let mut todo = Todo::new_at("写周报".to_string(), None, clock.now());
+        assert_eq!(*todo.created_at(), start);
+        clock.advance(Duration::hours(2));
+        todo.mark_as_done_at(clock.now());
+        assert_eq!(todo.completed_at(), Some(&(start + Duration::hours(2))));
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+
+        assert_eq!(parse_time("2026-10-19"), parse_time("2026-10-19T00:00:00"));
+        assert_eq!(
+            parse_time("2026-10-19T01:00:00Z"),
+            Some(Local.from_utc_datetime(&NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(1, 0, 0).unwrap()))
+        );
+        assert!(parse_time("明天").is_none());
+    }
+}
@@ -0,0 +1,105 @@
+//! 标题和标签的排序规则
+//!
+//! - `unicode`：Unicode 排序算法的默认规则，拉丁字母先不区分大小写和重音比较，汉字按码位
+//! - `pinyin`：汉字按拼音排序，排在拉丁字母之前
+//! - `stroke`：汉字按笔画数排序，同样排在拉丁字母之前
+//! - `bytes`：按 UTF-8 字节比较
+
+use std::cmp::Ordering;
+
+use icu_collator::CollatorOptions;
+use icu_locid::{locale, Locale};
+
+/// 支持的排序规则名称
+pub const COLLATIONS: &[&str] = &["pinyin", "stroke", "unicode", "bytes"];
+
+/// 排序规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collation {
+    Pinyin,
+    Stroke,
+    #[default]
+    Unicode,
+    Bytes,
+}
+
+impl Collation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Collation::Pinyin => "pinyin",
+            Collation::Stroke => "stroke",
+            Collation::Unicode => "unicode",
+            Collation::Bytes => "bytes",
+        }
+    }
+
+    /// 创建比较器；创建的开销较大，排序前创建一次，比较时重复使用
+    pub fn collator(self) -> Collator {
+        let locale: Locale = match self {
+            Collation::Pinyin => locale!("zh"),
+            Collation::Stroke => locale!("zh-u-co-stroke"),
+            Collation::Unicode => locale!("und"),
+            Collation::Bytes => return Collator { inner: None },
+        };
+        let inner = icu_collator::Collator::try_new(&locale.into(), CollatorOptions::new())
+            .expect("编译进程序的排序数据总是可用");
+        Collator { inner: Some(inner) }
+    }
+}
+
+impl std::str::FromStr for Collation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pinyin" => Ok(Collation::Pinyin),
+            "stroke" => Ok(Collation::Stroke),
+            "unicode" => Ok(Collation::Unicode),
+            "bytes" => Ok(Collation::Bytes),
+            _ => Err(format!("无效的排序规则: {}（可用规则: {}）", s, COLLATIONS.join(", "))),
+        }
+    }
+}
+
+/// 按排序规则比较字符串
+pub struct Collator {
+    /// `None` 表示按字节比较
+    inner: Option<icu_collator::Collator>,
+}
+
+impl Collator {
+    /// 排序规则认为相等的字符串（如只有 Unicode 规范化形式不同）再按字节比较，保证结果稳定
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match &self.inner {
+            Some(collator) => collator.compare(a, b).then_with(|| a.cmp(b)),
+            None => a.cmp(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collation: Collation, words: &[&str]) -> Vec<String> {
+        let collator = collation.collator();
+        let mut words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        words.sort_by(|a, b| collator.compare(a, b));
+        words
+    }
+
+    #[test]
+    fn test_collations() {
+        let chinese = ["张三", "阿姨", "王五", "一月", "十月", "李四"];
+        assert_eq!(sorted(Collation::Pinyin, &chinese), ["阿姨", "李四", "十月", "王五", "一月", "张三"]);
+        assert_eq!(sorted(Collation::Stroke, &chinese), ["一月", "十月", "王五", "张三", "李四", "阿姨"]);
+        assert_eq!(sorted(Collation::Bytes, &chinese), ["一月", "十月", "张三", "李四", "王五", "阿姨"]);
+
+        let latin = ["banana", "Apple", "cherry", "apple"];
+        assert_eq!(sorted(Collation::Unicode, &latin), ["apple", "Apple", "banana", "cherry"]);
+        assert_eq!(sorted(Collation::Bytes, &latin), ["Apple", "apple", "banana", "cherry"]);
+
+        assert_eq!("stroke".parse::<Collation>(), Ok(Collation::Stroke));
+        assert!("gbk".parse::<Collation>().is_err());
+    }
+}
@@ -0,0 +1,581 @@
+//! 配置文件，默认位于 `~/.config/todo/config.toml`（可以用 `--config` 指定），不存在时使用默认配置
+//!
+//! ```toml
+//! file = "~/Documents/todo.json"
+//!
+//! [defaults]
+//! priority = "high"
+//! sort = "urgency"
+//! due_time = "18:00"
+//! export_format = "csv"
+//!
+//! [format]
+//! date = "%d.%m.%Y"
+//! datetime = "%Y-%m-%d %H:%M"
+//!
+//! [colors]
+//! mode = "auto"
+//! high = "#ff5f00"
+//!
+//! [list]
+//! columns = ["due", "tags", "urgency"]
+//! style = "table"
+//! table_columns = ["id", "priority", "title", "due"]
+//! ```
+//!
+//! 命令修改配置文件时通过 [`Config::update`] 编辑原文件，保留其中的注释和格式。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::collate::Collation;
+use crate::export;
+use crate::fields::{self, FieldDecls};
+use crate::filter::SortKey;
+use crate::tags;
+use crate::todo::Priority;
+use crate::view::{Context, View};
+
+/// `config get/set` 可以读写的设置项及其说明
+pub const SETTINGS: &[(&str, &str)] = &[
+    ("file", "数据文件路径，--file 优先"),
+    ("defaults.priority", "新建事项的优先级 (low, medium, high)"),
+    ("defaults.sort", "list 和 export 未指定 --sort 时的排序方式"),
+    ("defaults.due_time", "截止日期的时间 (HH:MM 或 HH:MM:SS)"),
+    ("defaults.export_format", "export 未指定 --format 时的格式"),
+    ("defaults.collate", "标题和标签的排序规则 (pinyin, stroke, unicode, bytes)"),
+    ("format.date", "输入截止日期的格式，如 %Y-%m-%d"),
+    ("format.datetime", "显示时间的格式，如 %Y-%m-%d %H:%M:%S"),
+    ("colors.mode", "是否使用颜色 (auto, always, never)"),
+    ("colors.high", "高优先级的颜色"),
+    ("colors.medium", "中优先级的颜色"),
+    ("colors.low", "低优先级的颜色"),
+    ("colors.overdue", "已过期的截止日期的颜色"),
+    ("colors.highlight", "search 结果中匹配部分的颜色"),
+    ("list.columns", "list 显示的内容及顺序，用逗号分隔"),
+    ("list.style", "list 的显示方式 (lines, table)"),
+    ("list.table_columns", "list 以表格显示时的列及顺序，用逗号分隔"),
+];
+
+/// `list` 中每个事项标题下可以显示的内容
+pub const LIST_COLUMNS: &[&str] = &["description", "created", "due", "tags", "fields", "annotations", "urgency", "reminders"];
+
+/// `list` 的显示方式：每个事项占多行，或者对齐的表格
+pub const LIST_STYLES: &[&str] = &["lines", "table"];
+
+/// `list` 以表格显示时可以使用的列
+pub const TABLE_COLUMNS: &[&str] = &[
+    "id", "status", "priority", "title", "description", "created", "due", "tags", "fields", "annotations", "urgency", "reminders",
+];
+
+/// 颜色的使用方式
+const COLOR_MODES: &[&str] = &["auto", "always", "never"];
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("读取配置文件 {0} 时出错: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("配置文件 {0} 格式错误: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("配置文件 {0} 格式错误: {1}")]
+    Syntax(PathBuf, toml_edit::TomlError),
+    #[error("配置文件 {path} 中的 {key} 无效: {message}")]
+    Invalid { path: PathBuf, key: String, message: String },
+}
+
+/// 配置文件的内容
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// 数据文件路径，可以用 `~/` 开头
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default)]
+    pub format: Formats,
+    #[serde(default)]
+    pub colors: Colors,
+    #[serde(default)]
+    pub list: ListLayout,
+    /// 自定义字段的声明
+    #[serde(default)]
+    pub fields: FieldDecls,
+    /// 命名的视图，由 `view save` 写入
+    #[serde(default)]
+    pub views: BTreeMap<String, View>,
+    /// 可以切换的上下文，由 `context define` 写入
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+    /// 当前的上下文，由 `context <名称>` 写入
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+impl Config {
+    /// 默认的配置文件路径
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| Path::new(&home).join(".config/todo/config.toml"))
+    }
+
+    /// 读取并校验配置文件，文件不存在时返回默认配置
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match read(path)? {
+            Some(contents) => Config::parse(path, &contents),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// 解析并校验配置文件的内容，`path` 只用于错误信息
+    fn parse(path: &Path, contents: &str) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str(contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        let invalid = |key: &str, message| ConfigError::Invalid {
+            path: path.to_path_buf(),
+            key: key.to_string(),
+            message,
+        };
+        for (name, decl) in &config.fields {
+            let key = format!("fields.{}", name);
+            fields::validate_name(name).map_err(|m| invalid(&key, m))?;
+            decl.validate().map_err(|m| invalid(&key, m))?;
+        }
+        if let Some(context) = &config.context {
+            if !config.contexts.contains_key(context) {
+                return Err(invalid("context", format!("未定义的上下文: {}", context)));
+            }
+        }
+
+        let defaults = &config.defaults;
+        defaults.priority.parse::<Priority>().map_err(|m| invalid("defaults.priority", m))?;
+        if let Some(sort) = &defaults.sort {
+            sort.parse::<SortKey>().map_err(|m| invalid("defaults.sort", m))?;
+        }
+        if parse_time(&defaults.due_time).is_none() {
+            let message = format!("无效的时间: {}（应为 HH:MM 或 HH:MM:SS）", defaults.due_time);
+            return Err(invalid("defaults.due_time", message));
+        }
+        if !export::FORMATS.contains(&defaults.export_format.as_str()) {
+            let message = format!("不支持的格式: {}（可用格式: {}）", defaults.export_format, export::FORMATS.join(", "));
+            return Err(invalid("defaults.export_format", message));
+        }
+        defaults.collate.parse::<Collation>().map_err(|m| invalid("defaults.collate", m))?;
+        validate_time_format(&config.format.date).map_err(|m| invalid("format.date", m))?;
+        validate_time_format(&config.format.datetime).map_err(|m| invalid("format.datetime", m))?;
+
+        let colors = &config.colors;
+        if !COLOR_MODES.contains(&colors.mode.as_str()) {
+            return Err(invalid("colors.mode", format!("无效的值: {}（可用值: {}）", colors.mode, COLOR_MODES.join(", "))));
+        }
+        for (key, color) in [
+            ("colors.high", &colors.high),
+            ("colors.medium", &colors.medium),
+            ("colors.low", &colors.low),
+            ("colors.overdue", &colors.overdue),
+            ("colors.highlight", &colors.highlight),
+        ] {
+            tags::validate_color(color).map_err(|m| invalid(key, m))?;
+        }
+
+        validate_columns(&config.list.columns, LIST_COLUMNS).map_err(|m| invalid("list.columns", m))?;
+        if !LIST_STYLES.contains(&config.list.style.as_str()) {
+            let message = format!("无效的值: {}（可用值: {}）", config.list.style, LIST_STYLES.join(", "));
+            return Err(invalid("list.style", message));
+        }
+        validate_columns(&config.list.table_columns, TABLE_COLUMNS).map_err(|m| invalid("list.table_columns", m))?;
+        Ok(config)
+    }
+
+    /// 配置中的数据文件路径，`~/` 展开为用户主目录
+    pub fn data_file(&self) -> Option<PathBuf> {
+        let file = self.file.as_deref()?;
+        match (file.strip_prefix("~/"), std::env::var_os("HOME")) {
+            (Some(rest), Some(home)) => Some(Path::new(&home).join(rest)),
+            _ => Some(PathBuf::from(file)),
+        }
+    }
+
+    /// 新建事项的默认优先级
+    pub fn default_priority(&self) -> Priority {
+        self.defaults.priority.parse().unwrap_or_default()
+    }
+
+    /// list 和 export 的默认排序方式
+    pub fn default_sort(&self) -> Option<SortKey> {
+        self.defaults.sort.as_deref().and_then(|s| s.parse().ok())
+    }
+
+    /// 未指定 `--collate` 时的排序规则
+    pub fn collation(&self) -> Collation {
+        self.defaults.collate.parse().unwrap_or_default()
+    }
+
+    /// 只给出日期的截止日期使用的时间
+    pub fn due_time(&self) -> NaiveTime {
+        parse_time(&self.defaults.due_time).unwrap_or_else(|| NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+    }
+
+    /// 按 `format.date` 解析截止日期，时间取 `defaults.due_time`
+    pub fn parse_due_date(&self, date: &str) -> Result<DateTime<Local>, String> {
+        let invalid = || format!("无效的日期: {}（格式应为 {}）", date, self.format.date);
+        let date = NaiveDate::parse_from_str(date, &self.format.date).map_err(|_| invalid())?;
+        Local
+            .from_local_datetime(&date.and_time(self.due_time()))
+            .earliest()
+            .ok_or_else(invalid)
+    }
+
+    /// 设置项的当前值，未知的设置项返回 `None`；没有设置数据文件路径时返回空字符串
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "file" => self.file.clone().unwrap_or_default(),
+            "defaults.priority" => self.defaults.priority.clone(),
+            "defaults.sort" => self.defaults.sort.clone().unwrap_or_default(),
+            "defaults.due_time" => self.defaults.due_time.clone(),
+            "defaults.export_format" => self.defaults.export_format.clone(),
+            "defaults.collate" => self.defaults.collate.clone(),
+            "format.date" => self.format.date.clone(),
+            "format.datetime" => self.format.datetime.clone(),
+            "colors.mode" => self.colors.mode.clone(),
+            "colors.high" => self.colors.high.clone(),
+            "colors.medium" => self.colors.medium.clone(),
+            "colors.low" => self.colors.low.clone(),
+            "colors.overdue" => self.colors.overdue.clone(),
+            "colors.highlight" => self.colors.highlight.clone(),
+            "list.columns" => self.list.columns.join(","),
+            "list.style" => self.list.style.clone(),
+            "list.table_columns" => self.list.table_columns.join(","),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// 编辑配置文件并写回，保留原有的注释和格式；修改后的配置无效时不会写入
+    pub fn update(path: &Path, edit: impl FnOnce(&mut toml_edit::DocumentMut)) -> Result<Self, ConfigError> {
+        let contents = read(path)?.unwrap_or_default();
+        let mut document: toml_edit::DocumentMut = contents
+            .parse()
+            .map_err(|e| ConfigError::Syntax(path.to_path_buf(), e))?;
+        // 只有注释的文件，注释保存在文档末尾，需要移到新加的内容之前
+        let header = if document.as_table().is_empty() {
+            let header = document.trailing().as_str().unwrap_or_default().to_string();
+            document.set_trailing("");
+            header
+        } else {
+            String::new()
+        };
+        edit(&mut document);
+        let contents = header + &document.to_string();
+        let config = Config::parse(path, &contents)?;
+        let write = || {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, &contents)
+        };
+        write().map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        Ok(config)
+    }
+}
+
+/// 新建和列出事项时的默认值
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    pub priority: String,
+    pub sort: Option<String>,
+    pub due_time: String,
+    pub export_format: String,
+    pub collate: String,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Defaults {
+            priority: "medium".to_string(),
+            sort: None,
+            due_time: "23:59:59".to_string(),
+            export_format: "json".to_string(),
+            collate: "unicode".to_string(),
+        }
+    }
+}
+
+/// 日期和时间的格式，使用 strftime 的写法
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Formats {
+    /// 输入截止日期的格式
+    pub date: String,
+    /// 显示创建、完成和截止时间的格式
+    pub datetime: String,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        Formats {
+            date: "%Y-%m-%d".to_string(),
+            datetime: "%Y-%m-%d %H:%M:%S".to_string(),
+        }
+    }
+}
+
+/// 终端输出的颜色，颜色的写法与标签相同
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    /// `auto` 只在输出到终端且没有设置 `NO_COLOR` 时使用颜色
+    pub mode: String,
+    pub high: String,
+    pub medium: String,
+    pub low: String,
+    pub overdue: String,
+    pub highlight: String,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            mode: "auto".to_string(),
+            high: "red".to_string(),
+            medium: "yellow".to_string(),
+            low: "green".to_string(),
+            overdue: "red".to_string(),
+            highlight: "cyan".to_string(),
+        }
+    }
+}
+
+impl Colors {
+    /// 是否使用颜色输出到标准输出
+    pub fn enabled(&self) -> bool {
+        use std::io::IsTerminal;
+        match self.mode.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+        }
+    }
+
+    /// 用指定的颜色显示文本，不使用颜色时原样返回
+    pub fn paint(&self, color: &str, text: &str) -> String {
+        if self.enabled() {
+            tags::paint(color, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// 优先级对应的颜色
+    pub fn priority(&self, priority: Priority) -> &str {
+        match priority {
+            Priority::High => &self.high,
+            Priority::Medium => &self.medium,
+            Priority::Low => &self.low,
+        }
+    }
+}
+
+/// `list` 的布局
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListLayout {
+    /// 标题下依次显示的内容，取自 [`LIST_COLUMNS`]
+    pub columns: Vec<String>,
+    /// 显示方式，取自 [`LIST_STYLES`]
+    pub style: String,
+    /// 表格的列，取自 [`TABLE_COLUMNS`]
+    pub table_columns: Vec<String>,
+}
+
+impl Default for ListLayout {
+    fn default() -> Self {
+        ListLayout {
+            columns: LIST_COLUMNS.iter().map(|c| c.to_string()).collect(),
+            style: "lines".to_string(),
+            table_columns: ["id", "status", "priority", "title", "due", "tags", "urgency"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 检查列的名称都在 `available` 中且没有重复
+pub fn validate_columns(columns: &[String], available: &[&str]) -> Result<(), String> {
+    for (i, column) in columns.iter().enumerate() {
+        if !available.contains(&column.as_str()) {
+            return Err(format!("未知的内容: {}（可用内容: {}）", column, available.join(", ")));
+        }
+        if columns[..i].contains(column) {
+            return Err(format!("重复的内容: {}", column));
+        }
+    }
+    Ok(())
+}
+
+/// 在配置文件中写入设置项，`key` 应已经过 [`check_key`] 检查
+///
+/// `list.columns` 和 `list.table_columns` 的值用逗号分隔，值是否有效由 [`Config::update`] 校验。
+pub fn set_value(document: &mut toml_edit::DocumentMut, key: &str, value: &str) {
+    let item = if key == "list.columns" || key == "list.table_columns" {
+        let columns = value.split(',').map(str::trim).filter(|c| !c.is_empty());
+        toml_edit::value(toml_edit::Array::from_iter(columns))
+    } else {
+        toml_edit::value(value)
+    };
+    match key.split_once('.') {
+        Some((section, name)) => table_mut(document, &[section])[name] = item,
+        None => document[key] = item,
+    }
+}
+
+/// 从配置文件中删除设置项，恢复为默认值；删除后为空的表也一并删除
+pub fn unset_value(document: &mut toml_edit::DocumentMut, key: &str) {
+    match key.split_once('.') {
+        Some((section, name)) => {
+            if let Some(table) = document.get_mut(section).and_then(toml_edit::Item::as_table_like_mut) {
+                table.remove(name);
+                if table.is_empty() {
+                    document.remove(section);
+                }
+            }
+        }
+        None => {
+            document.remove(key);
+        }
+    }
+}
+
+/// 检查设置项是否在 [`SETTINGS`] 中
+pub fn check_key(key: &str) -> Result<(), String> {
+    if SETTINGS.iter().any(|(k, _)| *k == key) {
+        Ok(())
+    } else {
+        let keys: Vec<&str> = SETTINGS.iter().map(|(k, _)| *k).collect();
+        Err(format!("未知的设置项: {}（可用设置项: {}）", key, keys.join(", ")))
+    }
+}
+
+/// 解析 `HH:MM` 或 `HH:MM:SS`
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()
+}
+
+fn validate_time_format(format: &str) -> Result<(), String> {
+    if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        Err(format!("无效的格式: {:?}", format))
+    } else {
+        Ok(())
+    }
+}
+
+/// 取得 `path` 对应的表，不存在时创建，如 `["views", "morning"]` 对应 `[views.morning]`
+///
+/// 中间的表是隐式的，不会单独输出 `[views]` 这样的空表头。
+pub fn table_mut<'a>(document: &'a mut toml_edit::DocumentMut, path: &[&str]) -> &'a mut toml_edit::Table {
+    let mut table = document.as_table_mut();
+    for (i, key) in path.iter().enumerate() {
+        let item = table.entry(key).or_insert_with(|| {
+            let mut new = toml_edit::Table::new();
+            new.set_implicit(i + 1 < path.len());
+            toml_edit::Item::Table(new)
+        });
+        if !item.is_table() {
+            // 内联表转换为普通的表
+            *item = match std::mem::take(item).into_table() {
+                Ok(existing) => toml_edit::Item::Table(existing),
+                Err(_) => toml_edit::Item::Table(toml_edit::Table::new()),
+            };
+        }
+        table = item.as_table_mut().expect("刚刚转换为表");
+    }
+    table
+}
+
+/// 读取配置文件，不存在时返回 `None`
+fn read(path: &Path) -> Result<Option<String>, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(ConfigError::Io(path.to_path_buf(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let dir = std::env::temp_dir().join(format!("todo_config_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        assert!(Config::load(&path).unwrap().fields.is_empty());
+
+        fs::write(&path, "[fields.points]\ntype = \"number\"\n\n[fields.size]\ntype = \"enum\"\nvalues = [\"S\", \"M\"]\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.fields.len(), 2);
+
+        fs::write(&path, "[fields.size]\ntype = \"enum\"\n").unwrap();
+        let error = Config::load(&path).unwrap_err().to_string();
+        assert!(error.contains("fields.size"), "{}", error);
+
+        fs::write(&path, "[fields.points]\ntype = \"integer\"\n").unwrap();
+        assert!(matches!(Config::load(&path), Err(ConfigError::Parse(..))));
+
+        // 编辑时保留注释，修改后无效的配置不会写入
+        fs::write(&path, "# 我的配置\n[fields.points]\ntype = \"number\"\n").unwrap();
+        Config::update(&path, |doc| {
+            table_mut(doc, &["views", "morning"])["args"] = toml_edit::value(toml_edit::Array::from_iter(["--all"]));
+        })
+        .unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# 我的配置\n"));
+        assert!(contents.ends_with("\n[views.morning]\nargs = [\"--all\"]\n"), "{}", contents);
+        assert_eq!(Config::load(&path).unwrap().views["morning"].args, ["--all"]);
+        assert!(Config::update(&path, |doc| doc["context"] = toml_edit::value("work")).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_settings() {
+        let dir = std::env::temp_dir().join(format!("todo_config_settings_{}", std::process::id()));
+        let path = dir.join("config.toml");
+
+        let config = Config::update(&path, |doc| {
+            set_value(doc, "defaults.priority", "high");
+            set_value(doc, "list.columns", "due, tags");
+        })
+        .unwrap();
+        assert_eq!(config.default_priority(), Priority::High);
+        assert_eq!(config.get("list.columns").as_deref(), Some("due,tags"));
+        assert_eq!(config.get("defaults.due_time").as_deref(), Some("23:59:59"));
+        assert!(config.get("fields").is_none());
+
+        let error = Config::update(&path, |doc| set_value(doc, "list.columns", "due,title")).unwrap_err();
+        assert!(matches!(&error, ConfigError::Invalid { key, .. } if key == "list.columns"), "{}", error);
+        let error = Config::update(&path, |doc| set_value(doc, "format.datetime", "%Y-%Q")).unwrap_err();
+        assert!(error.to_string().contains("format.datetime"), "{}", error);
+
+        let config = Config::update(&path, |doc| set_value(doc, "defaults.due_time", "18:00")).unwrap();
+        assert_eq!(config.due_time(), NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+        let config = Config::update(&path, |doc| unset_value(doc, "list.columns")).unwrap();
+        assert_eq!(config.list, ListLayout::default());
+        assert!(!fs::read_to_string(&path).unwrap().contains("[list]"));
+        assert!(check_key("colors.high").is_ok() && check_key("colors").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
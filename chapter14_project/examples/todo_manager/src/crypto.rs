@@ -0,0 +1,237 @@
+//! 数据文件的静态加密
+//!
+//! 加密后的数据文件是一个 JSON 信封：
+//!
+//! ```json
+//! {
+//!   "format": "todo_manager/encrypted",
+//!   "version": 1,
+//!   "key_source": "passphrase",
+//!   "kdf": { "algorithm": "argon2id", "salt": "...", "m_cost": 19456, "t_cost": 2, "p_cost": 1 },
+//!   "cipher": "xchacha20poly1305",
+//!   "nonce": "...",
+//!   "ciphertext": "..."
+//! }
+//! ```
+//!
+//! 密钥由密码或密钥文件的内容经 Argon2id（内存困难的密钥派生函数）派生，
+//! 再用 XChaCha20-Poly1305 进行认证加密。信封中的参数作为附加数据参与认证，
+//! 因此密码错误和任何篡改都会导致解密失败。
+
+use std::fs;
+use std::path::PathBuf;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::StorageError;
+
+const FORMAT: &str = "todo_manager/encrypted";
+const VERSION: u32 = 1;
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER: &str = "xchacha20poly1305";
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// 加密密钥的来源
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// 用户输入的密码
+    Passphrase(String),
+    /// 密钥文件，文件的全部内容作为密钥材料
+    KeyFile(PathBuf),
+}
+
+impl KeySource {
+    fn kind(&self) -> &'static str {
+        match self {
+            KeySource::Passphrase(_) => "passphrase",
+            KeySource::KeyFile(_) => "keyfile",
+        }
+    }
+
+    fn secret(&self) -> Result<Vec<u8>, StorageError> {
+        match self {
+            KeySource::Passphrase(passphrase) => Ok(passphrase.as_bytes().to_vec()),
+            KeySource::KeyFile(path) => {
+                let secret = fs::read(path)?;
+                if secret.is_empty() {
+                    return Err(StorageError::EncryptionError(format!(
+                        "密钥文件为空: {}",
+                        path.display()
+                    )));
+                }
+                Ok(secret)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    key_source: String,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Envelope {
+    /// 参与认证的附加数据，防止信封中的参数被替换
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{}:{}",
+            self.format,
+            self.version,
+            self.key_source,
+            self.kdf.algorithm,
+            self.kdf.m_cost,
+            self.kdf.t_cost,
+            self.kdf.p_cost,
+            self.cipher
+        )
+        .into_bytes()
+    }
+}
+
+/// 检查文件内容是否为加密信封
+pub fn is_encrypted(contents: &str) -> bool {
+    serde_json::from_str::<Envelope>(contents).is_ok_and(|e| e.format == FORMAT)
+}
+
+/// 加密数据，返回 JSON 信封
+pub fn encrypt(plaintext: &[u8], key: &KeySource) -> Result<String, StorageError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let params = Params::default();
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let mut envelope = Envelope {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        key_source: key.kind().to_string(),
+        kdf: KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            salt: BASE64.encode(salt),
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+        },
+        cipher: CIPHER.to_string(),
+        nonce: BASE64.encode(nonce),
+        ciphertext: String::new(),
+    };
+
+    let derived = derive_key(&key.secret()?, &salt, &envelope.kdf)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&derived));
+    let aad = envelope.associated_data();
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: &aad })
+        .map_err(|_| StorageError::EncryptionError("加密数据失败".to_string()))?;
+    envelope.ciphertext = BASE64.encode(ciphertext);
+
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+/// 解密 JSON 信封
+///
+/// 密码错误、密钥文件不匹配或信封被篡改时返回 [`StorageError::DecryptionFailed`]。
+pub fn decrypt(contents: &str, key: &KeySource) -> Result<Vec<u8>, StorageError> {
+    let envelope: Envelope =
+        serde_json::from_str(contents).map_err(|_| StorageError::DecryptionFailed)?;
+    if envelope.format != FORMAT
+        || envelope.version != VERSION
+        || envelope.kdf.algorithm != KDF_ALGORITHM
+        || envelope.cipher != CIPHER
+    {
+        return Err(StorageError::EncryptionError(format!(
+            "不支持的加密格式: {} v{} ({}, {})",
+            envelope.format, envelope.version, envelope.kdf.algorithm, envelope.cipher
+        )));
+    }
+
+    let salt = BASE64
+        .decode(&envelope.kdf.salt)
+        .map_err(|_| StorageError::DecryptionFailed)?;
+    let nonce = BASE64
+        .decode(&envelope.nonce)
+        .ok()
+        .filter(|n| n.len() == XNonce::default().len())
+        .ok_or(StorageError::DecryptionFailed)?;
+    let ciphertext = BASE64
+        .decode(&envelope.ciphertext)
+        .map_err(|_| StorageError::DecryptionFailed)?;
+
+    let derived = derive_key(&key.secret()?, &salt, &envelope.kdf)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&derived));
+    let aad = envelope.associated_data();
+    cipher
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|_| StorageError::DecryptionFailed)
+}
+
+fn derive_key(secret: &[u8], salt: &[u8], kdf: &KdfParams) -> Result<[u8; KEY_LEN], StorageError> {
+    // 参数来自文件，不合法的参数视为被篡改
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(KEY_LEN))
+        .map_err(|_| StorageError::DecryptionFailed)?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|_| StorageError::DecryptionFailed)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(p: &str) -> KeySource {
+        KeySource::Passphrase(p.to_string())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let envelope = encrypt("[\"客户名单\"]".as_bytes(), &passphrase("正确的密码")).unwrap();
+        assert!(is_encrypted(&envelope));
+        assert!(!envelope.contains("客户名单"));
+        assert!(!is_encrypted("[]"));
+
+        let plaintext = decrypt(&envelope, &passphrase("正确的密码")).unwrap();
+        assert_eq!(plaintext, "[\"客户名单\"]".as_bytes());
+    }
+
+    #[test]
+    fn test_wrong_passphrase_and_tampering() {
+        let envelope = encrypt(b"secret", &passphrase("correct")).unwrap();
+        assert!(matches!(
+            decrypt(&envelope, &passphrase("wrong")),
+            Err(StorageError::DecryptionFailed)
+        ));
+
+        let mut parsed: Envelope = serde_json::from_str(&envelope).unwrap();
+        let mut ciphertext = BASE64.decode(&parsed.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        parsed.ciphertext = BASE64.encode(ciphertext);
+        let tampered = serde_json::to_string(&parsed).unwrap();
+        assert!(matches!(
+            decrypt(&tampered, &passphrase("correct")),
+            Err(StorageError::DecryptionFailed)
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! 命令行程序的错误和退出状态
+//!
+//! 所有子命令的错误都汇总为 [`AppError`]，由 `main` 统一输出并以对应的退出状态结束，
+//! 脚本可以根据退出状态区分错误的类别：
+//!
+//! | 退出状态 | 类别         | 说明                                                   |
+//! |----------|--------------|--------------------------------------------------------|
+//! | 0        | —            | 成功                                                   |
+//! | 1        | `io`、`sync` | 读写其他文件、导出、运行插件、同步等出错               |
+//! | 2        | `usage`、`parse` | 命令行参数、日期、提醒时间、导入内容等格式错误     |
+//! | 3        | `invalid_id` | ID 不是数字或没有对应的待办事项                        |
+//! | 4        | `not_found`  | 视图、上下文、标签、注释或插件不存在                   |
+//! | 5        | `storage`    | 读写或解密数据文件出错                                 |
+//! | 6        | `validation`、`config`、`hook` | 配置文件或自定义字段无效，钩子拒绝了操作 |
+//! | 7        | `conflict`   | 操作与已有的数据冲突，如重命名为已被使用的标签         |
+//!
+//! 使用 `--output json` 时错误以一行 JSON 对象输出到标准错误，如
+//! `{"error":"invalid_id","message":"无效的ID: abc","exit_code":3}`，
+//! 字段与 HTTP 接口的错误响应一致。
+
+use std::io;
+use std::path::Path;
+
+use serde_json::json;
+use thiserror::Error;
+
+use crate::config::ConfigError;
+use crate::export::ExportError;
+use crate::hooks::HookError;
+use crate::import::ImportError;
+use crate::storage::StorageError;
+use crate::sync::caldav::SyncError;
+use crate::tags::RetagError;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("{0}")]
+    Usage(#[from] clap::Error),
+    #[error("{0}")]
+    Parse(String),
+    #[error("无效的ID: {0}")]
+    InvalidId(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Validation(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{context}: {source}")]
+    Storage {
+        context: String,
+        #[source]
+        source: StorageError,
+    },
+    /// 配置错误中包含 TOML 的解析错误，装箱以免 `AppError` 过大
+    #[error(transparent)]
+    Config(Box<ConfigError>),
+    #[error(transparent)]
+    Hook(#[from] HookError),
+    #[error(transparent)]
+    Import(#[from] ImportError),
+    #[error("导出待办事项时出错: {0}")]
+    Export(#[from] ExportError),
+    #[error("同步时出错: {0}")]
+    Sync(#[from] SyncError),
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl AppError {
+    /// 数据文件的错误，用作 `map_err` 的参数；没有对应事项的 ID 归为 [`AppError::InvalidId`]
+    pub fn storage(context: impl Into<String>) -> impl FnOnce(StorageError) -> AppError {
+        move |source| match source {
+            StorageError::InvalidId(id) => AppError::InvalidId(id.to_string()),
+            source => AppError::Storage {
+                context: context.into(),
+                source,
+            },
+        }
+    }
+
+    /// 其他文件或进程的 IO 错误，用作 `map_err` 的参数
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> AppError {
+        move |source| AppError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// 读写指定文件时的 IO 错误
+    pub fn file(action: &str, path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> AppError {
+        Self::io(format!("{} {} 时出错", action, path.as_ref().display()))
+    }
+
+    /// 错误的类别，用于 JSON 输出
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Usage(_) => "usage",
+            AppError::Parse(_) | AppError::Import(_) => "parse",
+            AppError::InvalidId(_) => "invalid_id",
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) => "validation",
+            AppError::Conflict(_) => "conflict",
+            AppError::Storage { .. } | AppError::Sync(SyncError::Storage(_)) => "storage",
+            AppError::Config(_) => "config",
+            AppError::Hook(_) => "hook",
+            AppError::Sync(_) => "sync",
+            AppError::Io { .. } | AppError::Export(_) => "io",
+        }
+    }
+
+    /// 进程的退出状态，见模块文档中的表格
+    pub fn exit_code(&self) -> i32 {
+        match self.kind() {
+            "usage" | "parse" => 2,
+            "invalid_id" => 3,
+            "not_found" => 4,
+            "storage" => 5,
+            "validation" | "config" | "hook" => 6,
+            "conflict" => 7,
+            _ => 1,
+        }
+    }
+
+    /// `--output json` 时输出的错误对象
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "error": self.kind(),
+            "message": self.to_string().trim_end(),
+            "exit_code": self.exit_code(),
+        })
+    }
+}
+
+impl From<RetagError> for AppError {
+    fn from(e: RetagError) -> Self {
+        match e {
+            RetagError::Empty => AppError::Validation(e.to_string()),
+            RetagError::NotInUse(_) => AppError::NotFound(e.to_string()),
+            RetagError::Conflict(_) => AppError::Conflict(e.to_string()),
+        }
+    }
+}
+
+impl From<ConfigError> for AppError {
+    fn from(e: ConfigError) -> Self {
+        AppError::Config(Box::new(e))
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(e: StorageError) -> Self {
+        AppError::storage("读写数据文件时出错")(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(AppError::from(StorageError::InvalidId(9)).exit_code(), 3);
+        assert_eq!(AppError::from(StorageError::KeyRequired).exit_code(), 5);
+        assert_eq!(AppError::from(SyncError::Storage(StorageError::FileNotFound)).kind(), "storage");
+        assert_eq!(AppError::from(SyncError::Http("超时".to_string())).exit_code(), 1);
+        assert_eq!(AppError::Conflict("标签已被使用".to_string()).exit_code(), 7);
+
+        let error = AppError::storage("保存待办事项时出错")(StorageError::FileNotFound);
+        assert_eq!(
+            error.to_json(),
+            json!({ "error": "storage", "message": "保存待办事项时出错: 找不到文件", "exit_code": 5 })
+        );
+    }
+}
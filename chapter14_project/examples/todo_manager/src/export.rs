@@ -0,0 +1,256 @@
+//! 将待办事项导出为各种格式
+//!
+//! 每种格式对应一个 [`Exporter`] 实现，通过 [`exporter`] 按名称创建。
+
+mod csv;
+mod html;
+mod interchange;
+mod json;
+
+use std::io::{self, Write};
+
+use thiserror::Error;
+
+use crate::format::GroupBy;
+use crate::todo::Todo;
+
+pub use self::csv::CsvExporter;
+pub use self::html::HtmlExporter;
+pub use self::interchange::{IcsExporter, MarkdownExporter, OrgExporter, TaskwarriorExporter, TodoTxtExporter};
+pub use self::json::JsonExporter;
+
+/// 支持的导出格式名称
+pub const FORMATS: &[&str] = &["json", "csv", "html", "todotxt", "ics", "markdown", "org", "taskwarrior"];
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("IO错误: {0}")]
+    IoError(#[from] io::Error),
+    #[error("序列化错误: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+/// 一条待导出的待办事项
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    /// 待办事项在存储中的编号，与 `done`、`edit` 等命令使用的 ID 一致
+    pub id: usize,
+    pub todo: &'a Todo,
+}
+
+/// 导出器：将一组待办事项写入输出
+pub trait Exporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError>;
+}
+
+/// 可以选择导出的字段（用于 CSV、HTML 和 JSON）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Id,
+    Uid,
+    Title,
+    Description,
+    Priority,
+    Status,
+    Created,
+    Completed,
+    Due,
+    Tags,
+    Annotations,
+    /// 自定义字段，在 `--fields` 中写作 `field.<名称>`
+    Custom(String),
+}
+
+impl Field {
+    pub const ALL: &'static [Field] = &[
+        Field::Id,
+        Field::Uid,
+        Field::Title,
+        Field::Description,
+        Field::Priority,
+        Field::Status,
+        Field::Created,
+        Field::Completed,
+        Field::Due,
+        Field::Tags,
+        Field::Annotations,
+    ];
+
+    /// 未指定 `--fields` 时 CSV 和 HTML 导出的列
+    pub const DEFAULT: &'static [Field] = &[
+        Field::Id,
+        Field::Title,
+        Field::Description,
+        Field::Priority,
+        Field::Status,
+        Field::Created,
+        Field::Due,
+        Field::Tags,
+    ];
+
+    /// 未指定 `--fields` 时 CSV 和 HTML 导出的列：默认列加上导出的事项中出现的注释和全部自定义字段
+    pub fn defaults(records: &[Record]) -> Vec<Field> {
+        let mut custom: Vec<&String> = records.iter().flat_map(|r| r.todo.fields().keys()).collect();
+        custom.sort();
+        custom.dedup();
+        let mut fields = Field::DEFAULT.to_vec();
+        if records.iter().any(|r| !r.todo.annotations().is_empty()) {
+            fields.push(Field::Annotations);
+        }
+        fields.extend(custom.into_iter().map(|name| Field::Custom(name.clone())));
+        fields
+    }
+
+    /// 字段名，用于 `--fields` 和 JSON 的键
+    pub fn name(&self) -> String {
+        let name = match self {
+            Field::Id => "id",
+            Field::Uid => "uid",
+            Field::Title => "title",
+            Field::Description => "description",
+            Field::Priority => "priority",
+            Field::Status => "status",
+            Field::Created => "created",
+            Field::Completed => "completed",
+            Field::Due => "due",
+            Field::Tags => "tags",
+            Field::Annotations => "annotations",
+            Field::Custom(name) => return format!("field.{}", name),
+        };
+        name.to_string()
+    }
+
+    /// 表头，用于 CSV 和 HTML
+    pub fn header(&self) -> &str {
+        match self {
+            Field::Id => "ID",
+            Field::Uid => "标识符",
+            Field::Title => "标题",
+            Field::Description => "描述",
+            Field::Priority => "优先级",
+            Field::Status => "状态",
+            Field::Created => "创建时间",
+            Field::Completed => "完成时间",
+            Field::Due => "截止日期",
+            Field::Tags => "标签",
+            Field::Annotations => "注释",
+            Field::Custom(name) => name,
+        }
+    }
+
+    /// 解析逗号分隔的字段列表，保持给出的顺序
+    pub fn parse_list(list: &str) -> Result<Vec<Field>, String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if let Some(custom) = name.strip_prefix("field.").filter(|n| !n.is_empty()) {
+                    return Ok(Field::Custom(custom.to_string()));
+                }
+                Field::ALL.iter().find(|f| f.name() == name).cloned().ok_or_else(|| {
+                    let names: Vec<String> = Field::ALL.iter().map(Field::name).collect();
+                    format!("未知的字段: {}（可用字段: {}, field.<自定义字段>）", name, names.join(", "))
+                })
+            })
+            .collect()
+    }
+
+    /// 字段的文本形式，用于 CSV 和 HTML
+    pub fn text(&self, record: &Record) -> String {
+        let todo = record.todo;
+        match self {
+            Field::Id => record.id.to_string(),
+            Field::Uid => todo.uid().unwrap_or_default().to_string(),
+            Field::Title => todo.title().to_string(),
+            Field::Description => todo.description().cloned().unwrap_or_default(),
+            Field::Priority => todo.priority().to_string(),
+            Field::Status => if todo.is_completed() { "已完成" } else { "未完成" }.to_string(),
+            Field::Created => todo.created_at().format("%Y-%m-%d %H:%M:%S").to_string(),
+            Field::Completed => todo
+                .completed_at()
+                .map_or_else(String::new, |d| d.format("%Y-%m-%d %H:%M:%S").to_string()),
+            Field::Due => todo
+                .due_date()
+                .map_or_else(String::new, |d| d.format("%Y-%m-%d").to_string()),
+            Field::Tags => todo.tags().join(","),
+            // 每条注释一行，以时间开头
+            Field::Annotations => todo
+                .annotations()
+                .iter()
+                .map(|a| format!("[{}] {}", a.entry.format("%Y-%m-%d %H:%M"), a.text))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Field::Custom(name) => todo.field(name).unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// 创建导出器时的选项
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// 导出的字段及顺序，`None` 表示使用各格式的默认字段
+    pub fields: Option<Vec<Field>>,
+    /// Markdown/Org 清单的分组方式
+    pub group_by: GroupBy,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            fields: None,
+            group_by: GroupBy::Priority,
+        }
+    }
+}
+
+/// 格式是否支持 `--fields` 选择字段
+pub fn supports_fields(format: &str) -> bool {
+    matches!(format, "json" | "csv" | "html")
+}
+
+/// 根据格式名称创建导出器，不支持的格式返回 `None`
+pub fn exporter(format: &str, options: ExportOptions) -> Option<Box<dyn Exporter>> {
+    let fields = options.fields;
+    let exporter: Box<dyn Exporter> = match format {
+        "json" => Box::new(JsonExporter { fields }),
+        "csv" => Box::new(CsvExporter { fields }),
+        "html" => Box::new(HtmlExporter { fields }),
+        "todotxt" => Box::new(TodoTxtExporter),
+        "ics" => Box::new(IcsExporter),
+        "markdown" => Box::new(MarkdownExporter {
+            group_by: options.group_by,
+        }),
+        "org" => Box::new(OrgExporter {
+            group_by: options.group_by,
+        }),
+        "taskwarrior" => Box::new(TaskwarriorExporter),
+        _ => return None,
+    };
+    Some(exporter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_field_list() {
+        assert_eq!(
+            Field::parse_list("title, due,id").unwrap(),
+            vec![Field::Title, Field::Due, Field::Id]
+        );
+        assert!(Field::parse_list("title,unknown").unwrap_err().contains("unknown"));
+        assert_eq!(
+            Field::parse_list("field.points").unwrap(),
+            vec![Field::Custom("points".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_every_format_has_exporter() {
+        for format in FORMATS {
+            assert!(exporter(format, ExportOptions::default()).is_some(), "{}", format);
+        }
+        assert!(exporter("pdf", ExportOptions::default()).is_none());
+    }
+}
@@ -0,0 +1,34 @@
+use std::io::Write;
+
+use super::{ExportError, Exporter, Field, Record};
+
+/// CSV 导出，按 RFC 4180 对包含逗号、引号或换行的值加引号
+pub struct CsvExporter {
+    /// 导出的列，`None` 时使用 [`Field::defaults`]
+    pub fields: Option<Vec<Field>>,
+}
+
+impl Exporter for CsvExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        let fields = self.fields.clone().unwrap_or_else(|| Field::defaults(records));
+        let header: Vec<String> = fields.iter().map(|field| escape(field.header())).collect();
+        writeln!(out, "{}", header.join(","))?;
+
+        for record in records {
+            let row: Vec<String> = fields
+                .iter()
+                .map(|field| escape(&field.text(record)))
+                .collect();
+            writeln!(out, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,103 @@
+use std::io::Write;
+
+use super::{ExportError, Exporter, Field, Record};
+use crate::todo::Priority;
+
+/// HTML 表格导出
+pub struct HtmlExporter {
+    /// 导出的列，`None` 时使用 [`Field::defaults`]
+    pub fields: Option<Vec<Field>>,
+}
+
+const STYLE: &str = "body { font-family: Arial, sans-serif; margin: 20px; }
+h1 { color: #333; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ddd; padding: 8px; text-align: left; }
+th { background-color: #f2f2f2; }
+.high { color: red; }
+.medium { color: orange; }
+.low { color: green; }
+.completed { text-decoration: line-through; }
+.annotations { margin: 0; padding-left: 1.2em; }
+.annotations time { color: #888; margin-right: 0.5em; }
+.tag { display: inline-block; background-color: #eee; padding: 2px 5px; margin: 2px; border-radius: 3px; }
+";
+
+impl Exporter for HtmlExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        write!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>待办事项列表</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+            STYLE
+        )?;
+        writeln!(out, "<h1>待办事项列表</h1>")?;
+        writeln!(out, "<table>")?;
+        let fields = self.fields.clone().unwrap_or_else(|| Field::defaults(records));
+        let header: String = fields
+            .iter()
+            .map(|field| format!("<th>{}</th>", escape(field.header())))
+            .collect();
+        writeln!(out, "<tr>{}</tr>", header)?;
+
+        for record in records {
+            writeln!(out, "<tr>")?;
+            for field in &fields {
+                writeln!(out, "{}", cell(field, record))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+
+        writeln!(out, "</table>")?;
+        write!(out, "</body>\n</html>")?;
+        Ok(())
+    }
+}
+
+fn cell(field: &Field, record: &Record) -> String {
+    let todo = record.todo;
+    match field {
+        Field::Title => {
+            let class = if todo.is_completed() { "completed" } else { "" };
+            format!("<td class=\"{}\">{}</td>", class, escape(todo.title()))
+        }
+        Field::Priority => {
+            let class = match todo.priority() {
+                Priority::Low => "low",
+                Priority::Medium => "medium",
+                Priority::High => "high",
+            };
+            format!("<td class=\"{}\">{}</td>", class, todo.priority())
+        }
+        Field::Tags => {
+            let tags: Vec<String> = todo
+                .tags()
+                .iter()
+                .map(|tag| format!("<span class=\"tag\">{}</span>", escape(tag)))
+                .collect();
+            format!("<td>{}</td>", tags.join(" "))
+        }
+        Field::Annotations if !todo.annotations().is_empty() => {
+            let items: String = todo
+                .annotations()
+                .iter()
+                .map(|a| {
+                    format!(
+                        "<li><time datetime=\"{}\">{}</time>{}</li>",
+                        a.entry.to_rfc3339(),
+                        a.entry.format("%Y-%m-%d %H:%M"),
+                        escape(&a.text)
+                    )
+                })
+                .collect();
+            format!("<td><ul class=\"annotations\">{}</ul></td>", items)
+        }
+        _ => format!("<td>{}</td>", escape(&field.text(record))),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,63 @@
+//! 与其他待办事项工具交换数据的格式，具体转换由 [`crate::format`] 完成
+
+use std::io::Write;
+
+use super::{ExportError, Exporter, Record};
+use crate::format::{ical, markdown, org, taskwarrior, todotxt, GroupBy};
+
+/// todo.txt 导出
+pub struct TodoTxtExporter;
+
+impl Exporter for TodoTxtExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        out.write_all(todotxt::to_string(records.iter().map(|r| r.todo)).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// iCalendar VTODO 导出
+pub struct IcsExporter;
+
+impl Exporter for IcsExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        out.write_all(ical::to_string(records.iter().map(|r| r.todo)).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Markdown 任务清单导出
+pub struct MarkdownExporter {
+    pub group_by: GroupBy,
+}
+
+impl Exporter for MarkdownExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        let contents = markdown::to_string(records.iter().map(|r| r.todo), self.group_by);
+        out.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Org-mode 清单导出
+pub struct OrgExporter {
+    pub group_by: GroupBy,
+}
+
+impl Exporter for OrgExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        let contents = org::to_string(records.iter().map(|r| r.todo), self.group_by);
+        out.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Taskwarrior JSON 导出，可以用 `task import` 导入
+pub struct TaskwarriorExporter;
+
+impl Exporter for TaskwarriorExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        let json = taskwarrior::to_string(records.iter().map(|r| r.todo))?;
+        writeln!(out, "{}", json)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::io::Write;
+
+use serde_json::{json, Map, Value};
+
+use super::{ExportError, Exporter, Field, Record};
+use crate::todo::Todo;
+
+/// JSON 导出：未选择字段时输出完整的待办事项，与数据文件格式相同
+pub struct JsonExporter {
+    pub fields: Option<Vec<Field>>,
+}
+
+impl Exporter for JsonExporter {
+    fn export(&self, records: &[Record], out: &mut dyn Write) -> Result<(), ExportError> {
+        match &self.fields {
+            None => {
+                let todos: Vec<&Todo> = records.iter().map(|r| r.todo).collect();
+                serde_json::to_writer_pretty(&mut *out, &todos)?;
+            }
+            Some(fields) => {
+                let objects: Vec<Value> = records
+                    .iter()
+                    .map(|record| {
+                        let object: Map<String, Value> = fields
+                            .iter()
+                            .map(|field| (field.name(), json_value(field, record)))
+                            .collect();
+                        Value::Object(object)
+                    })
+                    .collect();
+                serde_json::to_writer_pretty(&mut *out, &objects)?;
+            }
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+fn json_value(field: &Field, record: &Record) -> Value {
+    let todo = record.todo;
+    match field {
+        Field::Id => json!(record.id),
+        Field::Uid => json!(todo.uid()),
+        Field::Title => json!(todo.title()),
+        Field::Description => json!(todo.description()),
+        Field::Priority => json!(todo.priority()),
+        Field::Status => json!(if todo.is_completed() { "completed" } else { "pending" }),
+        Field::Created => json!(todo.created_at()),
+        Field::Completed => json!(todo.completed_at()),
+        Field::Due => json!(todo.due_date()),
+        Field::Tags => json!(todo.tags()),
+        Field::Annotations => json!(todo.annotations()),
+        Field::Custom(name) => json!(todo.field(name)),
+    }
+}
@@ -0,0 +1,318 @@
+//! 自定义字段：在配置文件中声明名称和类型，通过 `--set 名称=值` 设置
+//!
+//! ```toml
+//! [fields.points]
+//! type = "number"
+//!
+//! [fields.size]
+//! type = "enum"
+//! values = ["S", "M", "L"]
+//! ```
+//!
+//! 字段值以规范化的文本保存（数字去掉多余的零，日期为 `YYYY-MM-DD`，时长如 `1d2h30m`），
+//! 比较和排序时再按声明的类型解析。
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::todo::Todo;
+
+/// 自定义字段的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    String,
+    Number,
+    Date,
+    Duration,
+    Enum,
+}
+
+/// 配置文件中的字段声明
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldDecl {
+    #[serde(rename = "type")]
+    pub kind: FieldKind,
+    /// `enum` 类型的可选值，排序时按声明的顺序
+    #[serde(default)]
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 按字段名索引的声明
+pub type FieldDecls = BTreeMap<String, FieldDecl>;
+
+impl FieldDecl {
+    /// 检查声明本身是否有效
+    pub fn validate(&self) -> Result<(), String> {
+        match self.kind {
+            FieldKind::Enum if self.values.is_empty() => Err("enum 类型需要在 values 中列出可选值".to_string()),
+            FieldKind::Enum => Ok(()),
+            _ if !self.values.is_empty() => Err("只有 enum 类型可以设置 values".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 按声明的类型校验输入，返回规范化的值
+    pub fn parse_value(&self, name: &str, raw: &str) -> Result<String, String> {
+        let raw = raw.trim();
+        let invalid = |expected: &str| format!("字段 {} 的值无效: {}，应为{}", name, raw, expected);
+        match self.kind {
+            FieldKind::String => Ok(raw.to_string()),
+            FieldKind::Number => raw
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(|n| n.to_string())
+                .ok_or_else(|| invalid("数字")),
+            FieldKind::Date => NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .map_err(|_| invalid(" YYYY-MM-DD 格式的日期")),
+            FieldKind::Duration => parse_duration(raw)
+                .map(format_duration)
+                .ok_or_else(|| invalid("时长，如 30m、2h、1d4h")),
+            FieldKind::Enum => self
+                .values
+                .iter()
+                .find(|v| v.as_str() == raw)
+                .cloned()
+                .ok_or_else(|| invalid(&format!("以下值之一: {}", self.values.join(", ")))),
+        }
+    }
+
+    /// 按声明的类型比较两个值，无法解析的值排在后面
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self.kind {
+            FieldKind::Number => compare_parsed(a, b, |s| s.parse::<f64>().ok(), f64::total_cmp),
+            FieldKind::Duration => compare_parsed(a, b, parse_duration, |x, y| x.cmp(y)),
+            FieldKind::Enum => {
+                let position = |s: &str| self.values.iter().position(|v| v == s);
+                compare_parsed(a, b, position, |x, y| x.cmp(y))
+            }
+            FieldKind::String | FieldKind::Date => a.cmp(b),
+        }
+    }
+}
+
+fn compare_parsed<T>(a: &str, b: &str, parse: impl Fn(&str) -> Option<T>, cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    match (parse(a), parse(b)) {
+        (Some(x), Some(y)) => cmp(&x, &y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// 比较两个字段值：有声明时按声明的类型，否则都是数字时按数值，其余按文本
+pub fn compare_values(decl: Option<&FieldDecl>, a: &str, b: &str) -> Ordering {
+    match decl {
+        Some(decl) => decl.compare(a, b),
+        None => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.total_cmp(&y),
+            _ => a.cmp(b),
+        },
+    }
+}
+
+/// 字段名不能为空，也不能包含空白和 `= : , . < > !`，以免与命令行和导出格式的语法冲突
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().any(|c| c.is_whitespace() || "=:,.<>!".contains(c)) {
+        Err(format!("无效的字段名: {:?}", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// 解析时长，如 `90m`、`1d4h`，返回分钟数
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut rest = s;
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let value: i64 = rest[..digits].parse().ok()?;
+        let size = match rest[digits..].chars().next()? {
+            'w' => 7 * 24 * 60,
+            'd' => 24 * 60,
+            'h' => 60,
+            'm' => 1,
+            _ => return None,
+        };
+        total = total.checked_add(value.checked_mul(size)?)?;
+        rest = &rest[digits + 1..];
+    }
+    Some(total)
+}
+
+/// 将分钟数格式化为时长，如 `1d2h30m`
+pub fn format_duration(minutes: i64) -> String {
+    if minutes == 0 {
+        return "0m".to_string();
+    }
+    let mut text = String::new();
+    let mut rest = minutes;
+    for (size, unit) in [(24 * 60, 'd'), (60, 'h'), (1, 'm')] {
+        if rest >= size {
+            text.push_str(&format!("{}{}", rest / size, unit));
+            rest %= size;
+        }
+    }
+    text
+}
+
+/// 解析 `名称=值`，值为空表示清除该字段
+pub fn parse_assignment(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("无效的字段设置: {}，应为 名称=值", s))?;
+    let name = name.trim();
+    validate_name(name)?;
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// 按声明校验并设置待办事项的字段，值为空时清除该字段
+pub fn assign(todo: &mut Todo, decls: &FieldDecls, name: &str, value: &str) -> Result<(), String> {
+    let Some(decl) = decls.get(name) else {
+        return Err(format!("未声明的自定义字段: {}，请先在配置文件的 [fields.{}] 中声明", name, name));
+    };
+    if value.is_empty() {
+        todo.set_field(name, None);
+        return Ok(());
+    }
+    let value = decl.parse_value(name, value)?;
+    todo.set_field(name, Some(value));
+    Ok(())
+}
+
+/// 字段的比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exists,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// 筛选条件，如 `points>=3`、`customer=ACME`、`estimate`（有值即可）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub name: String,
+    op: Op,
+    value: String,
+}
+
+impl std::str::FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 先匹配两个字符的运算符
+        let ops = [("!=", Op::Ne), (">=", Op::Ge), ("<=", Op::Le), ("=", Op::Eq), ("<", Op::Lt), (">", Op::Gt)];
+        let found = ops
+            .iter()
+            .filter_map(|(token, op)| s.find(token).map(|i| (i, *token, *op)))
+            .min_by_key(|(i, token, _)| (*i, std::cmp::Reverse(token.len())));
+        let (name, op, value) = match found {
+            Some((i, token, op)) => (&s[..i], op, &s[i + token.len()..]),
+            None => (s, Op::Exists, ""),
+        };
+        let name = name.trim();
+        validate_name(name)?;
+        Ok(Condition {
+            name: name.to_string(),
+            op,
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+impl Condition {
+    /// 检查待办事项是否满足条件，没有该字段的事项只满足 `!=`
+    pub fn matches(&self, todo: &Todo, decls: &FieldDecls) -> bool {
+        let Some(actual) = todo.field(&self.name) else {
+            return self.op == Op::Ne;
+        };
+        let decl = decls.get(&self.name);
+        // 按声明规范化条件中的值，例如 `estimate<90m` 与保存的 `1h30m` 比较
+        let expected = decl
+            .and_then(|d| d.parse_value(&self.name, &self.value).ok())
+            .unwrap_or_else(|| self.value.clone());
+        let ordering = compare_values(decl, actual, &expected);
+        match self.op {
+            Op::Exists => true,
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decl(kind: FieldKind, values: &[&str]) -> FieldDecl {
+        FieldDecl {
+            kind,
+            values: values.iter().map(|v| v.to_string()).collect(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(decl(FieldKind::Number, &[]).parse_value("points", "3.0").unwrap(), "3");
+        assert!(decl(FieldKind::Number, &[]).parse_value("points", "三").is_err());
+        assert_eq!(decl(FieldKind::Duration, &[]).parse_value("estimate", "90m").unwrap(), "1h30m");
+        assert!(decl(FieldKind::Date, &[]).parse_value("review", "2026-13-01").is_err());
+        let size = decl(FieldKind::Enum, &["S", "M", "L"]);
+        assert_eq!(size.parse_value("size", "M").unwrap(), "M");
+        assert!(size.parse_value("size", "XL").unwrap_err().contains("S, M, L"));
+        assert!(decl(FieldKind::Enum, &[]).validate().is_err());
+    }
+
+    #[test]
+    fn test_condition() {
+        let mut decls = FieldDecls::new();
+        decls.insert("points".to_string(), decl(FieldKind::Number, &[]));
+        decls.insert("size".to_string(), decl(FieldKind::Enum, &["S", "M", "L"]));
+        let mut todo = Todo::new("写周报".to_string(), None);
+        todo.set_field("points", Some("10".to_string()));
+        todo.set_field("size", Some("M".to_string()));
+
+        let check = |s: &str| s.parse::<Condition>().unwrap().matches(&todo, &decls);
+        assert!(check("points>=3"));
+        assert!(!check("points<9"));
+        assert!(check("size>S"));
+        assert!(check("size!=L"));
+        assert!(check("points"));
+        assert!(!check("customer"));
+        assert!(check("customer!=ACME"));
+        assert!("a b=1".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn test_assign() {
+        let mut decls = FieldDecls::new();
+        decls.insert("estimate".to_string(), decl(FieldKind::Duration, &[]));
+        let mut todo = Todo::new("写周报".to_string(), None);
+        assign(&mut todo, &decls, "estimate", "90m").unwrap();
+        assert_eq!(todo.fields()["estimate"], "1h30m");
+        assert!(assign(&mut todo, &decls, "estimate", "很久").is_err());
+        assert!(assign(&mut todo, &decls, "points", "3").unwrap_err().contains("[fields.points]"));
+        assign(&mut todo, &decls, "estimate", "").unwrap();
+        assert!(todo.fields().is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! 待办事项的筛选与排序，`list` 和 `export` 共用
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Local};
+
+use crate::clock::Clock;
+use crate::collate::{Collation, Collator};
+use crate::fields::{self, Condition, FieldDecls};
+use crate::tags;
+use crate::todo::{Priority, Todo};
+use crate::urgency::UrgencyWeights;
+
+/// 排序方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    /// 优先级从高到低
+    Priority,
+    /// 截止日期从早到晚，没有截止日期的按创建时间排在后面
+    Date,
+    /// 按标题排序，使用筛选条件中的排序规则
+    Title,
+    /// 紧急度从高到低
+    Urgency,
+    /// 按自定义字段从小到大，没有该字段的排在后面；写作 `field.<名称>`
+    Field(String),
+}
+
+impl SortKey {
+    /// 比较两个待办事项，紧急度和自定义字段的比较方式由筛选条件中的权重和声明决定，标题由 `collator` 比较
+    pub fn compare(&self, a: &Todo, b: &Todo, filter: &Filter, collator: &Collator, now: &DateTime<Local>) -> Ordering {
+        let weights = &filter.weights;
+        match self {
+            SortKey::Priority => b.priority().cmp(&a.priority()),
+            SortKey::Date => match (a.due_date(), b.due_date()) {
+                (Some(a_date), Some(b_date)) => a_date.cmp(b_date),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => a.created_at().cmp(b.created_at()),
+            },
+            SortKey::Title => collator.compare(a.title(), b.title()),
+            SortKey::Urgency => weights.score(b, now).total().total_cmp(&weights.score(a, now).total()),
+            SortKey::Field(name) => match (a.field(name), b.field(name)) {
+                (Some(x), Some(y)) => fields::compare_values(filter.declarations.get(name), x, y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "priority" => Ok(SortKey::Priority),
+            "date" => Ok(SortKey::Date),
+            "title" => Ok(SortKey::Title),
+            "urgency" => Ok(SortKey::Urgency),
+            _ => match s.strip_prefix("field.") {
+                Some(name) if fields::validate_name(name).is_ok() => Ok(SortKey::Field(name.to_string())),
+                _ => Err(format!("无效的排序方式: {}", s)),
+            },
+        }
+    }
+}
+
+/// 待办事项的筛选条件和排序方式
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// 是否包含已完成的待办事项
+    pub include_completed: bool,
+    /// 只保留指定优先级
+    pub priority: Option<Priority>,
+    /// 只保留带有指定标签或其下级标签的
+    pub tag: Option<String>,
+    /// 只保留有截止日期的
+    pub with_due_date: bool,
+    /// 只保留已过期且未完成的
+    pub overdue: bool,
+    /// 只保留标题、描述或注释中包含该文本的，不区分大小写
+    pub search: Option<String>,
+    /// 排序方式，`None` 时保持存储中的顺序
+    pub sort: Option<SortKey>,
+    /// 配置文件中的默认排序方式，命令行和上下文都没有指定排序方式时使用
+    pub default_sort: Option<SortKey>,
+    /// 按标题排序时的排序规则
+    pub collation: Collation,
+    /// 按紧急度排序时使用的权重
+    pub weights: UrgencyWeights,
+    /// 自定义字段需要满足的条件
+    pub conditions: Vec<Condition>,
+    /// 自定义字段的声明，决定比较和排序时的类型
+    pub declarations: FieldDecls,
+    /// 当前上下文的筛选条件，事项还必须同时满足它
+    pub context: Option<Box<Filter>>,
+}
+
+impl Filter {
+    /// 检查待办事项是否符合筛选条件
+    pub fn matches(&self, todo: &Todo, now: &DateTime<Local>) -> bool {
+        if !self.include_completed && todo.is_completed() {
+            return false;
+        }
+        if self.priority.is_some_and(|p| todo.priority() != p) {
+            return false;
+        }
+        if let Some(tag) = &self.tag {
+            if !todo.tags().iter().any(|t| tags::is_within(t, tag)) {
+                return false;
+            }
+        }
+        if self.with_due_date && todo.due_date().is_none() {
+            return false;
+        }
+        if !self.conditions.iter().all(|c| c.matches(todo, &self.declarations)) {
+            return false;
+        }
+        if let Some(query) = &self.search {
+            if !contains_text(todo, query) {
+                return false;
+            }
+        }
+        if self.context.as_ref().is_some_and(|context| !context.matches(todo, now)) {
+            return false;
+        }
+        if self.overdue {
+            let overdue = todo.due_date().is_some_and(|due| due < now) && !todo.is_completed();
+            if !overdue {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 筛选并排序，返回（待办事项在存储中的编号，待办事项）；是否过期和紧急度按 `clock` 的当前时间计算
+    pub fn apply<'a>(&self, todos: &'a [Todo], clock: &dyn Clock) -> Vec<(usize, &'a Todo)> {
+        let now = clock.now();
+        let mut selected: Vec<(usize, &Todo)> = todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| self.matches(todo, &now))
+            .collect();
+        // 没有指定排序方式时依次使用上下文和配置文件中的排序方式
+        let sort = self
+            .sort
+            .as_ref()
+            .or_else(|| self.context.as_ref().and_then(|c| c.sort.as_ref()))
+            .or(self.default_sort.as_ref());
+        if let Some(sort) = sort {
+            let collator = self.collation.collator();
+            selected.sort_by(|(_, a), (_, b)| sort.compare(a, b, self, &collator, &now));
+        }
+        selected
+    }
+}
+
+/// 标题、描述或任意一条注释中是否包含 `query`，不区分大小写
+fn contains_text(todo: &Todo, query: &str) -> bool {
+    let query = query.to_lowercase();
+    let found = |text: &str| text.to_lowercase().contains(&query);
+    found(todo.title())
+        || todo.description().is_some_and(|d| found(d))
+        || todo.annotations().iter().any(|a| found(&a.text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FixedClock, SystemClock};
+
+    fn todos() -> Vec<Todo> {
+        let mut a = Todo::new("b 任务".to_string(), None);
+        a.set_priority(Priority::High);
+        a.add_tag("工作".to_string());
+        let mut b = Todo::new("a 任务".to_string(), None);
+        b.mark_as_done();
+        let c = Todo::new("c 任务".to_string(), None);
+        vec![a, b, c]
+    }
+
+    #[test]
+    fn test_apply_keeps_storage_ids() {
+        let todos = todos();
+        let filter = Filter {
+            sort: Some(SortKey::Title),
+            ..Default::default()
+        };
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![0, 2]);
+
+        let filter = Filter {
+            include_completed: true,
+            sort: Some(SortKey::Title),
+            ..Default::default()
+        };
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_apply_uses_clock() {
+        let due = crate::clock::parse_time("2026-10-20 18:00").unwrap();
+        let todos = vec![Todo::with_due_date("写周报".to_string(), None, due)];
+        let filter = Filter {
+            overdue: true,
+            ..Default::default()
+        };
+        let clock = FixedClock::new(due - chrono::Duration::hours(1));
+        assert!(filter.apply(&todos, &clock).is_empty());
+        clock.advance(chrono::Duration::hours(2));
+        assert_eq!(filter.apply(&todos, &clock).len(), 1);
+    }
+
+    #[test]
+    fn test_matches() {
+        let todos = todos();
+        let filter = Filter {
+            priority: Some(Priority::High),
+            tag: Some("工作".to_string()),
+            ..Default::default()
+        };
+        let now = Local::now();
+        assert!(filter.matches(&todos[0], &now));
+        assert!(!filter.matches(&todos[2], &now));
+
+        // 按上级标签筛选时也匹配下级标签
+        let mut child = Todo::new("写接口文档".to_string(), None);
+        child.add_tag("工作/后端".to_string());
+        let filter = Filter {
+            tag: Some("工作".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&child, &now));
+        let filter = Filter {
+            tag: Some("工作/前端".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&child, &now));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut todos = todos();
+        todos[2].add_annotation("已联系 ACME 的张经理".to_string());
+        let filter = Filter {
+            search: Some("acme".to_string()),
+            ..Default::default()
+        };
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn test_sort_by_title_collation() {
+        let todos: Vec<Todo> = ["张三的生日", "阿姨来访", "Zoom 会议", "apple 订单"]
+            .into_iter()
+            .map(|title| Todo::new(title.to_string(), None))
+            .collect();
+        let mut filter = Filter {
+            sort: Some(SortKey::Title),
+            ..Default::default()
+        };
+        let ids = |filter: &Filter| -> Vec<usize> { filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect() };
+        assert_eq!(ids(&filter), vec![3, 2, 0, 1]);
+        filter.collation = Collation::Pinyin;
+        assert_eq!(ids(&filter), vec![1, 0, 3, 2]);
+        filter.collation = Collation::Bytes;
+        assert_eq!(ids(&filter), vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_sort_by_urgency() {
+        let todos = todos();
+        let mut filter = Filter {
+            sort: Some(SortKey::Urgency),
+            ..Default::default()
+        };
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![0, 2]);
+
+        filter.weights.set("tag.工作", -10.0).unwrap();
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![2, 0]);
+    }
+
+    #[test]
+    fn test_custom_fields() {
+        let mut todos = todos();
+        todos[0].set_field("points", Some("10".to_string()));
+        todos[2].set_field("points", Some("9".to_string()));
+        let mut filter = Filter {
+            sort: Some("field.points".parse().unwrap()),
+            ..Default::default()
+        };
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![2, 0]);
+
+        filter.conditions.push("points>9".parse().unwrap());
+        let ids: Vec<usize> = filter.apply(&todos, &SystemClock).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![0]);
+    }
+}
@@ -0,0 +1,180 @@
+//! 待办事项与其他文本格式之间的相互转换
+
+pub mod ical;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+pub mod todotxt;
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::collate::Collation;
+use crate::todo::{Annotation, Priority, Todo};
+
+/// 将日期和时刻组合为本地时间
+pub fn local_datetime(date: NaiveDate, hour: u32, min: u32, sec: u32) -> Option<DateTime<Local>> {
+    let datetime = date.and_hms_opt(hour, min, sec)?;
+    Local.from_local_datetime(&datetime).earliest()
+}
+
+/// 只精确到天的截止日期按当天的最后一秒处理
+pub fn end_of_day(date: NaiveDate) -> Option<DateTime<Local>> {
+    local_datetime(date, 23, 59, 59)
+}
+
+/// iCalendar 和 Taskwarrior 使用的紧凑 UTC 时间格式，如 `20261020T235959Z`
+const BASIC_UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// 将本地时间格式化为紧凑的 UTC 时间
+pub fn format_basic_utc(datetime: &DateTime<Local>) -> String {
+    datetime.with_timezone(&Utc).format(BASIC_UTC_FORMAT).to_string()
+}
+
+/// 解析紧凑的 UTC 时间并转换为本地时间
+pub fn parse_basic_utc(s: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(s, BASIC_UTC_FORMAT).ok()?;
+    Some(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+/// 清单类格式（Markdown、Org）的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// 按优先级从高到低分组
+    Priority,
+    /// 按第一个标签分组，分组按排序规则排列，没有标签的放在最后的“未分类”中
+    Tag(Collation),
+}
+
+const UNTAGGED_GROUP: &str = "未分类";
+
+/// 按指定方式将待办事项分组，返回（分组标题，分组内的事项）
+pub fn group_todos<'a>(
+    todos: impl IntoIterator<Item = &'a Todo>,
+    group_by: GroupBy,
+) -> Vec<(String, Vec<&'a Todo>)> {
+    let todos: Vec<&Todo> = todos.into_iter().collect();
+    let mut groups: Vec<(String, Vec<&Todo>)> = Vec::new();
+    match group_by {
+        GroupBy::Priority => {
+            for priority in [Priority::High, Priority::Medium, Priority::Low] {
+                let items: Vec<&Todo> = todos.iter().copied().filter(|t| t.priority() == priority).collect();
+                if !items.is_empty() {
+                    groups.push((priority_heading(priority), items));
+                }
+            }
+        }
+        GroupBy::Tag(collation) => {
+            let mut untagged = Vec::new();
+            for todo in todos {
+                let Some(tag) = todo.tags().first() else {
+                    untagged.push(todo);
+                    continue;
+                };
+                match groups.iter_mut().find(|(name, _)| name == tag) {
+                    Some((_, items)) => items.push(todo),
+                    None => groups.push((tag.clone(), vec![todo])),
+                }
+            }
+            let collator = collation.collator();
+            groups.sort_by(|(a, _), (b, _)| collator.compare(a, b));
+            if !untagged.is_empty() {
+                groups.push((UNTAGGED_GROUP.to_string(), untagged));
+            }
+        }
+    }
+    groups
+}
+
+fn priority_heading(priority: Priority) -> String {
+    format!("{}优先级", priority)
+}
+
+/// 分组标题对应的上下文：导入时，分组中的事项会继承该优先级或标签
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupContext {
+    pub priority: Option<Priority>,
+    pub tag: Option<String>,
+}
+
+impl GroupContext {
+    /// 根据分组标题还原上下文
+    pub fn from_heading(heading: &str) -> Self {
+        let heading = heading.trim();
+        let priority = [Priority::High, Priority::Medium, Priority::Low]
+            .into_iter()
+            .find(|p| priority_heading(*p) == heading);
+        let tag = match priority {
+            Some(_) => None,
+            None if heading.is_empty() || heading == UNTAGGED_GROUP => None,
+            None => Some(heading.to_string()),
+        };
+        GroupContext { priority, tag }
+    }
+}
+
+/// 从清单类格式中解析出的条目
+///
+/// 与 [`Todo`] 不同，这里只包含清单中实际写出的字段，
+/// 以便合并时不会覆盖清单没有表达的信息。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChecklistItem {
+    pub title: String,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Local>>,
+    pub priority: Option<Priority>,
+    pub due_date: Option<DateTime<Local>>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub fields: BTreeMap<String, String>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl ChecklistItem {
+    /// 转换为在 `now` 创建的新待办事项
+    pub fn into_todo(self, now: DateTime<Local>) -> Todo {
+        let mut todo = Todo::new_at(self.title.clone(), None, now);
+        self.apply_to(&mut todo, now);
+        todo.set_modified_at(None);
+        todo
+    }
+
+    /// 将清单中的修改合并到已有的待办事项
+    ///
+    /// 完成状态以清单为准；优先级、截止日期和描述只在清单中写出时才更新；
+    /// 标签和注释取并集；清单中写出的自定义字段覆盖原有的值。
+    /// 清单没有写出完成时间时以 `now` 作为完成时间。
+    pub fn apply_to(self, todo: &mut Todo, now: DateTime<Local>) {
+        if self.completed && !todo.is_completed() {
+            todo.mark_as_done_at(self.completed_at.unwrap_or(now));
+        } else if !self.completed && todo.is_completed() {
+            todo.mark_as_undone();
+        }
+        if let Some(priority) = self.priority {
+            todo.set_priority(priority);
+        }
+        if self.due_date.is_some() {
+            todo.set_due_date(self.due_date);
+        }
+        if self.description.is_some() {
+            todo.set_description(self.description);
+        }
+        for tag in self.tags {
+            todo.add_tag(tag);
+        }
+        for (name, value) in self.fields {
+            todo.set_field(&name, Some(value));
+        }
+        for annotation in self.annotations {
+            // 清单中的注释时间只精确到分钟，同一分钟内内容相同的视为同一条
+            let exists = todo
+                .annotations()
+                .iter()
+                .any(|a| a.text == annotation.text && (a.entry - annotation.entry).num_minutes() == 0);
+            if !exists {
+                todo.add_annotation_at(annotation.entry, annotation.text);
+            }
+        }
+    }
+}
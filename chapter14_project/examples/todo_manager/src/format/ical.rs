@@ -0,0 +1,478 @@
+//! iCalendar 格式 (RFC 5545) 中的 VTODO 组件
+//!
+//! 导出的文件可以直接被日历应用打开，从日历应用导出的任务也能导入回来：
+//!
+//! - `UID` 对应待办事项的标识符，`SUMMARY`/`DESCRIPTION` 对应标题和描述
+//! - `PRIORITY` 使用 1（高）、5（中）、9（低），导入时 1-4 视为高，6-9 视为低
+//! - `CATEGORIES` 对应标签，`STATUS`/`COMPLETED` 对应完成状态
+//! - 自定义字段写作扩展属性 `X-TODO-FIELD;X-NAME=<名称>:<值>`
+//! - 有截止日期时，单独设置的提醒时间写作 `VALARM` 组件，`TRIGGER` 为相对 `DUE` 的时长；
+//!   不提醒写作 `X-TODO-REMINDERS:NONE`
+//! - 超过 75 个字节的内容行会折行，文本值中的 `\`、`;`、`,` 和换行会被转义
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use super::{end_of_day, format_basic_utc as format_utc, parse_basic_utc};
+use crate::todo::{Priority, ReminderOffset, Todo};
+
+const MAX_LINE_OCTETS: usize = 75;
+
+/// 将待办事项列表转换为 iCalendar 文本
+pub fn to_string<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Rust学习者//todo_manager//ZH".to_string(),
+    ];
+    for todo in todos {
+        lines.extend(vtodo_lines(todo));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut contents = String::new();
+    for line in lines {
+        contents.push_str(&fold_line(&line));
+    }
+    contents
+}
+
+/// 解析 iCalendar 文本中的所有 VTODO 组件
+///
+/// 没有写出创建时间或完成时间的事项以 `now` 补齐。
+pub fn parse(contents: &str, now: DateTime<Local>) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    // VTODO 内部的 VALARM 组件中的 TRIGGER 属性
+    let mut triggers = Vec::new();
+    // VTODO 内部嵌套组件（如 VALARM）的层数
+    let mut nested = 0;
+
+    for line in unfold_lines(contents) {
+        let Some(prop) = Property::parse(&line) else {
+            continue;
+        };
+        match (prop.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if prop.value.eq_ignore_ascii_case("VTODO") => {
+                current = Some(Vec::new());
+                triggers.clear();
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(props)) if prop.value.eq_ignore_ascii_case("VTODO") => {
+                todos.push(todo_from_properties(props, &triggers, now));
+                current = None;
+            }
+            ("TRIGGER", Some(_)) if nested == 1 => triggers.push(prop),
+            (_, Some(props)) if nested == 0 => props.push(prop),
+            _ => {}
+        }
+    }
+    todos
+}
+
+fn vtodo_lines(todo: &Todo) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTODO".to_string()];
+    if let Some(uid) = todo.uid() {
+        lines.push(format!("UID:{}", escape_text(uid)));
+    }
+    // 没有 METHOD 的日历中 DTSTAMP 表示对象最后修改的时间（RFC 5545 3.8.7.2）
+    lines.push(format!("DTSTAMP:{}", format_utc(todo.modified_at())));
+    lines.push(format!("CREATED:{}", format_utc(todo.created_at())));
+    lines.push(format!("LAST-MODIFIED:{}", format_utc(todo.modified_at())));
+    lines.push(format!("SUMMARY:{}", escape_text(todo.title())));
+    if let Some(desc) = todo.description() {
+        lines.push(format!("DESCRIPTION:{}", escape_text(desc)));
+    }
+    lines.push(format!("PRIORITY:{}", priority_value(todo.priority())));
+    if let Some(due) = todo.due_date() {
+        lines.push(format!("DUE:{}", format_utc(due)));
+    }
+    if !todo.tags().is_empty() {
+        let categories: Vec<String> = todo.tags().iter().map(|t| escape_text(t)).collect();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    for (name, value) in todo.fields() {
+        lines.push(format!("X-TODO-FIELD;X-NAME={}:{}", name, escape_text(value)));
+    }
+    match (todo.reminders(), todo.due_date()) {
+        (Some([]), _) => lines.push("X-TODO-REMINDERS:NONE".to_string()),
+        (Some(reminders), Some(_)) => {
+            for offset in reminders {
+                lines.push("BEGIN:VALARM".to_string());
+                lines.push("ACTION:DISPLAY".to_string());
+                lines.push(format!("DESCRIPTION:{}", escape_text(todo.title())));
+                lines.push(format!("TRIGGER;RELATED=END:{}", format_trigger(offset)));
+                lines.push("END:VALARM".to_string());
+            }
+        }
+        _ => {}
+    }
+    if todo.is_completed() {
+        lines.push("STATUS:COMPLETED".to_string());
+        if let Some(completed_at) = todo.completed_at() {
+            lines.push(format!("COMPLETED:{}", format_utc(completed_at)));
+        }
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+fn todo_from_properties(props: &[Property], triggers: &[Property], now: DateTime<Local>) -> Todo {
+    let text = |name: &str| {
+        props
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| unescape_text(&p.value))
+    };
+    let datetime = |name: &str| props.iter().find(|p| p.name == name).and_then(Property::datetime);
+
+    let priority = props
+        .iter()
+        .find(|p| p.name == "PRIORITY")
+        .and_then(|p| p.value.trim().parse::<u8>().ok())
+        .and_then(priority_from_value)
+        .unwrap_or_default();
+
+    let mut todo = Todo::new_at(
+        text("SUMMARY").unwrap_or_default(),
+        text("DESCRIPTION").filter(|d| !d.is_empty()),
+        datetime("CREATED").or_else(|| datetime("DTSTAMP")).unwrap_or(now),
+    );
+    todo.set_priority(priority);
+    if let Some(uid) = text("UID") {
+        todo.set_uid(uid);
+    }
+    todo.set_due_date(datetime("DUE"));
+    for prop in props.iter().filter(|p| p.name == "CATEGORIES") {
+        for tag in split_text_list(&prop.value) {
+            if !tag.is_empty() {
+                todo.add_tag(tag);
+            }
+        }
+    }
+    for prop in props.iter().filter(|p| p.name == "X-TODO-FIELD") {
+        if let Some(name) = prop.param("X-NAME").filter(|n| !n.is_empty()) {
+            todo.set_field(name, Some(unescape_text(&prop.value)));
+        }
+    }
+
+    // 只识别相对截止日期、在截止日期之前（或同时）的提醒
+    let reminders: Vec<ReminderOffset> = triggers
+        .iter()
+        .filter(|p| p.param("RELATED").is_some_and(|r| r.eq_ignore_ascii_case("END")))
+        .filter_map(|p| parse_trigger(&p.value))
+        .collect();
+    if text("X-TODO-REMINDERS").is_some_and(|r| r.eq_ignore_ascii_case("NONE")) {
+        todo.set_reminders(Some(Vec::new()));
+    } else if !reminders.is_empty() {
+        todo.set_reminders(Some(reminders));
+    }
+
+    let status = text("STATUS").unwrap_or_default();
+    let completed_at = datetime("COMPLETED");
+    if status.eq_ignore_ascii_case("COMPLETED") || completed_at.is_some() {
+        todo.mark_as_done_at(completed_at.unwrap_or(now));
+    }
+    todo.set_modified_at(datetime("LAST-MODIFIED"));
+    todo
+}
+
+/// 一个内容行：`NAME;PARAM=VALUE:值`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        // 冒号可能出现在带引号的参数值中，需要跳过
+        let mut in_quotes = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ':' if !in_quotes => Some(i),
+            _ => None,
+        })?;
+
+        let mut head = line[..colon].split(';');
+        let name = head.next()?.trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        let params = head
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.trim().to_ascii_uppercase(), v.trim_matches('"').to_string()))
+            .collect();
+        Some(Property {
+            name,
+            params,
+            value: line[colon + 1..].to_string(),
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// 解析 DATE 或 DATE-TIME 类型的值
+    ///
+    /// UTC 时间会转换为本地时间；带 `TZID` 或不带时区的时间按本地时间处理；
+    /// 只有日期时按当天的最后一秒处理（与命令行中的 `--due` 一致）。
+    fn datetime(&self) -> Option<DateTime<Local>> {
+        let value = self.value.trim();
+        if self.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return end_of_day(date);
+        }
+        if value.ends_with('Z') {
+            return parse_basic_utc(value);
+        }
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        Local.from_local_datetime(&naive).earliest()
+    }
+}
+
+fn priority_value(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+/// 0 表示未定义优先级
+fn priority_from_value(value: u8) -> Option<Priority> {
+    match value {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// 提前的时长写作负的 DURATION 值，如 `-P1D`、`-PT90M`，到期时提醒写作 `PT0S`
+fn format_trigger(offset: &ReminderOffset) -> String {
+    match offset.duration().num_minutes() {
+        0 => "PT0S".to_string(),
+        m if m % (7 * 24 * 60) == 0 => format!("-P{}W", m / (7 * 24 * 60)),
+        m if m % (24 * 60) == 0 => format!("-P{}D", m / (24 * 60)),
+        m if m % 60 == 0 => format!("-PT{}H", m / 60),
+        m => format!("-PT{}M", m),
+    }
+}
+
+/// 解析 `TRIGGER` 的 DURATION 值，截止日期之后的提醒返回 `None`
+fn parse_trigger(value: &str) -> Option<ReminderOffset> {
+    let value = value.trim();
+    let (negative, rest) = match value.as_bytes().first()? {
+        b'-' => (true, &value[1..]),
+        b'+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if number.is_empty() => in_time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                let size = match (c, in_time) {
+                    ('W', false) => 7 * 24 * 3600,
+                    ('D', false) => 24 * 3600,
+                    ('H', true) => 3600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+                seconds = seconds.checked_add(n.checked_mul(size)?)?;
+            }
+        }
+    }
+    if !number.is_empty() || (seconds > 0 && !negative) {
+        return None;
+    }
+    Some(ReminderOffset::from_minutes(seconds / 60))
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// 按未转义的逗号拆分多值文本（如 CATEGORIES）
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                items.push(unescape_text(value[start..i].trim()));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(unescape_text(value[start..].trim()));
+    items
+}
+
+/// 将一个内容行折行为不超过 75 个字节的若干行，不会拆开多字节字符
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // 续行开头的空格也计入长度
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// 还原折行：以空格或制表符开头的行接在上一行后面
+fn unfold_lines(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in contents.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_line() {
+        let line = format!("SUMMARY:{}", "待办".repeat(30));
+        let folded = fold_line(&line);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(unfold_lines(&folded), vec![line]);
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let text = "a;b,c\\d\n第二行";
+        assert_eq!(escape_text(text), "a\\;b\\,c\\\\d\\n第二行");
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(split_text_list("工作,a\\,b, 家"), vec!["工作", "a,b", "家"]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut todo = Todo::new("写周报; 第一版".to_string(), Some("包含, 逗号\n和换行".to_string()));
+        todo.set_priority(Priority::High);
+        todo.set_due_date(end_of_day(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()));
+        todo.add_tag("工作".to_string());
+        todo.add_tag("季度,汇报".to_string());
+        todo.set_field("customer", Some("ACME, Inc".to_string()));
+        todo.mark_as_done();
+
+        let ics = to_string(std::slice::from_ref(&todo));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+
+        let parsed = parse(&ics, Local::now());
+        assert_eq!(parsed.len(), 1);
+        let parsed = &parsed[0];
+        assert_eq!(parsed.uid(), todo.uid());
+        assert_eq!(parsed.title(), todo.title());
+        assert_eq!(parsed.description(), todo.description());
+        assert_eq!(parsed.priority(), Priority::High);
+        assert_eq!(parsed.tags(), todo.tags());
+        assert_eq!(parsed.due_date(), todo.due_date());
+        assert_eq!(parsed.fields(), todo.fields());
+        assert!(parsed.is_completed());
+    }
+
+    #[test]
+    fn test_reminders_round_trip() {
+        let mut todo = Todo::with_due_date("写周报".to_string(), None, Local::now());
+        todo.set_reminders(Some(ReminderOffset::parse_list("1w,1d,2h,90m,0m").unwrap()));
+        let ics = to_string(std::slice::from_ref(&todo));
+        for trigger in ["-P1W", "-P1D", "-PT2H", "-PT90M", "PT0S"] {
+            assert!(ics.contains(&format!("TRIGGER;RELATED=END:{}\r\n", trigger)), "{}", ics);
+        }
+        assert_eq!(parse(&ics, Local::now())[0].reminders(), todo.reminders());
+
+        todo.set_reminders(Some(Vec::new()));
+        let ics = to_string(std::slice::from_ref(&todo));
+        assert!(!ics.contains("VALARM"));
+        assert_eq!(parse(&ics, Local::now())[0].reminders(), Some(&[][..]));
+
+        todo.set_reminders(None);
+        assert_eq!(parse(&to_string(std::slice::from_ref(&todo)), Local::now())[0].reminders(), None);
+
+        assert_eq!(parse_trigger("-P1DT12H"), Some(ReminderOffset::from_minutes(36 * 60)));
+        assert_eq!(parse_trigger("PT15M"), None);
+        assert_eq!(parse_trigger("-P1X"), None);
+    }
+
+    #[test]
+    fn test_parse_calendar_app_output() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:abc@example.com\r\nSUMMARY:预约\r\n 牙医\r\n\
+                   DUE;VALUE=DATE:20261101\r\nPRIORITY:0\r\nBEGIN:VALARM\r\nDESCRIPTION:提醒\r\n\
+                   END:VALARM\r\nCATEGORIES:个人\r\nCATEGORIES:健康\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let now = Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        let todos = parse(ics, now);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].created_at(), &now);
+        assert_eq!(todos[0].uid(), Some("abc@example.com"));
+        assert_eq!(todos[0].title(), "预约牙医");
+        assert!(todos[0].description().is_none());
+        assert_eq!(todos[0].priority(), Priority::Medium);
+        assert_eq!(todos[0].tags(), ["个人", "健康"]);
+        assert_eq!(todos[0].due_date().unwrap().format("%Y-%m-%d").to_string(), "2026-11-01");
+        assert!(!todos[0].is_completed());
+    }
+}
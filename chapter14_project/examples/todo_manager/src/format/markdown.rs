@@ -0,0 +1,265 @@
+//! GitHub 风格的 Markdown 任务清单
+//!
+//! ```markdown
+//! # 待办事项
+//!
+//! ## 高优先级
+//!
+//! - [ ] 写周报 (截止: 2026-10-20) #工作
+//!   本周的工作总结
+//!   - [2026-10-18 10:30] 已收集数据
+//! - [x] 买牛奶
+//! ```
+//!
+//! 每个二级标题是一个分组（优先级或第一个标签），事项下方缩进的行是描述，
+//! 缩进的 `- [时间] 内容` 列表项是注释。
+//! 自定义字段使用 Dataview 的行内写法 `[名称:: 值]`，写在标签之前。
+//! 导入时事项会继承所在分组的优先级或标签，行尾的 `#标签` 也会被识别；
+//! 标题中的 `#` 导出为 `\#`，不会被当作标签。
+
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+
+use super::{end_of_day, group_todos, ChecklistItem, GroupBy, GroupContext};
+use crate::todo::{Annotation, Todo};
+
+const DUE_PREFIX: &str = "(截止: ";
+const ANNOTATION_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// 将待办事项转换为按指定方式分组的 Markdown 清单
+pub fn to_string<'a>(todos: impl IntoIterator<Item = &'a Todo>, group_by: GroupBy) -> String {
+    let mut md = String::from("# 待办事项\n");
+    for (heading, items) in group_todos(todos, group_by) {
+        md.push_str(&format!("\n## {}\n\n", heading));
+        for todo in items {
+            let checkbox = if todo.is_completed() { "[x]" } else { "[ ]" };
+            // 标题中的 `#` 写为 Markdown 转义的 `\#`，导入时不会被当作标签
+            md.push_str(&format!("- {} {}", checkbox, todo.title().replace('#', "\\#")));
+            if let Some(due) = todo.due_date() {
+                md.push_str(&format!(" {}{})", DUE_PREFIX, due.format("%Y-%m-%d")));
+            }
+            for (name, value) in todo.fields() {
+                md.push_str(&format!(" [{}:: {}]", name, value));
+            }
+            // 按标签分组时，第一个标签已经体现在分组标题中
+            let skip = usize::from(matches!(group_by, GroupBy::Tag(_)));
+            for tag in todo.tags().iter().skip(skip) {
+                md.push_str(&format!(" #{}", tag.split_whitespace().collect::<Vec<_>>().join("_")));
+            }
+            md.push('\n');
+            if let Some(desc) = todo.description() {
+                for line in desc.lines() {
+                    md.push_str(&format!("  {}\n", line));
+                }
+            }
+            for annotation in todo.annotations() {
+                let entry = annotation.entry.format(ANNOTATION_TIME_FORMAT);
+                // 注释中的换行会破坏列表结构，用空格代替
+                let text = annotation.text.lines().collect::<Vec<_>>().join(" ");
+                md.push_str(&format!("  - [{}] {}\n", entry, text));
+            }
+        }
+    }
+    md
+}
+
+/// 解析 Markdown 清单中的所有任务项
+pub fn parse(contents: &str) -> Vec<ChecklistItem> {
+    let mut items: Vec<ChecklistItem> = Vec::new();
+    let mut context = GroupContext::default();
+    // 当前是否处于某个任务项的描述中
+    let mut in_item = false;
+
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        if let Some(heading) = trimmed.strip_prefix("## ") {
+            context = GroupContext::from_heading(heading);
+            in_item = false;
+        } else if trimmed.starts_with('#') && trimmed.trim_start_matches('#').starts_with(' ') {
+            // 其他级别的标题开始新的上下文
+            context = GroupContext::default();
+            in_item = false;
+        } else if let Some(item) = parse_item(trimmed, &context) {
+            items.push(item);
+            in_item = true;
+        } else if let Some(annotation) = parse_annotation(trimmed).filter(|_| in_item && line.starts_with([' ', '\t'])) {
+            items.last_mut().unwrap().annotations.push(annotation);
+        } else if in_item && line.starts_with([' ', '\t']) && !trimmed.is_empty() {
+            let item = items.last_mut().unwrap();
+            let desc = item.description.get_or_insert_with(String::new);
+            if !desc.is_empty() {
+                desc.push('\n');
+            }
+            desc.push_str(trimmed);
+        } else if !trimmed.is_empty() {
+            in_item = false;
+        }
+    }
+    items
+}
+
+/// 解析一行 `- [ ] 标题 (截止: YYYY-MM-DD) #标签`
+fn parse_item(line: &str, context: &GroupContext) -> Option<ChecklistItem> {
+    let rest = line.strip_prefix(['-', '*', '+'])?.strip_prefix(' ')?;
+    let (completed, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let (rest, fields) = extract_fields(rest);
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let mut tags = Vec::new();
+    while let Some(tag) = words.last().and_then(|w| w.strip_prefix('#')).filter(|t| !t.is_empty()) {
+        tags.insert(0, tag.to_string());
+        words.pop();
+    }
+    let mut title = words.join(" ").replace("\\#", "#");
+
+    let mut due_date = None;
+    if let Some(start) = title.rfind(DUE_PREFIX).filter(|_| title.ends_with(')')) {
+        let date = &title[start + DUE_PREFIX.len()..title.len() - 1];
+        if let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+            due_date = end_of_day(date);
+            title.truncate(start);
+            title.truncate(title.trim_end().len());
+        }
+    }
+
+    if let Some(tag) = &context.tag {
+        tags.insert(0, tag.clone());
+    }
+    Some(ChecklistItem {
+        title,
+        completed,
+        priority: context.priority,
+        due_date,
+        tags,
+        fields,
+        ..Default::default()
+    })
+}
+
+/// 解析缩进的注释行 `- [2026-10-18 10:30] 内容`
+fn parse_annotation(line: &str) -> Option<Annotation> {
+    let (entry, text) = line.strip_prefix("- [")?.split_once("] ")?;
+    let entry = NaiveDateTime::parse_from_str(entry, ANNOTATION_TIME_FORMAT).ok()?;
+    Some(Annotation {
+        entry: chrono::Local.from_local_datetime(&entry).earliest()?,
+        text: text.trim().to_string(),
+    })
+}
+
+/// 取出行内的 `[名称:: 值]`，返回去掉这些字段后的文本
+fn extract_fields(line: &str) -> (String, BTreeMap<String, String>) {
+    let mut fields = BTreeMap::new();
+    let mut text = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('[') {
+        let field = rest[start + 1..].split_once(']').and_then(|(inner, after)| {
+            let (name, value) = inner.split_once("::")?;
+            let name = name.trim();
+            (!name.is_empty() && !name.contains(char::is_whitespace)).then_some((name, value.trim(), after))
+        });
+        match field {
+            Some((name, value, after)) => {
+                fields.insert(name.to_string(), value.to_string());
+                text.push_str(&rest[..start]);
+                rest = after;
+            }
+            None => {
+                text.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    (text, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::Collation;
+    use crate::todo::Priority;
+
+    #[test]
+    fn test_export_grouped_by_tag() {
+        let mut todo = Todo::new("写周报".to_string(), Some("第一行\n第二行".to_string()));
+        todo.add_tag("工作".to_string());
+        todo.add_tag("周报".to_string());
+        let done = {
+            let mut t = Todo::new("买牛奶".to_string(), None);
+            t.mark_as_done();
+            t
+        };
+
+        let md = to_string(&[todo, done], GroupBy::Tag(Collation::default()));
+        assert!(md.contains("## 工作\n\n- [ ] 写周报 #周报\n  第一行\n  第二行\n"));
+        assert!(md.contains("## 未分类\n\n- [x] 买牛奶\n"));
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut todo = Todo::new("写周报".to_string(), None);
+        todo.add_tag("工作".to_string());
+        todo.set_field("points", Some("3".to_string()));
+        let md = to_string(&[todo], GroupBy::Priority);
+        assert!(md.contains("- [ ] 写周报 [points:: 3] #工作\n"));
+
+        let items = parse(&md);
+        assert_eq!(items[0].title, "写周报");
+        assert_eq!(items[0].tags, vec!["工作"]);
+        assert_eq!(items[0].fields.get("points").map(String::as_str), Some("3"));
+        assert!(parse("- [ ] 看 [链接] 和 [a::]\n")[0].title.contains("[链接]"));
+    }
+
+    #[test]
+    fn test_hash_in_title() {
+        let mut todo = Todo::new("修复 issue #123".to_string(), None);
+        todo.add_tag("工作".to_string());
+        let md = to_string(&[todo], GroupBy::Priority);
+        assert!(md.contains("- [ ] 修复 issue \\#123 #工作\n"));
+
+        let items = parse(&md);
+        assert_eq!(items[0].title, "修复 issue #123");
+        assert_eq!(items[0].tags, vec!["工作"]);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut todo = Todo::new("写周报".to_string(), Some("总结".to_string()));
+        todo.add_annotation("已收集数据".to_string());
+        let md = to_string(&[todo], GroupBy::Priority);
+        assert!(md.contains("  总结\n  - ["));
+
+        let items = parse(&md);
+        assert_eq!(items[0].description.as_deref(), Some("总结"));
+        assert_eq!(items[0].annotations.len(), 1);
+        assert_eq!(items[0].annotations[0].text, "已收集数据");
+    }
+
+    #[test]
+    fn test_parse() {
+        let md = "# 周记\n\n## 高优先级\n\n- [ ] 写周报 (截止: 2026-10-20) #工作\n  总结\n\n\
+                  ## 家庭\n\n- [X] 买牛奶\n* [ ] 修水管\n- 普通列表项\n";
+        let items = parse(md);
+        assert_eq!(items.len(), 3);
+
+        assert_eq!(items[0].title, "写周报");
+        assert_eq!(items[0].priority, Some(Priority::High));
+        assert_eq!(items[0].tags, vec!["工作"]);
+        assert_eq!(items[0].description.as_deref(), Some("总结"));
+        assert_eq!(items[0].due_date.unwrap().format("%Y-%m-%d").to_string(), "2026-10-20");
+
+        assert_eq!(items[1].title, "买牛奶");
+        assert!(items[1].completed);
+        assert_eq!(items[1].priority, None);
+        assert_eq!(items[1].tags, vec!["家庭"]);
+        assert_eq!(items[2].title, "修水管");
+        assert!(!items[2].completed);
+    }
+}
@@ -0,0 +1,324 @@
+//! Org-mode 标题清单
+//!
+//! ```org
+//! #+TITLE: 待办事项
+//!
+//! * 高优先级
+//! ** TODO [#A] 写周报 :工作:周报:
+//!    DEADLINE: <2026-10-20 Tue>
+//!    :PROPERTIES:
+//!    :points: 3
+//!    :END:
+//!    本周的工作总结
+//! ** DONE [#A] 交报销单
+//!    CLOSED: [2026-10-18 Sun 10:00]
+//! ```
+//!
+//! 一级标题是分组，带 `TODO`/`DONE` 关键字的标题是事项；优先级使用 `[#A]`/`[#B]`/`[#C]`，
+//! 标签写在行尾，自定义字段写在 `:PROPERTIES:` 抽屉中。
+//! 导入时没有写出优先级的事项会继承所在分组的优先级或标签。
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use super::{end_of_day, group_todos, local_datetime, ChecklistItem, GroupBy, GroupContext};
+use crate::todo::{Priority, Todo};
+
+/// Org-mode 自身使用的属性，导入时不作为自定义字段
+const SPECIAL_PROPERTIES: &[&str] = &[
+    "ID", "CUSTOM_ID", "CATEGORY", "ORDERED", "STYLE", "VISIBILITY", "LOGGING", "ARCHIVE", "COOKIE_DATA", "LAST_REPEAT",
+];
+
+/// 将待办事项转换为按指定方式分组的 Org 文档
+pub fn to_string<'a>(todos: impl IntoIterator<Item = &'a Todo>, group_by: GroupBy) -> String {
+    let mut org = String::from("#+TITLE: 待办事项\n");
+    for (heading, items) in group_todos(todos, group_by) {
+        org.push_str(&format!("\n* {}\n", heading));
+        for todo in items {
+            let keyword = if todo.is_completed() { "DONE" } else { "TODO" };
+            org.push_str(&format!(
+                "** {} [#{}] {}",
+                keyword,
+                priority_cookie(todo.priority()),
+                todo.title()
+            ));
+            if !todo.tags().is_empty() {
+                let tags: Vec<String> = todo.tags().iter().map(|t| org_tag(t)).collect();
+                org.push_str(&format!(" :{}:", tags.join(":")));
+            }
+            org.push('\n');
+
+            let mut planning = Vec::new();
+            if let Some(completed_at) = todo.completed_at().filter(|_| todo.is_completed()) {
+                planning.push(format!("CLOSED: [{}]", completed_at.format("%Y-%m-%d %a %H:%M")));
+            }
+            if let Some(due) = todo.due_date() {
+                planning.push(format!("DEADLINE: <{}>", due.format("%Y-%m-%d %a")));
+            }
+            if !planning.is_empty() {
+                org.push_str(&format!("   {}\n", planning.join(" ")));
+            }
+            if !todo.fields().is_empty() {
+                org.push_str("   :PROPERTIES:\n");
+                for (name, value) in todo.fields() {
+                    org.push_str(&format!("   :{}: {}\n", name, value));
+                }
+                org.push_str("   :END:\n");
+            }
+            if let Some(desc) = todo.description() {
+                for line in desc.lines() {
+                    org.push_str(&format!("   {}\n", line));
+                }
+            }
+        }
+    }
+    org
+}
+
+/// 解析 Org 文档中所有带 `TODO`/`DONE` 关键字的标题
+pub fn parse(contents: &str) -> Vec<ChecklistItem> {
+    let mut items: Vec<ChecklistItem> = Vec::new();
+    let mut context = GroupContext::default();
+    let mut in_item = false;
+    // 当前所在的抽屉名称，如 `PROPERTIES`
+    let mut drawer: Option<String> = None;
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if line.starts_with('*') {
+            let stars = line.chars().take_while(|c| *c == '*').count();
+            let Some(heading) = line[stars..].strip_prefix(' ') else {
+                continue;
+            };
+            match parse_heading(heading, &context) {
+                Some(item) => {
+                    items.push(item);
+                    in_item = true;
+                }
+                None => {
+                    context = GroupContext::from_heading(strip_tags(heading).0);
+                    in_item = false;
+                }
+            }
+            drawer = None;
+        } else if !in_item || trimmed.is_empty() || trimmed.starts_with("#+") {
+            continue;
+        } else if trimmed == ":END:" {
+            drawer = None;
+        } else if drawer.as_deref() == Some("PROPERTIES") {
+            // 属性抽屉中的 `:名称: 值` 是自定义字段
+            let property = trimmed.strip_prefix(':').and_then(|p| p.split_once(':'));
+            if let Some((name, value)) = property.filter(|(n, _)| !n.is_empty() && !SPECIAL_PROPERTIES.contains(n)) {
+                let item = items.last_mut().unwrap();
+                item.fields.insert(name.to_string(), value.trim().to_string());
+            }
+        } else if drawer.is_some() {
+            // 跳过 :LOGBOOK: 等其他抽屉
+            continue;
+        } else if trimmed.len() > 2 && trimmed.starts_with(':') && trimmed.ends_with(':') && !trimmed[1..].contains(' ') {
+            drawer = Some(trimmed.trim_matches(':').to_string());
+        } else if is_planning_line(trimmed) {
+            let item = items.last_mut().unwrap();
+            parse_planning(trimmed, item);
+        } else {
+            let item = items.last_mut().unwrap();
+            let desc = item.description.get_or_insert_with(String::new);
+            if !desc.is_empty() {
+                desc.push('\n');
+            }
+            desc.push_str(trimmed);
+        }
+    }
+    items
+}
+
+/// 解析标题中关键字之后的部分，不是事项时返回 `None`
+fn parse_heading(heading: &str, context: &GroupContext) -> Option<ChecklistItem> {
+    let (keyword, rest) = heading.split_once(' ').unwrap_or((heading, ""));
+    let completed = match keyword {
+        "TODO" => false,
+        "DONE" => true,
+        _ => return None,
+    };
+
+    let mut rest = rest.trim_start();
+    let mut priority = context.priority;
+    let cookie = rest.strip_prefix("[#").and_then(|r| {
+        let mut chars = r.chars();
+        let p = priority_from_cookie(chars.next()?)?;
+        Some((p, chars.as_str().strip_prefix(']')?))
+    });
+    if let Some((p, after)) = cookie {
+        priority = Some(p);
+        rest = after.trim_start();
+    }
+
+    let (title, tags) = strip_tags(rest);
+    let mut tags: Vec<String> = tags.into_iter().map(parse_tag).collect();
+    if let Some(tag) = &context.tag {
+        if !tags.contains(tag) {
+            tags.insert(0, tag.clone());
+        }
+    }
+    Some(ChecklistItem {
+        title: title.to_string(),
+        completed,
+        priority,
+        tags,
+        ..Default::default()
+    })
+}
+
+/// 拆分标题行尾的 `:标签1:标签2:`
+fn strip_tags(heading: &str) -> (&str, Vec<&str>) {
+    let heading = heading.trim_end();
+    if let Some((title, last)) = heading.rsplit_once(' ') {
+        if last.len() > 2 && last.starts_with(':') && last.ends_with(':') {
+            let tags = last.trim_matches(':').split(':').filter(|t| !t.is_empty()).collect();
+            return (title.trim_end(), tags);
+        }
+    }
+    (heading, Vec::new())
+}
+
+fn is_planning_line(line: &str) -> bool {
+    ["DEADLINE:", "SCHEDULED:", "CLOSED:"].iter().any(|k| line.starts_with(k))
+}
+
+/// 解析 `CLOSED: [2026-10-18 Sun 10:00] DEADLINE: <2026-10-20 Tue>`
+fn parse_planning(line: &str, item: &mut ChecklistItem) {
+    if let Some(deadline) = timestamp_after(line, "DEADLINE:") {
+        let date = deadline.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        item.due_date = date.and_then(end_of_day);
+    }
+    if let Some(closed) = timestamp_after(line, "CLOSED:") {
+        let mut parts = closed.split_whitespace();
+        let date = parts.next().and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        let time = parts
+            .find_map(|p| NaiveTime::parse_from_str(p, "%H:%M").ok())
+            .unwrap_or_default();
+        item.completed_at = date.and_then(|d| local_datetime(d, time.hour(), time.minute(), 0));
+    }
+}
+
+/// 取出关键字后面 `<...>` 或 `[...]` 中的内容
+fn timestamp_after<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = line[line.find(keyword)? + keyword.len()..].trim_start();
+    let close = match rest.chars().next()? {
+        '<' => '>',
+        '[' => ']',
+        _ => return None,
+    };
+    rest.get(1..rest.find(close)?)
+}
+
+fn priority_cookie(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+fn priority_from_cookie(c: char) -> Option<Priority> {
+    match c {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Org 标签只能包含字母、数字以及 `_@#%`
+///
+/// 层级标签的 `/` 写为 `%2F`（`%` 本身写为 `%25`），导入时由 [`parse_tag`] 还原；
+/// 其他不能出现的字符替换为 `_`。
+fn org_tag(tag: &str) -> String {
+    let mut escaped = String::with_capacity(tag.len());
+    for c in tag.chars() {
+        match c {
+            '/' => escaped.push_str("%2F"),
+            '%' => escaped.push_str("%25"),
+            c if c.is_alphanumeric() || "_@#".contains(c) => escaped.push(c),
+            _ => escaped.push('_'),
+        }
+    }
+    escaped
+}
+
+/// 还原 [`org_tag`] 转义的 `/` 和 `%`
+fn parse_tag(tag: &str) -> String {
+    let mut unescaped = String::with_capacity(tag.len());
+    let mut rest = tag;
+    while let Some(i) = rest.find('%') {
+        unescaped.push_str(&rest[..i]);
+        let escape = rest.get(i..i + 3);
+        match escape {
+            Some(e) if e.eq_ignore_ascii_case("%2F") => unescaped.push('/'),
+            Some("%25") => unescaped.push('%'),
+            _ => {
+                unescaped.push('%');
+                rest = &rest[i + 1..];
+                continue;
+            }
+        }
+        rest = &rest[i + 3..];
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collate::Collation;
+
+    #[test]
+    fn test_export() {
+        let mut todo = Todo::new("写周报".to_string(), Some("总结".to_string()));
+        todo.set_priority(Priority::High);
+        todo.add_tag("工作".to_string());
+        todo.add_tag("周 报".to_string());
+        todo.set_due_date(end_of_day(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()));
+
+        let org = to_string(&[todo], GroupBy::Priority);
+        assert!(org.contains("* 高优先级\n** TODO [#A] 写周报 :工作:周_报:\n   DEADLINE: <2026-10-20 Tue>\n   总结\n"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let org = "#+TITLE: 周记\n* 家庭\n** DONE 买牛奶\n   CLOSED: [2026-10-18 Sun 10:30]\n\
+                   ** TODO [#A] 修水管 :紧急:\n   :PROPERTIES:\n   :ID: 1\n   :points: 5\n   :END:\n   找物业\n* 笔记\n随便写写\n";
+        let items = parse(org);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].title, "买牛奶");
+        assert!(items[0].completed);
+        assert_eq!(items[0].tags, vec!["家庭"]);
+        assert_eq!(
+            items[0].completed_at.unwrap().format("%Y-%m-%d %H:%M").to_string(),
+            "2026-10-18 10:30"
+        );
+
+        assert_eq!(items[1].title, "修水管");
+        assert!(!items[1].completed);
+        assert_eq!(items[1].priority, Some(Priority::High));
+        assert_eq!(items[1].tags, vec!["家庭", "紧急"]);
+        assert_eq!(items[1].description.as_deref(), Some("找物业"));
+        assert_eq!(items[1].fields.len(), 1);
+        assert_eq!(items[1].fields["points"], "5");
+    }
+
+    #[test]
+    fn test_hierarchical_tags_round_trip() {
+        let mut todo = Todo::new("修复登录".to_string(), None);
+        todo.add_tag("work/backend".to_string());
+        todo.add_tag("100%".to_string());
+
+        let org = to_string(&[todo], GroupBy::Tag(Collation::default()));
+        assert!(org.contains("* work/backend\n** TODO [#B] 修复登录 :work%2Fbackend:100%25:\n"));
+
+        let items = parse(&org);
+        assert_eq!(items[0].tags, vec!["work/backend", "100%"]);
+        assert_eq!(parse_tag("50%off_%2f"), "50%off_/");
+    }
+}
@@ -0,0 +1,332 @@
+//! Taskwarrior 的 JSON 格式（`task export` 的输出）
+//!
+//! | Taskwarrior            | 待办事项              |
+//! |------------------------|-----------------------|
+//! | `uuid`                 | 标识符                |
+//! | `description`          | 标题                  |
+//! | `annotations`          | 注释（内容和时间）    |
+//! | `todo_description`     | 描述（自定义属性）    |
+//! | `status`               | 完成状态              |
+//! | `priority` (`H/M/L`)   | 优先级                |
+//! | `entry` / `end` / `due`| 创建、完成、截止时间  |
+//! | `modified`             | 最后修改时间          |
+//! | `tags`                 | 标签                  |
+//! | 自定义属性 (UDA)       | 自定义字段            |
+//!
+//! 其他内置字段（如 `project`、`scheduled`）无法映射，导入时会记录在报告中。
+//! Taskwarrior 没有描述字段，描述保存在自定义属性 `todo_description` 中，
+//! 没有配置该属性的 Taskwarrior 也会原样保留它。
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Map, Value};
+
+use super::{format_basic_utc, parse_basic_utc};
+use crate::todo::{Priority, Todo};
+
+/// 保存描述的自定义属性
+const DESCRIPTION_UDA: &str = "todo_description";
+
+/// 由 Taskwarrior 计算得出、不需要导入的字段
+const DERIVED_FIELDS: &[&str] = &["id", "urgency"];
+
+/// Taskwarrior 的其他内置字段，不会被当作自定义属性
+const BUILTIN_FIELDS: &[&str] = &[
+    "project", "scheduled", "wait", "until", "start", "recur", "mask", "imask", "parent", "depends",
+];
+
+/// 一个无法映射到待办事项的 Taskwarrior 字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unmapped {
+    /// 所属任务的描述
+    pub task: String,
+    pub field: String,
+    pub value: String,
+}
+
+/// 导入的结果
+#[derive(Debug, Default)]
+pub struct Import {
+    pub todos: Vec<Todo>,
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// 将待办事项转换为 Taskwarrior JSON，可以用 `task import` 导入
+pub fn to_string<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> serde_json::Result<String> {
+    let tasks: Vec<Value> = todos.into_iter().map(to_task).collect();
+    serde_json::to_string_pretty(&tasks)
+}
+
+/// 解析 `task export` 的输出
+///
+/// 同时支持 JSON 数组和旧版本每行一个 JSON 对象的格式。
+/// 已删除的任务和重复任务的模板不会被导入，而是记录在报告中。
+/// 没有 `entry` 的任务以 `now` 作为创建时间。
+pub fn parse(contents: &str, now: DateTime<Local>) -> serde_json::Result<Import> {
+    let tasks: Vec<Value> = match serde_json::from_str(contents) {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            let lines: Vec<&str> = contents
+                .lines()
+                .map(|l| l.trim().trim_end_matches(','))
+                .filter(|l| !l.is_empty() && *l != "[" && *l != "]")
+                .collect();
+            if lines.is_empty() {
+                return Err(e);
+            }
+            lines.iter().map(|l| serde_json::from_str(l)).collect::<Result<_, _>>()?
+        }
+    };
+
+    let mut import = Import::default();
+    for task in tasks {
+        let Value::Object(task) = task else {
+            continue;
+        };
+        if let Some(todo) = from_task(task, now, &mut import.unmapped) {
+            import.todos.push(todo);
+        }
+    }
+    Ok(import)
+}
+
+fn to_task(todo: &Todo) -> Value {
+    let mut task = Map::new();
+    if let Some(uid) = todo.uid() {
+        task.insert("uuid".to_string(), json!(uid));
+    }
+    task.insert("description".to_string(), json!(todo.title()));
+    let status = if todo.is_completed() { "completed" } else { "pending" };
+    task.insert("status".to_string(), json!(status));
+    task.insert("entry".to_string(), json!(format_basic_utc(todo.created_at())));
+    task.insert("modified".to_string(), json!(format_basic_utc(todo.modified_at())));
+    if let Some(end) = todo.completed_at().filter(|_| todo.is_completed()) {
+        task.insert("end".to_string(), json!(format_basic_utc(end)));
+    }
+    if let Some(due) = todo.due_date() {
+        task.insert("due".to_string(), json!(format_basic_utc(due)));
+    }
+    task.insert("priority".to_string(), json!(priority_code(todo.priority())));
+    if !todo.tags().is_empty() {
+        task.insert("tags".to_string(), json!(todo.tags()));
+    }
+    if let Some(desc) = todo.description() {
+        task.insert(DESCRIPTION_UDA.to_string(), json!(desc));
+    }
+    if !todo.annotations().is_empty() {
+        let annotations: Vec<Value> = todo
+            .annotations()
+            .iter()
+            .map(|a| json!({ "entry": format_basic_utc(&a.entry), "description": a.text }))
+            .collect();
+        task.insert("annotations".to_string(), Value::Array(annotations));
+    }
+    for (name, value) in todo.fields() {
+        task.entry(name.clone()).or_insert_with(|| json!(value));
+    }
+    Value::Object(task)
+}
+
+fn from_task(mut task: Map<String, Value>, now: DateTime<Local>, unmapped: &mut Vec<Unmapped>) -> Option<Todo> {
+    let title = match task.remove("description") {
+        Some(Value::String(s)) => s,
+        _ => String::new(),
+    };
+    let mut report = |field: &str, value: &Value| {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        unmapped.push(Unmapped {
+            task: title.clone(),
+            field: field.to_string(),
+            value,
+        });
+    };
+
+    let status = task.remove("status");
+    let completed = match status.as_ref().and_then(Value::as_str) {
+        Some("pending") | Some("waiting") | None => false,
+        Some("completed") => true,
+        Some(_) => {
+            // deleted 和 recurring（重复任务的模板）没有对应的待办事项
+            report("status", status.as_ref().unwrap());
+            return None;
+        }
+    };
+
+    let mut todo = Todo::new_at(title.clone(), None, now);
+    let mut end = None;
+    let mut modified = None;
+    for (field, value) in task {
+        let text = value.as_str();
+        let mapped = match field.as_str() {
+            "uuid" => text.map(|uid| todo.set_uid(uid.to_string())).is_some(),
+            "entry" => text
+                .and_then(parse_basic_utc)
+                .map(|entry| todo.set_created_at(entry))
+                .is_some(),
+            "end" => {
+                end = text.and_then(parse_basic_utc);
+                end.is_some()
+            }
+            "modified" => {
+                modified = text.and_then(parse_basic_utc);
+                modified.is_some()
+            }
+            "due" => {
+                let due = text.and_then(parse_basic_utc);
+                todo.set_due_date(due);
+                due.is_some()
+            }
+            "priority" => text
+                .and_then(priority_from_code)
+                .map(|priority| todo.set_priority(priority))
+                .is_some(),
+            "tags" => match value.as_array() {
+                Some(tags) => {
+                    for tag in tags {
+                        match tag.as_str() {
+                            Some(tag) => todo.add_tag(tag.to_string()),
+                            None => report("tags", tag),
+                        }
+                    }
+                    true
+                }
+                None => false,
+            },
+            "annotations" => match value.as_array() {
+                Some(annotations) => {
+                    for annotation in annotations {
+                        let text = annotation.get("description").and_then(Value::as_str);
+                        let entry = annotation.get("entry").and_then(Value::as_str).and_then(parse_basic_utc);
+                        match (text, entry) {
+                            (Some(text), Some(entry)) => todo.add_annotation_at(entry, text.to_string()),
+                            _ => report("annotations", annotation),
+                        }
+                    }
+                    true
+                }
+                None => false,
+            },
+            DESCRIPTION_UDA => text
+                .map(|desc| todo.set_description(Some(desc.to_string())))
+                .is_some(),
+            f if DERIVED_FIELDS.contains(&f) => true,
+            f if BUILTIN_FIELDS.contains(&f) => false,
+            // 其余的字符串和数字视为自定义属性
+            _ => match &value {
+                Value::String(s) => {
+                    todo.set_field(&field, Some(s.clone()));
+                    true
+                }
+                Value::Number(n) => {
+                    todo.set_field(&field, Some(n.to_string()));
+                    true
+                }
+                _ => false,
+            },
+        };
+        if !mapped {
+            report(&field, &value);
+        }
+    }
+
+    if completed {
+        todo.mark_as_done_at(end.unwrap_or(*todo.created_at()));
+    }
+    todo.set_modified_at(modified);
+    Some(todo)
+}
+
+fn priority_code(priority: Priority) -> &'static str {
+    match priority {
+        Priority::High => "H",
+        Priority::Medium => "M",
+        Priority::Low => "L",
+    }
+}
+
+fn priority_from_code(code: &str) -> Option<Priority> {
+    match code {
+        "H" => Some(Priority::High),
+        "M" => Some(Priority::Medium),
+        "L" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+{"id":1,"description":"写周报","entry":"20261001T080000Z","modified":"20261002T080000Z","status":"pending","uuid":"6e5d2c2a-1111-4b5c-9a3f-0123456789ab","priority":"H","due":"20261020T155959Z","project":"工作","tags":["周报"],"annotations":[{"entry":"20261002T080000Z","description":"先收集数据"}],"urgency":9.5},
+{"id":0,"description":"买牛奶","end":"20261018T020000Z","entry":"20261017T020000Z","status":"completed","uuid":"8f1a0f4e-2222-4d55-8b1e-0123456789ab","scheduled":"20261017T000000Z","estimate":"2h","points":3},
+{"id":0,"description":"旧任务","entry":"20261001T080000Z","status":"deleted","uuid":"9f1a0f4e-3333-4d55-8b1e-0123456789ab"}
+]"#;
+
+    #[test]
+    fn test_parse() {
+        let import = parse(EXPORT, Local::now()).unwrap();
+        assert_eq!(import.todos.len(), 2);
+
+        let todo = &import.todos[0];
+        assert_eq!(todo.uid(), Some("6e5d2c2a-1111-4b5c-9a3f-0123456789ab"));
+        assert_eq!(todo.title(), "写周报");
+        assert_eq!(todo.priority(), Priority::High);
+        assert_eq!(todo.tags(), ["周报"]);
+        assert!(todo.description().is_none());
+        assert_eq!(todo.annotations().len(), 1);
+        assert_eq!(todo.annotations()[0].text, "先收集数据");
+        assert_eq!(format_basic_utc(&todo.annotations()[0].entry), "20261002T080000Z");
+        assert_eq!(format_basic_utc(todo.due_date().unwrap()), "20261020T155959Z");
+        assert!(!todo.is_completed());
+
+        let done = &import.todos[1];
+        assert!(done.is_completed());
+        assert_eq!(format_basic_utc(done.completed_at().unwrap()), "20261018T020000Z");
+        assert_eq!(done.field("estimate"), Some("2h"));
+        assert_eq!(done.field("points"), Some("3"));
+
+        let fields: Vec<&str> = import.unmapped.iter().map(|u| u.field.as_str()).collect();
+        assert_eq!(fields, vec!["project", "scheduled", "status"]);
+        assert_eq!(import.unmapped[2].task, "旧任务");
+        assert_eq!(import.unmapped[2].value, "deleted");
+    }
+
+    #[test]
+    fn test_parse_line_per_task() {
+        let contents = "{\"description\":\"一\",\"status\":\"pending\"},\n{\"description\":\"二\",\"status\":\"waiting\"}\n";
+        let import = parse(contents, Local::now()).unwrap();
+        assert_eq!(import.todos.len(), 2);
+        assert!(import.unmapped.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut todo = Todo::new("写周报".to_string(), Some("第一行\n第二行".to_string()));
+        todo.set_priority(Priority::Low);
+        todo.add_tag("工作".to_string());
+        todo.set_field("customer", Some("ACME".to_string()));
+        todo.add_annotation("已约好会议室".to_string());
+        todo.mark_as_done();
+
+        let json = to_string(std::slice::from_ref(&todo)).unwrap();
+        let import = parse(&json, Local::now()).unwrap();
+        assert!(import.unmapped.is_empty());
+        let parsed = &import.todos[0];
+        assert_eq!(parsed.uid(), todo.uid());
+        assert_eq!(parsed.title(), todo.title());
+        assert_eq!(parsed.description(), todo.description());
+        assert_eq!(parsed.priority(), Priority::Low);
+        assert_eq!(parsed.tags(), todo.tags());
+        assert_eq!(parsed.fields(), todo.fields());
+        assert_eq!(parsed.annotations().len(), 1);
+        assert_eq!(parsed.annotations()[0].text, "已约好会议室");
+        assert_eq!(
+            format_basic_utc(&parsed.annotations()[0].entry),
+            format_basic_utc(&todo.annotations()[0].entry)
+        );
+        assert!(parsed.is_completed());
+    }
+}
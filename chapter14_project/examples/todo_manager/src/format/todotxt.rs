@@ -0,0 +1,362 @@
+//! todo.txt 格式 (https://github.com/todotxt/todo.txt)
+//!
+//! 每行一个待办事项，例如：
+//!
+//! ```text
+//! (A) 2026-10-01 写周报 +工作 @办公室 due:2026-10-20
+//! x 2026-10-18 2026-10-01 买牛奶 +家庭 pri:B
+//! ```
+//!
+//! - 优先级 `High`/`Medium`/`Low` 对应 `(A)`/`(B)`/`(C)`，已完成的事项改用 `pri:` 扩展记录
+//! - 标题中的换行和连续空白合并为一个空格，保证每个事项只占一行
+//! - 以 `@` 开头的标签原样输出为场景，其余标签输出为 `+项目`
+//! - 截止日期写作 `due:YYYY-MM-DD`，描述写作 `desc:`（空白和 `%` 做百分号编码）
+//! - 自定义字段写作 `field.<名称>:<值>`，值的编码方式与描述相同
+//! - 注释写作 `note:<RFC 3339 时间>;<内容>`，每条注释一个，内容的编码方式与描述相同
+//! - 单独设置的提醒时间写作 `remind:1d,2h`，不提醒写作 `remind:none`
+//! - 稳定标识符写作 `id:<uuid>`，保证每次加载后标识符不变
+//! - 无法识别的 `key:value` 扩展保留在标题中，以免破坏其他工具写入的信息
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use super::{end_of_day, local_datetime};
+use crate::todo::{Priority, ReminderOffset, Todo};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 将待办事项列表转换为 todo.txt 文本
+pub fn to_string<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> String {
+    let mut contents = String::new();
+    for todo in todos {
+        contents.push_str(&format_line(todo));
+        contents.push('\n');
+    }
+    contents
+}
+
+/// 解析 todo.txt 文本，忽略空行；没有创建日期的事项以 `now` 作为创建时间
+pub fn parse(contents: &str, now: DateTime<Local>) -> Vec<Todo> {
+    contents.lines().filter_map(|line| parse_line(line, now)).collect()
+}
+
+/// 将单个待办事项转换为一行 todo.txt 文本
+pub fn format_line(todo: &Todo) -> String {
+    let mut parts = Vec::new();
+    let created = todo.created_at().format(DATE_FORMAT).to_string();
+
+    if todo.is_completed() {
+        parts.push("x".to_string());
+        let completed = todo
+            .completed_at()
+            .map_or_else(|| created.clone(), |d| d.format(DATE_FORMAT).to_string());
+        parts.push(completed);
+    } else {
+        parts.push(format!("({})", priority_letter(todo.priority())));
+    }
+    parts.push(created);
+    parts.push(todo.title().split_whitespace().collect::<Vec<_>>().join(" "));
+
+    for tag in todo.tags() {
+        let tag = tag.split_whitespace().collect::<Vec<_>>().join("_");
+        if tag.starts_with('@') || tag.starts_with('+') {
+            parts.push(tag);
+        } else {
+            parts.push(format!("+{}", tag));
+        }
+    }
+    if let Some(due) = todo.due_date() {
+        parts.push(format!("due:{}", due.format(DATE_FORMAT)));
+    }
+    if todo.is_completed() {
+        parts.push(format!("pri:{}", priority_letter(todo.priority())));
+    }
+    if let Some(desc) = todo.description() {
+        parts.push(format!("desc:{}", encode_value(desc)));
+    }
+    for (name, value) in todo.fields() {
+        parts.push(format!("field.{}:{}", name, encode_value(value)));
+    }
+    for annotation in todo.annotations() {
+        parts.push(format!("note:{};{}", annotation.entry.to_rfc3339(), encode_value(&annotation.text)));
+    }
+    if let Some(reminders) = todo.reminders() {
+        parts.push(format!("remind:{}", format_reminders(reminders)));
+    }
+    if let Some(uid) = todo.uid() {
+        parts.push(format!("id:{}", uid));
+    }
+
+    parts.retain(|p| !p.is_empty());
+    parts.join(" ")
+}
+
+/// 解析一行 todo.txt 文本，空行返回 `None`；没有创建日期时以 `now` 作为创建时间
+pub fn parse_line(line: &str, now: DateTime<Local>) -> Option<Todo> {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.peek()?;
+
+    let mut completed_at = None;
+    let mut completed = false;
+    if tokens.peek() == Some(&"x") {
+        tokens.next();
+        completed = true;
+        if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+            tokens.next();
+            completed_at = local_datetime(date, 0, 0, 0);
+        }
+    }
+
+    let mut priority = None;
+    if let Some(p) = tokens.peek().and_then(|t| parse_priority_marker(t)) {
+        tokens.next();
+        priority = Some(p);
+    }
+
+    let mut created_at = None;
+    if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+        tokens.next();
+        created_at = local_datetime(date, 0, 0, 0);
+    }
+
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+    let mut due_date = None;
+    let mut description = None;
+    let mut fields = Vec::new();
+    let mut annotations = Vec::new();
+    let mut reminders = None;
+    let mut uid = None;
+    for token in tokens {
+        if let Some(project) = token.strip_prefix('+').filter(|t| !t.is_empty()) {
+            tags.push(project.to_string());
+        } else if token.len() > 1 && token.starts_with('@') {
+            tags.push(token.to_string());
+        } else if let Some(due) = token.strip_prefix("due:").and_then(parse_date) {
+            due_date = end_of_day(due);
+        } else if let Some(desc) = token.strip_prefix("desc:") {
+            description = Some(decode_value(desc));
+        } else if let Some(p) = token.strip_prefix("pri:").and_then(parse_priority_letter) {
+            priority = Some(p);
+        } else if let Some((name, value)) = token.strip_prefix("field.").and_then(|t| t.split_once(':')) {
+            fields.push((name.to_string(), decode_value(value)));
+        } else if let Some((entry, text)) = token.strip_prefix("note:").and_then(parse_annotation) {
+            annotations.push((entry, text));
+        } else if let Some(list) = token.strip_prefix("remind:").and_then(parse_reminders) {
+            reminders = Some(list);
+        } else if let Some(id) = token.strip_prefix("id:").filter(|id| !id.is_empty()) {
+            uid = Some(id.to_string());
+        } else {
+            words.push(token);
+        }
+    }
+
+    let mut todo = Todo::new_at(words.join(" "), description, created_at.unwrap_or(now));
+    todo.set_tags(tags);
+    todo.set_priority(priority.unwrap_or_default());
+    todo.set_due_date(due_date);
+    if let Some(uid) = uid {
+        todo.set_uid(uid);
+    }
+    for (name, value) in fields {
+        todo.set_field(&name, Some(value));
+    }
+    for (entry, text) in annotations {
+        todo.add_annotation_at(entry, text);
+    }
+    todo.set_reminders(reminders);
+    if completed {
+        todo.mark_as_done_at(completed_at.unwrap_or(*todo.created_at()));
+    }
+    // todo.txt 不记录修改时间
+    todo.set_modified_at(None);
+    Some(todo)
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+fn parse_priority_letter(s: &str) -> Option<Priority> {
+    let mut chars = s.chars();
+    let letter = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_priority_marker(token: &str) -> Option<Priority> {
+    token
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .and_then(parse_priority_letter)
+}
+
+/// 解析 `note:` 扩展的值，时间无效时返回 `None`
+fn parse_annotation(s: &str) -> Option<(DateTime<Local>, String)> {
+    let (entry, text) = s.split_once(';')?;
+    let entry = DateTime::parse_from_rfc3339(entry).ok()?.with_timezone(&Local);
+    Some((entry, decode_value(text)))
+}
+
+fn format_reminders(reminders: &[ReminderOffset]) -> String {
+    if reminders.is_empty() {
+        return "none".to_string();
+    }
+    reminders.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// 无法解析时返回 `None`，整个扩展保留在标题中
+fn parse_reminders(s: &str) -> Option<Vec<ReminderOffset>> {
+    ReminderOffset::parse_setting(s).ok()
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, DATE_FORMAT).ok()
+}
+
+/// 对值中的 `%` 和空白字符做百分号编码，保证它仍是一个独立的 token
+fn encode_value(value: &str) -> String {
+    let mut encoded = String::new();
+    for c in value.chars() {
+        if c == '%' || c.is_whitespace() {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+fn decode_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let todo = parse_line("(A) 2026-10-01 写周报 +工作 @办公室 due:2026-10-20 t:2026-10-15", Local::now()).unwrap();
+        assert_eq!(todo.title(), "写周报 t:2026-10-15");
+        assert_eq!(todo.priority(), Priority::High);
+        assert_eq!(todo.tags(), ["工作", "@办公室"]);
+        assert_eq!(todo.created_at().format(DATE_FORMAT).to_string(), "2026-10-01");
+        assert_eq!(todo.due_date().unwrap().format(DATE_FORMAT).to_string(), "2026-10-20");
+        assert!(!todo.is_completed());
+    }
+
+    #[test]
+    fn test_parse_completed_line() {
+        let todo = parse_line("x 2026-10-18 2026-10-01 买牛奶 pri:C", Local::now()).unwrap();
+        assert!(todo.is_completed());
+        assert_eq!(todo.completed_at().unwrap().format(DATE_FORMAT).to_string(), "2026-10-18");
+        assert_eq!(todo.priority(), Priority::Low);
+        assert!(parse_line("   ", Local::now()).is_none());
+
+        // 没有日期时以传入的时间作为创建和完成时间
+        let now = crate::clock::parse_time("2026-10-19 09:00").unwrap();
+        let todo = parse_line("x 买牛奶", now).unwrap();
+        assert_eq!(*todo.created_at(), now);
+        assert_eq!(todo.completed_at(), Some(&now));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut todo = Todo::new("写周报".to_string(), Some("100% 完成\n第二行".to_string()));
+        todo.set_priority(Priority::Low);
+        todo.add_tag("工作".to_string());
+        todo.set_field("customer", Some("ACME Inc".to_string()));
+        todo.mark_as_done();
+
+        let line = format_line(&todo);
+        assert!(line.contains("field.customer:ACME%20Inc"));
+        assert!(line.starts_with("x "));
+        assert!(line.contains("+工作"));
+        assert!(line.contains("desc:100%25%20完成%0A第二行"));
+
+        let parsed = parse_line(&line, Local::now()).unwrap();
+        assert_eq!(parsed.title(), todo.title());
+        assert_eq!(parsed.description(), todo.description());
+        assert_eq!(parsed.priority(), Priority::Low);
+        assert_eq!(parsed.tags(), todo.tags());
+        assert_eq!(parsed.field("customer"), Some("ACME Inc"));
+        assert!(parsed.is_completed());
+
+        // 标题中的换行不会拆成两个事项
+        let todo = Todo::new("两行\r\n标题".to_string(), None);
+        let contents = to_string([&todo]);
+        assert_eq!(contents.lines().count(), 1);
+        assert_eq!(parse(&contents, Local::now())[0].title(), "两行 标题");
+    }
+
+    #[test]
+    fn test_annotations_round_trip() {
+        let mut todo = Todo::new("写周报".to_string(), None);
+        todo.add_annotation("已约好会议室; 周五 3 点".to_string());
+        todo.add_annotation("100% 完成".to_string());
+        let line = format_line(&todo);
+        assert!(line.contains(";已约好会议室;%20周五%203%20点 "), "{}", line);
+
+        let parsed = parse_line(&line, Local::now()).unwrap();
+        assert_eq!(parsed.annotations(), todo.annotations());
+        assert_eq!(parsed.title(), "写周报");
+        assert_eq!(parse_line("写周报 note:昨天;开会", Local::now()).unwrap().title(), "写周报 note:昨天;开会");
+    }
+
+    #[test]
+    fn test_reminders_round_trip() {
+        let mut todo = Todo::new("写周报".to_string(), None);
+        todo.set_reminders(Some(ReminderOffset::parse_list("1d,90m").unwrap()));
+        let line = format_line(&todo);
+        assert!(line.contains(" remind:1d,90m "), "{}", line);
+        assert_eq!(parse_line(&line, Local::now()).unwrap().reminders(), todo.reminders());
+
+        todo.set_reminders(Some(Vec::new()));
+        let line = format_line(&todo);
+        assert!(line.contains(" remind:none "), "{}", line);
+        assert_eq!(parse_line(&line, Local::now()).unwrap().reminders(), Some(&[][..]));
+
+        todo.set_reminders(None);
+        assert!(!format_line(&todo).contains("remind:"));
+        assert_eq!(parse_line("写周报 remind:soon", Local::now()).unwrap().title(), "写周报 remind:soon");
+    }
+
+    #[test]
+    fn test_uid_round_trip() {
+        let todo = Todo::new("写周报".to_string(), None);
+        let uid = todo.uid().unwrap();
+        let line = format_line(&todo);
+        assert!(line.ends_with(&format!(" id:{}", uid)), "{}", line);
+        assert_eq!(parse_line(&line, Local::now()).unwrap().uid(), Some(uid));
+        assert_eq!(parse_line(&line, Local::now()).unwrap().title(), "写周报");
+    }
+}
@@ -0,0 +1,267 @@
+//! 生命周期钩子
+//!
+//! 钩子是配置文件所在目录下 `hooks` 目录（默认为 `~/.config/todo/hooks`）中的可执行文件，
+//! 文件名决定触发的事件：`on-add`、`on-modify`、`on-done` 和 `on-remove`，也可以带后缀，如 `on-add-ticket.sh`。
+//! 同一事件的多个钩子按文件名顺序依次运行，后一个钩子收到的是前一个钩子修改后的事项。
+//!
+//! 钩子从标准输入读取事项的 JSON（与数据文件中的格式相同），每个事项一行：
+//!
+//! | 事件        | 标准输入         | 标准输出的第一行       |
+//! |-------------|------------------|------------------------|
+//! | `on-add`    | 新的事项         | 修改后的事项（可省略） |
+//! | `on-modify` | 修改前、修改后   | 修改后的事项（可省略） |
+//! | `on-done`   | 完成前、完成后   | 修改后的事项（可省略） |
+//! | `on-remove` | 删除的事项       | —                      |
+//!
+//! 标准输出中的其他行作为提示显示给用户。钩子以非零状态退出时拒绝这次操作，
+//! 提示作为拒绝的原因显示，数据文件不会被修改。运行钩子时环境变量 `TODO_EVENT` 为事件名称，
+//! `TODO_FILE` 为数据文件路径。
+//!
+//! 钩子在命令行的 `add`、`edit`、`note`、`done`、`undone`、`remove` 和 `tag rename`/`tag merge`
+//! （每个修改过的事项运行一次 `on-modify`）中运行，HTTP 和 JSON-RPC 接口中对应的创建、修改、
+//! 完成和删除也会运行钩子。导入、同步和合并不会触发钩子。
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use thiserror::Error;
+
+use crate::todo::Todo;
+
+/// 触发钩子的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Add,
+    Modify,
+    Done,
+    Remove,
+}
+
+impl Event {
+    /// 钩子文件名的前缀
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Add => "on-add",
+            Event::Modify => "on-modify",
+            Event::Done => "on-done",
+            Event::Remove => "on-remove",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("运行钩子 {0} 时出错: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("钩子 {hook} 拒绝了这次操作: {message}")]
+    Rejected { hook: String, message: String },
+    #[error("钩子 {hook} 的输出无效: {message}")]
+    InvalidOutput { hook: String, message: String },
+}
+
+/// 钩子运行的结果
+#[derive(Debug)]
+pub struct Outcome<T> {
+    /// 钩子修改后的事项
+    pub value: T,
+    /// 钩子输出的提示
+    pub messages: Vec<String>,
+}
+
+/// 一个钩子目录
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    /// `None` 表示不运行钩子
+    dir: Option<PathBuf>,
+    data_file: PathBuf,
+}
+
+impl Hooks {
+    pub fn new(dir: PathBuf, data_file: &Path) -> Self {
+        Hooks {
+            dir: Some(dir),
+            data_file: data_file.to_path_buf(),
+        }
+    }
+
+    /// 不运行任何钩子，用于 `--no-hooks`
+    pub fn disabled() -> Self {
+        Hooks {
+            dir: None,
+            data_file: PathBuf::new(),
+        }
+    }
+
+    /// 事件对应的钩子，按文件名排序；目录不存在时没有钩子，不可执行的文件会被忽略
+    pub fn scripts(&self, event: Event) -> Result<Vec<PathBuf>, HookError> {
+        let Some(dir) = &self.dir else {
+            return Ok(Vec::new());
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(HookError::Io(dir.clone(), e)),
+        };
+        let mut scripts = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| HookError::Io(dir.clone(), e))?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let matched = name
+                .strip_prefix(event.name())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '.']));
+            if matched && is_executable(&path) {
+                scripts.push(path);
+            }
+        }
+        scripts.sort();
+        Ok(scripts)
+    }
+
+    /// 新建事项前运行 `on-add`
+    pub fn on_add(&self, todo: Todo) -> Result<Outcome<Todo>, HookError> {
+        self.run_chain(Event::Add, None, todo)
+    }
+
+    /// 修改事项后、保存前运行 `on-modify` 或 `on-done`
+    pub fn on_modify(&self, event: Event, before: &Todo, after: Todo) -> Result<Outcome<Todo>, HookError> {
+        self.run_chain(event, Some(before), after)
+    }
+
+    /// 删除事项前运行 `on-remove`，钩子不能修改事项
+    pub fn on_remove(&self, todo: &Todo) -> Result<Outcome<()>, HookError> {
+        let mut messages = Vec::new();
+        for script in self.scripts(Event::Remove)? {
+            messages.extend(self.run(&script, Event::Remove, &[todo])?);
+        }
+        Ok(Outcome { value: (), messages })
+    }
+
+    fn run_chain(&self, event: Event, before: Option<&Todo>, mut todo: Todo) -> Result<Outcome<Todo>, HookError> {
+        let mut messages = Vec::new();
+        for script in self.scripts(event)? {
+            let input: Vec<&Todo> = before.into_iter().chain([&todo]).collect();
+            let mut lines = self.run(&script, event, &input)?.into_iter().peekable();
+            if let Some(first) = lines.next_if(|line| line.starts_with('{')) {
+                let invalid = |message: String| HookError::InvalidOutput {
+                    hook: display_name(&script),
+                    message,
+                };
+                let modified: Todo = serde_json::from_str(&first).map_err(|e| invalid(e.to_string()))?;
+                if modified.uid() != todo.uid() {
+                    return Err(invalid("不能修改事项的标识符".to_string()));
+                }
+                todo = modified;
+            }
+            messages.extend(lines);
+        }
+        Ok(Outcome { value: todo, messages })
+    }
+
+    /// 运行一个钩子，返回标准输出中的非空行；钩子的标准错误直接显示
+    fn run(&self, script: &Path, event: Event, input: &[&Todo]) -> Result<Vec<String>, HookError> {
+        let mut stdin = String::new();
+        for todo in input {
+            stdin.push_str(&serde_json::to_string(todo).expect("待办事项总能序列化为 JSON"));
+            stdin.push('\n');
+        }
+        let mut child = Command::new(script)
+            .env("TODO_EVENT", event.name())
+            .env("TODO_FILE", &self.data_file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| HookError::Io(script.to_path_buf(), e))?;
+        // 钩子可以不读取标准输入，此时写入失败不算错误
+        if let Some(mut pipe) = child.stdin.take() {
+            let _ = pipe.write_all(stdin.as_bytes());
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| HookError::Io(script.to_path_buf(), e))?;
+
+        let lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !output.status.success() {
+            let message = if lines.is_empty() {
+                output.status.to_string()
+            } else {
+                lines.join("\n")
+            };
+            return Err(HookError::Rejected {
+                hook: display_name(script),
+                message,
+            });
+        }
+        Ok(lines)
+    }
+}
+
+fn display_name(script: &Path) -> String {
+    script
+        .file_name()
+        .map_or_else(|| script.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// 是否为可执行文件，插件的查找也使用这个规则
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn write_script(dir: &Path, name: &str, body: &str) {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_scripts_and_chain() {
+        let dir = std::env::temp_dir().join(format!("todo_hooks_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        write_script(&dir, "on-add-2-suffix", "sed 's/写周报/写月报/'\necho 已改名");
+        write_script(&dir, "on-add-1", "cat");
+        write_script(&dir, "on-addition", "exit 1");
+        fs::write(dir.join("on-add-3"), "#!/bin/sh\nexit 1\n").unwrap();
+        write_script(&dir, "on-remove", "echo 不能删除\nexit 2");
+
+        let hooks = Hooks::new(dir.clone(), Path::new("todo.json"));
+        let names: Vec<String> = hooks.scripts(Event::Add).unwrap().iter().map(|p| display_name(p)).collect();
+        assert_eq!(names, ["on-add-1", "on-add-2-suffix"]);
+
+        let todo = Todo::new("写周报".to_string(), None);
+        let outcome = hooks.on_add(todo.clone()).unwrap();
+        assert_eq!(outcome.value.title(), "写月报");
+        assert_eq!(outcome.value.uid(), todo.uid());
+        assert_eq!(outcome.messages, ["已改名"]);
+
+        match hooks.on_remove(&todo) {
+            Err(HookError::Rejected { hook, message }) => {
+                assert_eq!(hook, "on-remove");
+                assert_eq!(message, "不能删除");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(Hooks::disabled().on_remove(&todo).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+//! 从其他格式导入待办事项
+//!
+//! 带标识符的格式（JSON、todo.txt、iCalendar、Taskwarrior）中标识符相同的事项会被更新；
+//! Markdown 和 Org 清单没有标识符，按标题与已有的待办事项合并。
+
+use thiserror::Error;
+
+use crate::format::{self, taskwarrior::Unmapped};
+use crate::storage::TodoStorage;
+use crate::todo::Todo;
+
+/// 支持的导入格式名称
+pub const FORMATS: &[&str] = &["json", "todotxt", "ics", "markdown", "org", "taskwarrior"];
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("解析待办事项时出错: {0}")]
+    InvalidJson(serde_json::Error),
+    #[error("解析 Taskwarrior 数据时出错: {0}")]
+    InvalidTaskwarrior(serde_json::Error),
+    #[error("不支持的导入格式: {0}")]
+    UnsupportedFormat(String),
+}
+
+/// 导入的结果
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    /// 无法映射、没有导入的字段
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// 将指定格式的内容导入到存储中，不会保存存储
+pub fn import(storage: &mut TodoStorage, format: &str, contents: &str) -> Result<ImportSummary, ImportError> {
+    let mut summary = ImportSummary::default();
+    let now = storage.clock().now();
+    if format == "markdown" || format == "org" {
+        let items = if format == "markdown" {
+            format::markdown::parse(contents)
+        } else {
+            format::org::parse(contents)
+        };
+
+        // 清单按标题与已有的待办事项合并
+        for item in items {
+            match storage.find_by_title(&item.title) {
+                Some(index) => {
+                    let mut todo = storage.todos()[index].clone();
+                    item.apply_to(&mut todo, now);
+                    storage.update(index, todo).expect("索引来自 find_by_title");
+                    summary.updated += 1;
+                }
+                None => {
+                    storage.add(item.into_todo(now));
+                    summary.added += 1;
+                }
+            }
+        }
+        return Ok(summary);
+    }
+
+    let todos: Vec<Todo> = match format {
+        "json" => serde_json::from_str(contents).map_err(ImportError::InvalidJson)?,
+        "todotxt" => format::todotxt::parse(contents, now),
+        "ics" => format::ical::parse(contents, now),
+        "taskwarrior" => {
+            let import = format::taskwarrior::parse(contents, now).map_err(ImportError::InvalidTaskwarrior)?;
+            summary.unmapped = import.unmapped;
+            import.todos
+        }
+        _ => return Err(ImportError::UnsupportedFormat(format.to_string())),
+    };
+
+    // 标识符相同的待办事项会被更新，而不是重复添加
+    for todo in todos {
+        if storage.upsert(todo) {
+            summary.updated += 1;
+        } else {
+            summary.added += 1;
+        }
+    }
+    Ok(summary)
+}
@@ -0,0 +1,60 @@
+//! 待办事项管理器的核心库
+//!
+//! 命令行程序 `todo_manager` 建立在这个库之上，其他工具可以直接使用相同的数据模型、存储、筛选和导出：
+//!
+//! - [`Todo`]、[`Priority`]：待办事项及其优先级
+//! - [`TodoStorage`]：读写数据文件（JSON 或 todo.txt，可以加密）
+//! - [`Filter`]、[`SortKey`]：与 `list` 相同的筛选和排序
+//! - [`Exporter`]：导出为 JSON、CSV、HTML 等格式，通过 [`export::exporter`] 按名称创建
+//! - [`Config`]：`~/.config/todo/config.toml` 中的配置
+//! - [`AppError`]：命令行程序的错误及其退出状态
+//!
+//! ```
+//! use todo_manager::clock::SystemClock;
+//! use todo_manager::{Filter, Priority, SortKey, Todo};
+//!
+//! let mut report = Todo::new("写周报".to_string(), None);
+//! report.set_priority(Priority::High);
+//! let todos = vec![Todo::new("买牛奶".to_string(), None), report];
+//!
+//! let filter = Filter {
+//!     sort: Some(SortKey::Priority),
+//!     ..Default::default()
+//! };
+//! let titles: Vec<&str> = filter.apply(&todos, &SystemClock).into_iter().map(|(_, todo)| todo.title()).collect();
+//! assert_eq!(titles, ["写周报", "买牛奶"]);
+//! ```
+
+pub mod api;
+pub mod cli;
+pub mod clock;
+pub mod collate;
+pub mod config;
+pub mod crypto;
+pub mod error;
+pub mod export;
+pub mod fields;
+pub mod filter;
+pub mod format;
+pub mod hooks;
+pub mod import;
+pub mod merge;
+pub mod plugin;
+pub mod remind;
+pub mod rpc;
+pub mod search;
+pub mod server;
+pub mod storage;
+pub mod sync;
+pub mod table;
+pub mod tags;
+pub mod todo;
+pub mod urgency;
+pub mod view;
+
+pub use config::Config;
+pub use error::AppError;
+pub use export::{ExportError, ExportOptions, Exporter, Field, Record};
+pub use filter::{Filter, SortKey};
+pub use storage::{StorageError, StorageFormat, TodoStorage};
+pub use todo::{Annotation, Priority, Todo};
@@ -1,44 +1,212 @@
-mod cli;
-mod storage;
-mod todo;
-
 use std::env;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process;
-use chrono::TimeZone;
+use std::sync::Arc;
+use clap::ArgMatches;
 
-use cli::build_cli;
-use storage::{StorageError, TodoStorage};
-use todo::Todo;
+use todo_manager::cli::{self, build_cli, parse_filter};
+use todo_manager::clock::{self, Clock};
+use todo_manager::config::{self, Config};
+use todo_manager::crypto::KeySource;
+use todo_manager::export::{self, ExportOptions, Field, Record};
+use todo_manager::hooks::{self, Event, Hooks};
+use todo_manager::storage::{StorageError, StorageFormat, TodoStorage};
+use todo_manager::todo::{self, Todo};
+use todo_manager::AppError;
+use todo_manager::{fields, filter, format, import, merge, plugin, remind, rpc, search, server, sync, table, tags, view};
 
 fn main() {
-    // 解析命令行参数
-    let matches = build_cli().get_matches();
+    let matches = parse_args().unwrap_or_else(|e| exit_with(&e, json_requested(env::args())));
+    let json = matches.value_of("output") == Some("json");
+    if let Err(e) = run(matches) {
+        exit_with(&e, json);
+    }
+}
+
+/// 参数解析失败时没有匹配结果，只能从子命令之前的全局选项中查找 `--output json`
+fn json_requested(args: impl Iterator<Item = String>) -> bool {
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => return args.next().as_deref() == Some("json"),
+            "--output=json" => return true,
+            "--file" | "--config" | "--storage" | "--key-file" => {
+                args.next();
+            }
+            flag if flag.starts_with('-') => {}
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// 输出错误并以错误类别对应的状态退出
+fn exit_with(error: &AppError, json: bool) -> ! {
+    if json {
+        eprintln!("{}", error.to_json());
+    } else {
+        eprintln!("{}", error);
+    }
+    process::exit(error.exit_code())
+}
+
+/// 在标准错误中输出警告，`details` 逐行列在警告之后
+///
+/// `--output json` 时输出一行 `{"warning": 警告, "details": [...]}`，与错误对象一样可以逐行解析。
+fn warn(json: bool, message: &str, details: &[String]) {
+    if json {
+        let mut warning = serde_json::json!({ "warning": message });
+        if !details.is_empty() {
+            warning["details"] = serde_json::json!(details);
+        }
+        eprintln!("{}", warning);
+    } else if details.is_empty() {
+        eprintln!("{}", message);
+    } else {
+        eprintln!("{}：", message);
+        for detail in details {
+            eprintln!("{}", detail);
+        }
+    }
+}
+
+/// 解析命令行参数，帮助中列出 PATH 中的插件
+///
+/// 只有显示帮助时才搜索 PATH，其他命令不需要遍历 PATH 中的目录。
+fn parse_args() -> Result<ArgMatches<'static>, AppError> {
+    match build_cli().get_matches_safe() {
+        Err(e) if e.kind == clap::ErrorKind::HelpDisplayed => {
+            let plugins = plugin::discover();
+            if plugins.is_empty() {
+                e.exit();
+            }
+            let plugin_help = plugin::help(&plugins);
+            checked_matches(build_cli().after_help(plugin_help.as_str()).get_matches_safe())
+        }
+        result => checked_matches(result),
+    }
+}
+
+/// 显示帮助和版本时直接退出，其他解析错误作为用法错误返回
+fn checked_matches(result: clap::Result<ArgMatches<'static>>) -> Result<ArgMatches<'static>, AppError> {
+    result.map_err(|e| match e.kind {
+        clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => e.exit(),
+        _ => AppError::Usage(e),
+    })
+}
 
-    // 获取用户主目录，用于存储待办事项文件
-    let home_dir = env::var("HOME").unwrap_or_else(|_| {
-        eprintln!("无法获取用户主目录");
-        process::exit(1);
-    });
+fn run(mut matches: ArgMatches<'static>) -> Result<(), AppError> {
+    // TODO_NOW 指定当前时间，用于重现与时间有关的输出
+    let clock = clock::from_env().map_err(AppError::Parse)?;
+
+    // 读取配置文件，其中包含默认设置、自定义字段、视图和上下文
+    let config_path = matches.value_of("config").map(PathBuf::from).or_else(Config::default_path);
+    let config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+
+    // `view <名称>` 展开为使用视图选项的 list 命令
+    if let ("view", Some(sub_matches)) = matches.subcommand() {
+        if let (Some(name), None) = (sub_matches.value_of("name"), sub_matches.subcommand_name()) {
+            let Some(view) = config.views.get(name) else {
+                return Err(AppError::NotFound(format!("未定义的视图: {}（使用 view list 查看保存的视图）", name)));
+            };
+            let argv: Vec<String> = env::args().collect();
+            matches = checked_matches(build_cli().get_matches_from_safe(view::expand(&argv, name, view)))?;
+        }
+    }
+
+    // 数据文件的存储格式，未指定时根据文件扩展名推断
+    let storage_format = match matches.value_of("storage") {
+        Some("todotxt") => Some(StorageFormat::TodoTxt),
+        Some(_) => Some(StorageFormat::Json),
+        None => None,
+    };
+
+    // 加密密钥：--key-file 优先，其次是 TODO_PASSPHRASE 环境变量
+    let key = match matches.value_of("key-file") {
+        Some(path) => Some(KeySource::KeyFile(PathBuf::from(path))),
+        None => env::var("TODO_PASSPHRASE").ok().map(KeySource::Passphrase),
+    };
+
+    // 以 JSON 输出错误时标准错误中只能有 JSON 对象
+    let json = matches.value_of("output") == Some("json");
+
+    // 合并操作的是命令行中指定的文件，不需要加载默认的数据文件
+    if let ("merge", Some(sub_matches)) = matches.subcommand() {
+        return run_merge(sub_matches, storage_format, key, &clock, json);
+    }
+
+    // 视图、上下文和设置只修改配置文件
+    match matches.subcommand() {
+        ("config", Some(sub_matches)) => {
+            return run_config(sub_matches, &config, config_path.as_deref());
+        }
+        ("view", Some(sub_matches)) => {
+            return run_view(sub_matches, &config, config_path.as_deref());
+        }
+        ("context", Some(sub_matches)) => {
+            return run_context(sub_matches, &config, config_path.as_deref());
+        }
+        _ => {}
+    }
 
-    // 创建待办事项存储文件路径
-    let todo_file = PathBuf::from(home_dir).join(".todo.json");
+    // 创建待办事项存储文件路径，--file 优先，其次是配置文件，都未指定时存放在用户主目录下
+    let todo_file = match matches.value_of("file").map(PathBuf::from).or_else(|| config.data_file()) {
+        Some(path) => path,
+        None => {
+            let home_dir = env::var("HOME").map_err(|_| AppError::NotFound("无法获取用户主目录".to_string()))?;
+            let file_name = match storage_format {
+                Some(StorageFormat::TodoTxt) => "todo.txt",
+                _ => ".todo.json",
+            };
+            PathBuf::from(home_dir).join(file_name)
+        }
+    };
     
     // 创建待办事项存储
-    let mut storage = TodoStorage::new(todo_file);
+    let mut storage = match storage_format {
+        Some(format) => TodoStorage::with_format(todo_file, format),
+        None => TodoStorage::new(todo_file),
+    };
+    
+    storage.set_key(key.clone());
+    storage.set_clock(clock.clone());
     
     // 尝试从文件加载待办事项
-    match storage.load() {
-        Ok(_) => {},
+    let file_exists = match storage.load() {
+        Ok(_) => true,
         Err(StorageError::FileNotFound) => {
-            println!("未找到待办事项文件，将创建新文件。");
+            // 首次运行的提示不是警告，以 JSON 输出时不显示
+            if !json {
+                eprintln!("未找到待办事项文件，将创建新文件。");
+            }
+            false
         },
-        Err(e) => {
-            eprintln!("加载待办事项时出错: {}", e);
-            process::exit(1);
-        }
+        Err(StorageError::KeyRequired) => {
+            // 数据文件已加密但没有提供密钥时，在终端中询问密码
+            let passphrase = read_passphrase("请输入数据文件的密码: ")?;
+            storage.set_key(Some(KeySource::Passphrase(passphrase)));
+            storage.load().map_err(AppError::storage("加载待办事项时出错"))?;
+            true
+        },
+        Err(e) => return Err(AppError::storage("加载待办事项时出错")(e)),
+    };
+    
+    // 已有的明文文件保持明文，只有 encrypt 命令会将其转换为加密文件
+    if file_exists && !storage.is_encrypted() {
+        storage.set_key(None);
     }
 
+    // 钩子放在配置文件所在目录下的 hooks 目录中
+    let hooks = match config_path.as_deref().and_then(Path::parent) {
+        Some(dir) if !matches.is_present("no-hooks") => Hooks::new(dir.join("hooks"), storage.file_path()),
+        _ => Hooks::disabled(),
+    };
+
     // 处理子命令
     match matches.subcommand() {
         ("add", Some(sub_matches)) => {
@@ -50,227 +218,239 @@ fn main() {
                 Some("low") => todo::Priority::Low,
                 Some("medium") => todo::Priority::Medium,
                 Some("high") => todo::Priority::High,
-                _ => todo::Priority::default(),
+                _ => config.default_priority(),
             };
             
             // 解析截止日期
-            let due_date = if let Some(date_str) = sub_matches.value_of("due") {
-                match chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                    Ok(date) => {
-                        let datetime = date.and_hms_opt(23, 59, 59).unwrap();
-                        Some(chrono::Local::now().timezone().from_local_datetime(&datetime).unwrap())
-                    },
-                    Err(_) => {
-                        eprintln!("无效的日期格式，应为YYYY-MM-DD");
-                        process::exit(1);
-                    }
-                }
-            } else {
-                None
-            };
+            let due_date = sub_matches.value_of("due").map(|date_str| parse_due_date(date_str, &config)).transpose()?;
             
             // 解析标签
             let tags = if let Some(tags_str) = sub_matches.value_of("tags") {
-                tags_str.split(',').map(|s| s.trim().to_string()).collect()
+                tags_str.split(',').map(tags::normalize).filter(|t| !t.is_empty()).collect()
             } else {
                 Vec::new()
             };
             
             // 创建待办事项
-            let mut todo = Todo::new(title, description);
+            let mut todo = Todo::new_at(title, description, clock.now());
+            todo.set_due_date(due_date);
             todo.set_priority(priority);
-            if let Some(due) = due_date {
-                todo.set_due_date(Some(due));
-            }
             for tag in tags {
                 todo.add_tag(tag);
             }
+            if let Some(remind) = sub_matches.value_of("remind") {
+                todo.set_reminders(Some(parse_reminders(remind)?));
+            }
+            set_fields(&mut todo, sub_matches, &config)?;
+            
+            // 加上当前上下文的默认标签
+            if let Some((_, context)) = view::current_context(&config).filter(|_| !sub_matches.is_present("no-context")) {
+                context.apply_tags(&mut todo);
+            }
+            todo.set_modified_at(None);
             
+            let todo = checked_hook(hooks.on_add(todo))?;
             storage.add(todo);
             
-            if let Err(e) = storage.save() {
-                eprintln!("保存待办事项时出错: {}", e);
-                process::exit(1);
-            }
+            save(&storage)?;
             
             println!("待办事项已添加。");
         },
         ("list", Some(sub_matches)) => {
-            let mut todos: Vec<&Todo> = storage.todos().iter().collect();
+            // 筛选并排序，未指定 --all 时不显示已完成的待办事项
+            let mut filter = parse_filter(sub_matches, &config);
+            filter.context = load_context_filter(sub_matches, &config)?;
+            let todos = filter.apply(storage.todos(), clock.as_ref());
             
-            // 筛选未完成的待办事项（除非指定--all参数）
-            if !sub_matches.is_present("all") {
-                todos.retain(|todo| !todo.is_completed());
+            if filter.context.is_some() {
+                println!("当前上下文: {}", config.context.as_deref().unwrap_or_default());
             }
-            
-            // 按优先级筛选
-            if let Some(priority_str) = sub_matches.value_of("priority") {
-                let priority = match priority_str {
-                    "low" => todo::Priority::Low,
-                    "medium" => todo::Priority::Medium,
-                    "high" => todo::Priority::High,
-                    _ => todo::Priority::Medium,
-                };
-                todos.retain(|todo| todo.priority() == priority);
-            }
-            
-            // 按标签筛选
-            if let Some(tag) = sub_matches.value_of("tag") {
-                todos.retain(|todo| todo.tags().iter().any(|t| t == tag));
+            if todos.is_empty() {
+                println!("没有符合条件的待办事项。");
+                return Ok(());
             }
             
-            // 筛选有截止日期的待办事项
-            if sub_matches.is_present("due") {
-                todos.retain(|todo| todo.due_date().is_some());
+            let now = clock.now();
+            // 指定 --columns 时默认以表格显示
+            let default_style = if sub_matches.is_present("columns") { "table" } else { config.list.style.as_str() };
+            if sub_matches.value_of("style").unwrap_or(default_style) == "table" {
+                let columns: Vec<String> = match sub_matches.value_of("columns") {
+                    Some(list) => list.split(',').map(|c| c.trim().to_string()).collect(),
+                    None => config.list.table_columns.clone(),
+                };
+                let table = table::todo_table(&todos, &columns, &filter, &config, &now);
+                // 输出不是终端且没有设置 COLUMNS 时不限制宽度，便于交给其他程序处理
+                print!("{}", table.render(table::terminal_width(), |color, text| config.colors.paint(color, text)));
+                return Ok(());
             }
             
-            // 筛选已过期的待办事项
-            if sub_matches.is_present("overdue") {
-                let now = chrono::Local::now();
-                todos.retain(|todo| {
-                    if let Some(due) = todo.due_date() {
-                        due < &now && !todo.is_completed()
-                    } else {
-                        false
-                    }
-                });
-            }
-            
-            // 排序
-            if let Some(sort_by) = sub_matches.value_of("sort") {
-                match sort_by {
-                    "priority" => {
-                        todos.sort_by(|a, b| b.priority().cmp(&a.priority()));
-                    },
-                    "date" => {
-                        todos.sort_by(|a, b| {
-                            match (a.due_date(), b.due_date()) {
-                                (Some(a_date), Some(b_date)) => a_date.cmp(b_date),
-                                (Some(_), None) => std::cmp::Ordering::Less,
-                                (None, Some(_)) => std::cmp::Ordering::Greater,
-                                (None, None) => a.created_at().cmp(b.created_at()),
+            let colors = &config.colors;
+            let datetime_format = config.format.datetime.as_str();
+            println!("待办事项列表：");
+            for (i, todo) in todos {
+                let status = if todo.is_completed() { "[✓]" } else { "[ ]" };
+                let priority_str = match todo.priority() {
+                    todo::Priority::Low => "[低]",
+                    todo::Priority::Medium => "[中]",
+                    todo::Priority::High => "[高]",
+                };
+                let priority_str = colors.paint(colors.priority(todo.priority()), priority_str);
+                println!("{} {}   {}. {}", status, priority_str, i, todo.title());
+                
+                // 按配置项 list.columns 的顺序显示
+                for column in &config.list.columns {
+                    match column.as_str() {
+                        "description" => if let Some(desc) = todo.description() {
+                            println!("   描述: {}", desc);
+                        },
+                        "created" => println!("   创建时间: {}", todo.created_at().format(datetime_format)),
+                        "due" => if let Some(due) = todo.due_date() {
+                            let status = if due < &now && !todo.is_completed() {
+                                colors.paint(&colors.overdue, "已过期")
+                            } else {
+                                "未过期".to_string()
+                            };
+                            println!("   截止日期: {} ({})", due.format(datetime_format), status);
+                        },
+                        "tags" if !todo.tags().is_empty() => {
+                            println!("   标签: {}", todo.tags().join(", "));
+                        },
+                        "fields" if !todo.fields().is_empty() => {
+                            let fields: Vec<String> = todo.fields().iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                            println!("   字段: {}", fields.join(", "));
+                        },
+                        "annotations" if !todo.annotations().is_empty() => {
+                            println!("   注释: {} 条", todo.annotations().len());
+                        },
+                        "urgency" if !todo.is_completed() => {
+                            println!("   紧急度: {:.1}", filter.weights.score(todo, &now).total());
+                        },
+                        "reminders" => match todo.reminders() {
+                            Some([]) => println!("   提醒: 不提醒"),
+                            Some(offsets) => {
+                                let offsets: Vec<String> = offsets.iter().map(|o| o.to_string()).collect();
+                                println!("   提醒: 提前 {}", offsets.join(", "));
                             }
-                        });
-                    },
-                    "title" => {
-                        todos.sort_by(|a, b| a.title().cmp(b.title()));
-                    },
-                    _ => {}
+                            None => {}
+                        },
+                        _ => {}
+                    }
                 }
+                
+                println!();
             }
+        },
+        ("next", Some(sub_matches)) => {
+            // 只在未完成的事项中按紧急度选择
+            let mut filter = parse_filter(sub_matches, &config);
+            filter.context = load_context_filter(sub_matches, &config)?;
+            filter.include_completed = false;
+            filter.sort = Some(filter::SortKey::Urgency);
             
-            if todos.is_empty() {
-                println!("没有符合条件的待办事项。");
-                return;
+            let Some((i, todo)) = filter.apply(storage.todos(), clock.as_ref()).into_iter().next() else {
+                println!("没有需要做的待办事项。");
+                return Ok(());
+            };
+            println!("{}. {}", i, todo.title());
+            if let Some(desc) = todo.description() {
+                println!("   描述: {}", desc);
+            }
+            if let Some(due) = todo.due_date() {
+                println!("   截止日期: {}", due.format(&config.format.datetime));
+            }
+            if !todo.tags().is_empty() {
+                println!("   标签: {}", todo.tags().join(", "));
+            }
+            println!("   紧急度: {}", filter.weights.score(todo, &clock.now()));
+        },
+        ("search", Some(sub_matches)) => {
+            let query: Vec<&str> = sub_matches.values_of("query").unwrap().collect();
+            let include_completed = sub_matches.is_present("all");
+            let candidates = storage
+                .todos()
+                .iter()
+                .enumerate()
+                .filter(|(_, todo)| include_completed || !todo.is_completed());
+            let mut hits = search::search(candidates, &query.join(" "));
+            if let Some(limit) = sub_matches.value_of("limit").and_then(|n| n.parse().ok()) {
+                hits.truncate(limit);
+            }
+            if hits.is_empty() {
+                println!("没有找到匹配的待办事项。");
+                return Ok(());
             }
             
-            println!("待办事项列表：");
-            for (i, todo) in todos.iter().enumerate() {
+            // 匹配的部分使用配置项 colors.highlight 的颜色
+            let colors = &config.colors;
+            let mark = |text: &str| colors.paint(&colors.highlight, text);
+            let highlighted = |hit: &search::Hit, field, text: &str| match hit.highlights.get(&field) {
+                Some(ranges) => search::highlight(text, ranges, mark),
+                None => text.to_string(),
+            };
+            println!("找到 {} 个待办事项：", hits.len());
+            for hit in &hits {
+                let todo = hit.todo;
                 let status = if todo.is_completed() { "[✓]" } else { "[ ]" };
-                let priority_str = match todo.priority() {
-                    todo::Priority::Low => "[低]  ",
-                    todo::Priority::Medium => "[中]  ",
-                    todo::Priority::High => "[高]  ",
-                };
-                println!("{} {} {}. {}", status, priority_str, i, todo.title());
-                
-                if let Some(desc) = todo.description() {
-                    println!("   描述: {}", desc);
+                println!("{} {}. {}", status, hit.id, highlighted(hit, search::Field::Title, todo.title()));
+                if !todo.tags().is_empty() {
+                    let tags: Vec<String> = todo
+                        .tags()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, tag)| highlighted(hit, search::Field::Tag(i), tag))
+                        .collect();
+                    println!("   标签: {}", tags.join(", "));
                 }
-                
-                println!("   创建时间: {}", todo.created_at().format("%Y-%m-%d %H:%M:%S"));
-                
-                if let Some(due) = todo.due_date() {
-                    let now = chrono::Local::now();
-                    let status = if due < &now && !todo.is_completed() {
-                        "已过期"
-                    } else {
-                        "未过期"
-                    };
-                    println!("   截止日期: {} ({})", due.format("%Y-%m-%d %H:%M:%S"), status);
+                if let Some(desc) = todo.description() {
+                    println!("   描述: {}", highlighted(hit, search::Field::Description, desc));
                 }
-                
-                if !todo.tags().is_empty() {
-                    println!("   标签: {}", todo.tags().join(", "));
+                // 注释可能很多，只显示匹配的几条
+                for (i, annotation) in todo.annotations().iter().enumerate() {
+                    if hit.highlights.contains_key(&search::Field::Annotation(i)) {
+                        println!("   注释: {}", highlighted(hit, search::Field::Annotation(i), &annotation.text));
+                    }
                 }
-                
-                println!("");
+                println!();
             }
         },
         ("done", Some(sub_matches)) => {
-            let id = sub_matches.value_of("id").unwrap().parse::<usize>().unwrap_or_else(|_| {
-                eprintln!("无效的ID");
-                process::exit(1);
-            });
-            
-            match storage.mark_done(id) {
-                Ok(_) => {
-                    if let Err(e) = storage.save() {
-                        eprintln!("保存待办事项时出错: {}", e);
-                        process::exit(1);
-                    }
-                    println!("待办事项已标记为完成。");
-                },
-                Err(e) => {
-                    eprintln!("标记待办事项时出错: {}", e);
-                    process::exit(1);
-                }
-            }
+            let id = parse_id(sub_matches)?;
+            
+            let before = storage.get_todo(id)?;
+            let mut todo = before.clone();
+            todo.mark_as_done_at(clock.now());
+            let todo = checked_hook(hooks.on_modify(Event::Done, before, todo))?;
+            storage.update(id, todo)?;
+            
+            save(&storage)?;
+            println!("待办事项已标记为完成。");
         },
         ("undone", Some(sub_matches)) => {
-            let id = sub_matches.value_of("id").unwrap().parse::<usize>().unwrap_or_else(|_| {
-                eprintln!("无效的ID");
-                process::exit(1);
-            });
-            
-            match storage.mark_undone(id) {
-                Ok(_) => {
-                    if let Err(e) = storage.save() {
-                        eprintln!("保存待办事项时出错: {}", e);
-                        process::exit(1);
-                    }
-                    println!("待办事项已标记为未完成。");
-                },
-                Err(e) => {
-                    eprintln!("标记待办事项时出错: {}", e);
-                    process::exit(1);
-                }
-            }
+            let id = parse_id(sub_matches)?;
+            
+            let before = storage.get_todo(id)?;
+            let mut todo = before.clone();
+            todo.mark_as_undone();
+            let todo = checked_hook(hooks.on_modify(Event::Modify, before, todo))?;
+            storage.update(id, todo)?;
+            
+            save(&storage)?;
+            println!("待办事项已标记为未完成。");
         },
         ("remove", Some(sub_matches)) => {
-            let id = sub_matches.value_of("id").unwrap().parse::<usize>().unwrap_or_else(|_| {
-                eprintln!("无效的ID");
-                process::exit(1);
-            });
-            
-            match storage.remove(id) {
-                Ok(_) => {
-                    if let Err(e) = storage.save() {
-                        eprintln!("保存待办事项时出错: {}", e);
-                        process::exit(1);
-                    }
-                    println!("待办事项已删除。");
-                },
-                Err(e) => {
-                    eprintln!("删除待办事项时出错: {}", e);
-                    process::exit(1);
-                }
-            }
+            let id = parse_id(sub_matches)?;
+            
+            checked_hook(hooks.on_remove(storage.get_todo(id)?))?;
+            storage.remove(id)?;
+            save(&storage)?;
+            println!("待办事项已删除。");
         },
         ("edit", Some(sub_matches)) => {
-            let id = sub_matches.value_of("id").unwrap().parse::<usize>().unwrap_or_else(|_| {
-                eprintln!("无效的ID");
-                process::exit(1);
-            });
-            
-            // 获取待办事项的可变引用
-            let todo = match storage.get_todo_mut(id) {
-                Ok(todo) => todo,
-                Err(e) => {
-                    eprintln!("获取待办事项时出错: {}", e);
-                    process::exit(1);
-                }
-            };
+            let id = parse_id(sub_matches)?;
+            
+            // 在副本上修改，通过钩子后再写回
+            let before = storage.get_todo(id)?;
+            let mut todo = before.clone();
             
             // 更新标题
             if let Some(title) = sub_matches.value_of("title") {
@@ -298,166 +478,637 @@ fn main() {
                 if date_str.to_lowercase() == "none" {
                     todo.set_due_date(None);
                 } else {
-                    match chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                        Ok(date) => {
-                            let datetime = date.and_hms_opt(23, 59, 59).unwrap();
-                            todo.set_due_date(Some(chrono::Local::now().timezone().from_local_datetime(&datetime).unwrap()));
-                        },
-                        Err(_) => {
-                            eprintln!("无效的日期格式，应为YYYY-MM-DD");
-                            process::exit(1);
-                        }
-                    }
+                    todo.set_due_date(Some(parse_due_date(date_str, &config)?));
                 }
             }
             
             // 添加标签
             if let Some(tag) = sub_matches.value_of("add-tag") {
-                todo.add_tag(tag.to_string());
+                todo.add_tag(tags::normalize(tag));
             }
             
             // 移除标签
             if let Some(tag) = sub_matches.value_of("remove-tag") {
-                todo.remove_tag(tag);
+                todo.remove_tag(&tags::normalize(tag));
             }
             
-            // 保存更改
-            if let Err(e) = storage.save() {
-                eprintln!("保存待办事项时出错: {}", e);
-                process::exit(1);
+            // 更新提醒时间
+            match sub_matches.value_of("remind") {
+                Some("default") => todo.set_reminders(None),
+                Some(remind) => todo.set_reminders(Some(parse_reminders(remind)?)),
+                None => {}
             }
             
+            // 更新自定义字段
+            set_fields(&mut todo, sub_matches, &config)?;
+            
+            let todo = checked_hook(hooks.on_modify(Event::Modify, before, todo))?;
+            storage.update(id, todo)?;
+            
+            // 保存更改
+            save(&storage)?;
+            
             println!("待办事项已更新。");
         },
+        ("note", Some(sub_matches)) => {
+            let id = parse_id(sub_matches)?;
+            
+            let before = storage.get_todo(id)?;
+            let mut todo = before.clone();
+            
+            // 注释编号从 1 开始，已由参数的 validator 校验
+            let number = |name| sub_matches.value_of(name).and_then(|n| n.parse::<usize>().ok());
+            let text = sub_matches.value_of("text").unwrap_or_default().to_string();
+            let message = if let Some(n) = number("delete") {
+                if todo.remove_annotation(n - 1).is_none() {
+                    return Err(AppError::NotFound(format!("待办事项 {} 没有第 {} 条注释", id, n)));
+                }
+                "注释已删除。"
+            } else if let Some(n) = number("edit") {
+                if !todo.edit_annotation(n - 1, text) {
+                    return Err(AppError::NotFound(format!("待办事项 {} 没有第 {} 条注释", id, n)));
+                }
+                "注释已修改。"
+            } else {
+                todo.add_annotation_at(clock.now(), text);
+                "注释已添加。"
+            };
+            let todo = checked_hook(hooks.on_modify(Event::Modify, before, todo))?;
+            storage.update(id, todo)?;
+            
+            save(&storage)?;
+            
+            println!("{}", message);
+        },
+        ("show", Some(sub_matches)) => {
+            let id = parse_id(sub_matches)?;
+            
+            print_details(id, storage.get_todo(id)?, &config);
+        },
+        ("tags", Some(sub_matches)) => {
+            let registry = load_tag_registry(&storage)?;
+            let collation = match sub_matches.value_of("collate") {
+                Some(name) => name.parse().map_err(AppError::Parse)?,
+                None => config.collation(),
+            };
+            let collator = collation.collator();
+            if sub_matches.is_present("tree") {
+                let roots = tags::tree(storage.todos());
+                if roots.is_empty() {
+                    println!("还没有使用任何标签。");
+                }
+                let paint = |tag: &str, meta: &tags::TagMeta| paint_tag(tag, meta, &config.colors);
+                print!("{}", tags::render_tree(&roots, &registry, &collator, &paint));
+            } else {
+                let mut counts: Vec<_> = tags::count(storage.todos()).into_iter().collect();
+                counts.sort_by(|(a, _), (b, _)| collator.compare(a, b));
+                if counts.is_empty() {
+                    println!("还没有使用任何标签。");
+                }
+                for (tag, count) in counts {
+                    let meta = registry.get(&tag).cloned().unwrap_or_default();
+                    print!("{} {} 项（未完成 {} 项）", paint_tag(&tag, &meta, &config.colors), count.total, count.pending);
+                    if let Some(description) = &meta.description {
+                        print!(" — {}", description);
+                    }
+                    println!();
+                }
+            }
+        },
+        ("tag", Some(sub_matches)) => {
+            run_tag(&mut storage, &hooks, sub_matches)?;
+        },
         ("export", Some(sub_matches)) => {
-            let format = sub_matches.value_of("format").unwrap_or("json");
+            let format = sub_matches.value_of("format").unwrap_or(&config.defaults.export_format);
             let output_path = sub_matches.value_of("output").unwrap();
             
-            let todos = storage.todos();
-            if todos.is_empty() {
-                println!("没有待办事项可导出。");
-                return;
-            }
-            
-            match format {
-                "json" => {
-                    // 导出为JSON格式
-                    let json = serde_json::to_string_pretty(&todos).unwrap_or_else(|e| {
-                        eprintln!("序列化待办事项时出错: {}", e);
-                        process::exit(1);
-                    });
-                    
-                    std::fs::write(output_path, json).unwrap_or_else(|e| {
-                        eprintln!("写入文件时出错: {}", e);
-                        process::exit(1);
-                    });
-                },
-                "csv" => {
-                    // 导出为CSV格式
-                    let mut csv_content = String::from("ID,标题,描述,优先级,状态,创建时间,截止日期,标签\n");
-                    
-                    for (i, todo) in todos.iter().enumerate() {
-                        let status = if todo.is_completed() { "已完成" } else { "未完成" };
-                        let priority = match todo.priority() {
-                            todo::Priority::Low => "低",
-                            todo::Priority::Medium => "中",
-                            todo::Priority::High => "高",
-                        };
-                        let description = todo.description().map_or("", |s| s);
-                        let due_date = todo.due_date().map_or("".to_string(), |d| d.format("%Y-%m-%d").to_string());
-                        let tags = todo.tags().join(",");
-                        
-                        csv_content.push_str(&format!("{},\"{}\",\"{}\",{},{},{},{},\"{}\"\n", 
-                            i, 
-                            todo.title().replace("\"", "\\\""), 
-                            description.replace("\"", "\\\""), 
-                            priority, 
-                            status,
-                            todo.created_at().format("%Y-%m-%d"),
-                            due_date,
-                            tags.replace("\"", "\\\"")
-                        ));
+            // 导出默认包含已完成的待办事项，--pending 时只导出未完成的
+            let mut filter = parse_filter(sub_matches, &config);
+            filter.include_completed = !sub_matches.is_present("pending");
+            let records: Vec<Record> = filter
+                .apply(storage.todos(), clock.as_ref())
+                .into_iter()
+                .map(|(id, todo)| Record { id, todo })
+                .collect();
+            // 没有符合条件的事项时仍然输出空的内容（如 `[]` 或只有表头的 CSV），便于其他程序处理
+            if records.is_empty() {
+                warn(json, "没有符合条件的待办事项，导出的内容为空。", &[]);
+            }
+            
+            // 解析导出字段
+            let fields = sub_matches
+                .value_of("fields")
+                .map(|list| {
+                    if !export::supports_fields(format) {
+                        warn(json, "--fields 只适用于 csv、html 和 json 格式，已忽略", &[]);
                     }
-                    
-                    std::fs::write(output_path, csv_content).unwrap_or_else(|e| {
-                        eprintln!("写入文件时出错: {}", e);
-                        process::exit(1);
-                    });
-                },
-                "html" => {
-                    // 导出为HTML格式
-                    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>待办事项列表</title>\n<style>\n");
-                    html.push_str("body { font-family: Arial, sans-serif; margin: 20px; }\n");
-                    html.push_str("h1 { color: #333; }\n");
-                    html.push_str("table { border-collapse: collapse; width: 100%; }\n");
-                    html.push_str("th, td { border: 1px solid #ddd; padding: 8px; text-align: left; }\n");
-                    html.push_str("th { background-color: #f2f2f2; }\n");
-                    html.push_str(".high { color: red; }\n");
-                    html.push_str(".medium { color: orange; }\n");
-                    html.push_str(".low { color: green; }\n");
-                    html.push_str(".completed { text-decoration: line-through; }\n");
-                    html.push_str(".tag { display: inline-block; background-color: #eee; padding: 2px 5px; margin: 2px; border-radius: 3px; }\n");
-                    html.push_str("</style>\n</head>\n<body>\n");
-                    html.push_str("<h1>待办事项列表</h1>\n");
-                    html.push_str("<table>\n");
-                    html.push_str("<tr><th>ID</th><th>标题</th><th>描述</th><th>优先级</th><th>状态</th><th>创建时间</th><th>截止日期</th><th>标签</th></tr>\n");
-                    
-                    for (i, todo) in todos.iter().enumerate() {
-                        let status = if todo.is_completed() { "已完成" } else { "未完成" };
-                        let priority_class = match todo.priority() {
-                            todo::Priority::Low => "low",
-                            todo::Priority::Medium => "medium",
-                            todo::Priority::High => "high",
-                        };
-                        let priority_text = match todo.priority() {
-                            todo::Priority::Low => "低",
-                            todo::Priority::Medium => "中",
-                            todo::Priority::High => "高",
-                        };
-                        let title_class = if todo.is_completed() { "completed" } else { "" };
-                        let description = todo.description().map_or("", |s| s);
-                        let due_date = todo.due_date().map_or("".to_string(), |d| d.format("%Y-%m-%d").to_string());
-                        
-                        let tags_html = if todo.tags().is_empty() {
-                            "".to_string()
-                        } else {
-                            todo.tags().iter()
-                                .map(|tag| format!("<span class=\"tag\">{}</span>", tag))
-                                .collect::<Vec<_>>()
-                                .join(" ")
-                        };
-                        
-                        html.push_str(&format!("<tr>\n"));
-                        html.push_str(&format!("<td>{}</td>\n", i));
-                        html.push_str(&format!("<td class=\"{}\">{}</td>\n", title_class, todo.title()));
-                        html.push_str(&format!("<td>{}</td>\n", description));
-                        html.push_str(&format!("<td class=\"{}\">{}</td>\n", priority_class, priority_text));
-                        html.push_str(&format!("<td>{}</td>\n", status));
-                        html.push_str(&format!("<td>{}</td>\n", todo.created_at().format("%Y-%m-%d %H:%M:%S")));
-                        html.push_str(&format!("<td>{}</td>\n", due_date));
-                        html.push_str(&format!("<td>{}</td>\n", tags_html));
-                        html.push_str(&format!("</tr>\n"));
+                    Field::parse_list(list).map_err(AppError::Parse)
+                })
+                .transpose()?;
+            let group_by = match sub_matches.value_of("group-by") {
+                Some("tag") => format::GroupBy::Tag(filter.collation),
+                _ => format::GroupBy::Priority,
+            };
+            
+            let exporter = export::exporter(format, ExportOptions { fields, group_by })
+                .ok_or_else(|| AppError::Parse(format!("不支持的导出格式: {}", format)))?;
+            
+            // `-` 表示输出到标准输出，便于与其他命令组合
+            let result = if output_path == "-" {
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                exporter.export(&records, &mut out).and_then(|_| Ok(out.flush()?))
+            } else {
+                let file = File::create(output_path).map_err(AppError::file("写入", output_path))?;
+                let mut out = BufWriter::new(file);
+                exporter.export(&records, &mut out).and_then(|_| Ok(out.flush()?))
+            };
+            result?;
+            
+            if output_path != "-" {
+                println!("已导出 {} 个待办事项到 {}", records.len(), output_path);
+            }
+        },
+        ("import", Some(sub_matches)) => {
+            let format = sub_matches.value_of("format").unwrap_or(&config.defaults.export_format);
+            let input_path = sub_matches.value_of("input").unwrap();
+
+            let contents = std::fs::read_to_string(input_path).map_err(AppError::file("读取", input_path))?;
+            let summary = import::import(&mut storage, format, &contents)?;
+
+            save(&storage)?;
+
+            println!("已从 {} 导入待办事项：新增 {} 个，更新 {} 个。", input_path, summary.added, summary.updated);
+
+            // 报告无法映射的字段，而不是悄悄丢弃
+            if !summary.unmapped.is_empty() {
+                println!("以下 {} 个字段无法映射，未被导入：", summary.unmapped.len());
+                for item in &summary.unmapped {
+                    println!("   {}: {} = {}", item.task, item.field, item.value);
+                }
+            }
+        },
+        ("encrypt", Some(_)) => {
+            if storage.is_encrypted() {
+                println!("数据文件已经是加密的。");
+                return Ok(());
+            }
+            
+            let key = match key {
+                Some(key) => key,
+                None => {
+                    let passphrase = read_passphrase("请设置数据文件的密码: ")?;
+                    if read_passphrase("请再次输入密码: ")? != passphrase {
+                        return Err(AppError::Validation("两次输入的密码不一致".to_string()));
                     }
-                    
-                    html.push_str("</table>\n");
-                    html.push_str("</body>\n</html>");
-                    
-                    std::fs::write(output_path, html).unwrap_or_else(|e| {
-                        eprintln!("写入文件时出错: {}", e);
-                        process::exit(1);
-                    });
-                },
-                _ => {
-                    eprintln!("不支持的导出格式: {}", format);
-                    process::exit(1);
+                    KeySource::Passphrase(passphrase)
                 }
+            };
+            storage.set_key(Some(key));
+            
+            save(&storage)?;
+            
+            println!("数据文件已加密。");
+        },
+        ("decrypt", Some(_)) => {
+            if !storage.is_encrypted() {
+                println!("数据文件没有加密。");
+                return Ok(());
             }
             
-            println!("待办事项已导出到 {}", output_path);
+            storage.set_key(None);
+            save(&storage)?;
+            
+            println!("数据文件已解密。");
+        },
+        ("serve", Some(sub_matches)) => {
+            let bind = sub_matches.value_of("bind").unwrap();
+            server::serve(storage, &hooks, &config.fields, bind).map_err(AppError::io("启动服务时出错"))?;
+        },
+        ("rpc", Some(_)) => {
+            rpc::run(storage, hooks, config.fields.clone()).map_err(AppError::io("处理 JSON-RPC 请求时出错"))?;
+        },
+        ("remind", Some(sub_matches)) => {
+            let defaults = parse_reminders(sub_matches.value_of("offsets").unwrap())?;
+            let interval = sub_matches.value_of("interval").unwrap().parse::<u64>()
+                .map_err(|_| AppError::Parse("无效的检查间隔".to_string()))?;
+            let notify = sub_matches.value_of("notify").unwrap();
+            let target = match notify {
+                "webhook" => sub_matches.value_of("webhook"),
+                _ => sub_matches.value_of("command"),
+            };
+            let notifier = remind::notifier(notify, target).map_err(|e| AppError::Validation(e.to_string()))?;
+            
+            if sub_matches.is_present("daemon") {
+                remind::run_daemon(&mut storage, &defaults, notifier.as_ref(), std::time::Duration::from_secs(interval));
+            }
+            remind::check(&mut storage, &defaults, notifier.as_ref()).map_err(AppError::storage("检查提醒时出错"))?;
         },
-        ("html", Some(sub_matches)) => {
-            // HTML导出逻辑已在上面实现
+        ("sync", Some(sub_matches)) => {
+            if let ("caldav", Some(caldav_matches)) = sub_matches.subcommand() {
+                run_caldav_sync(&mut storage, caldav_matches, json)?;
+            }
+        },
+        (name, Some(sub_matches)) => {
+            run_plugin(name, sub_matches, &storage, config_path.as_deref())?;
         },
         _ => unreachable!()
     }
-}
\ No newline at end of file
+    Ok(())
+}
+
+/// 运行外部子命令插件，以插件的退出状态退出
+fn run_plugin(name: &str, sub_matches: &ArgMatches, storage: &TodoStorage, config_path: Option<&Path>) -> Result<(), AppError> {
+    let Some(path) = plugin::find(name) else {
+        return Err(AppError::NotFound(format!("未知的子命令: {}（PATH 中没有 {}{}）", name, plugin::PREFIX, name)));
+    };
+    let args: Vec<&str> = sub_matches.values_of("").into_iter().flatten().collect();
+    let status = plugin::run(&path, &args, storage.todos(), storage.file_path(), config_path)
+        .map_err(AppError::file("运行插件", &path))?;
+    process::exit(status.code().unwrap_or(1))
+}
+
+/// 三方合并数据文件，冲突写入报告而不是中止合并
+fn run_merge(
+    sub_matches: &ArgMatches,
+    storage_format: Option<StorageFormat>,
+    key: Option<KeySource>,
+    clock: &Arc<dyn Clock>,
+    json: bool,
+) -> Result<(), AppError> {
+    let ours_path = sub_matches.value_of("ours").unwrap();
+    let format = storage_format.unwrap_or_else(|| StorageFormat::from_path(Path::new(ours_path)));
+
+    // 按原样读取三个版本，没有标识符的事项按标题和创建时间匹配
+    let paths = ["base", "ours", "theirs"].map(|name| Path::new(sub_matches.value_of(name).unwrap()));
+    let merged = merge::merge_files(paths, format, key, clock, || {
+        Ok(KeySource::Passphrase(read_passphrase("请输入数据文件的密码: ")?))
+    })?;
+    let count = merged.result.todos.len();
+
+    let output = if sub_matches.is_present("driver") {
+        Some(ours_path)
+    } else {
+        sub_matches.value_of("output")
+    };
+    let (storage, conflicts) = merged.into_storage(Path::new(output.unwrap_or("-")), format);
+    match output {
+        Some(path) => {
+            storage.save().map_err(AppError::storage("保存合并结果时出错"))?;
+            println!("已合并 {} 个待办事项到 {}，{} 处冲突。", count, path, conflicts.len());
+        }
+        None => {
+            let contents = storage.serialize().map_err(AppError::storage("保存合并结果时出错"))?;
+            println!("{}", contents);
+        }
+    }
+
+    match sub_matches.value_of("report") {
+        Some(path) => {
+            merge::write_report(Path::new(path), &conflicts).map_err(AppError::file("写入冲突报告", path))?;
+        }
+        None if !conflicts.is_empty() => {
+            let details: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+            warn(json, &format!("合并时发现 {} 处冲突", conflicts.len()), &details);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// 与 CalDAV 集合同步，同步状态和冲突日志保存在数据文件旁边
+fn run_caldav_sync(storage: &mut TodoStorage, sub_matches: &ArgMatches, json: bool) -> Result<(), AppError> {
+    let password = match sub_matches.value_of("user") {
+        Some(_) => match env::var("TODO_CALDAV_PASSWORD") {
+            Ok(password) => Some(password),
+            Err(_) => Some(read_passphrase("请输入 CalDAV 密码: ")?),
+        },
+        None => None,
+    };
+    let credentials = sub_matches.value_of("user").zip(password.as_deref());
+    let client = sync::caldav::CalDavClient::new(
+        sub_matches.value_of("url").unwrap(),
+        sub_matches.value_of("calendar").unwrap(),
+        credentials,
+    );
+
+    let report = sync::caldav::sync_file(storage, &client)?;
+
+    println!(
+        "同步完成：拉取 {} 个，推送 {} 个，删除本地 {} 个，删除远程 {} 个，冲突 {} 个。",
+        report.pulled,
+        report.pushed,
+        report.deleted_local,
+        report.deleted_remote,
+        report.conflicts.len()
+    );
+    if !report.conflicts.is_empty() {
+        for conflict in &report.conflicts {
+            println!("   {}", conflict);
+        }
+        let log_path = sync::conflict_log_path(storage.file_path(), "caldav");
+        match sync::append_conflict_log(&log_path, &report.conflicts, &storage.clock().now()) {
+            Ok(()) => println!("冲突已记录到 {}", log_path.display()),
+            Err(e) => warn(json, &format!("写入冲突日志时出错: {}", e), &[]),
+        }
+    }
+    Ok(())
+}
+
+/// 当前上下文的筛选条件，子命令指定了 `--no-context` 时不筛选；上下文中保存的选项无效时出错
+fn load_context_filter(sub_matches: &ArgMatches, config: &Config) -> Result<Option<Box<filter::Filter>>, AppError> {
+    if sub_matches.is_present("no-context") {
+        return Ok(None);
+    }
+    let filter = view::context_filter(config).map_err(AppError::Validation)?;
+    Ok(filter.map(Box::new))
+}
+
+/// 修改配置文件，没有主目录时无法确定配置文件的位置
+fn update_config(config_path: Option<&Path>, edit: impl FnOnce(&mut toml_edit::DocumentMut)) -> Result<(), AppError> {
+    let Some(path) = config_path else {
+        return Err(AppError::NotFound("无法获取用户主目录，不能保存配置文件".to_string()));
+    };
+    Config::update(path, edit)?;
+    Ok(())
+}
+
+/// 校验保存在视图或上下文中的 list 选项
+fn checked_list_args(sub_matches: &ArgMatches) -> Result<Vec<String>, AppError> {
+    let args: Vec<String> = sub_matches.values_of("args").into_iter().flatten().map(str::to_string).collect();
+    cli::parse_list_args(args.iter().map(String::as_str)).map_err(|e| AppError::Parse(format!("无效的 list 选项: {}", e)))?;
+    Ok(args)
+}
+
+/// `view save/delete/list`，`view <名称>` 已在解析命令行时展开为 list
+fn run_view(sub_matches: &ArgMatches, config: &Config, config_path: Option<&Path>) -> Result<(), AppError> {
+    match sub_matches.subcommand() {
+        ("save", Some(args)) => {
+            let name = args.value_of("name").unwrap();
+            let list_args = checked_list_args(args)?;
+            update_config(config_path, |doc| {
+                config::table_mut(doc, &["views", name])["args"] = toml_edit::value(toml_edit::Array::from_iter(&list_args));
+            })?;
+            println!("视图 {} 已保存。", name);
+        }
+        ("delete", Some(args)) => {
+            let name = args.value_of("name").unwrap();
+            if !config.views.contains_key(name) {
+                return Err(AppError::NotFound(format!("未定义的视图: {}", name)));
+            }
+            update_config(config_path, |doc| {
+                if let Some(views) = doc.get_mut("views").and_then(toml_edit::Item::as_table_like_mut) {
+                    views.remove(name);
+                }
+            })?;
+            println!("视图 {} 已删除。", name);
+        }
+        ("list", Some(_)) => {
+            if config.views.is_empty() {
+                println!("还没有保存任何视图。");
+            }
+            for (name, view) in &config.views {
+                println!("{}: {}", name, view.args.join(" "));
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// 显示、切换、定义和删除上下文
+fn run_context(sub_matches: &ArgMatches, config: &Config, config_path: Option<&Path>) -> Result<(), AppError> {
+    match sub_matches.subcommand() {
+        ("define", Some(args)) => {
+            let name = args.value_of("name").unwrap();
+            let filter = checked_list_args(args)?;
+            // 未指定默认标签时使用筛选条件中的 --tag
+            let default_tags: Vec<String> = match args.value_of("tags") {
+                Some(list) => list.split(',').map(tags::normalize).filter(|t| !t.is_empty()).collect(),
+                None => cli::parse_list_args(filter.iter().map(String::as_str))
+                    .ok()
+                    .and_then(|m| m.value_of("tag").map(tags::normalize))
+                    .into_iter()
+                    .collect(),
+            };
+            update_config(config_path, |doc| {
+                let context = config::table_mut(doc, &["contexts", name]);
+                context["filter"] = toml_edit::value(toml_edit::Array::from_iter(&filter));
+                context["tags"] = toml_edit::value(toml_edit::Array::from_iter(&default_tags));
+            })?;
+            println!("上下文 {} 已定义。", name);
+        }
+        ("delete", Some(args)) => {
+            let name = args.value_of("name").unwrap();
+            if !config.contexts.contains_key(name) {
+                return Err(AppError::NotFound(format!("未定义的上下文: {}", name)));
+            }
+            update_config(config_path, |doc| {
+                if let Some(contexts) = doc.get_mut("contexts").and_then(toml_edit::Item::as_table_like_mut) {
+                    contexts.remove(name);
+                }
+                // 删除当前的上下文时一并取消
+                if doc.get("context").and_then(toml_edit::Item::as_str) == Some(name) {
+                    doc.remove("context");
+                }
+            })?;
+            println!("上下文 {} 已删除。", name);
+        }
+        ("list", Some(_)) => {
+            if config.contexts.is_empty() {
+                println!("还没有定义任何上下文。");
+            }
+            for (name, context) in &config.contexts {
+                let marker = if config.context.as_deref() == Some(name) { "*" } else { " " };
+                print!("{} {}: {}", marker, name, context.filter.join(" "));
+                if !context.tags.is_empty() {
+                    print!("（默认标签: {}）", context.tags.join(", "));
+                }
+                println!();
+            }
+        }
+        _ => match sub_matches.value_of("name") {
+            None => match &config.context {
+                Some(name) => println!("当前上下文: {}", name),
+                None => println!("没有设置上下文。"),
+            },
+            Some("none") => {
+                update_config(config_path, |doc| {
+                    doc.remove("context");
+                })?;
+                println!("已取消上下文。");
+            }
+            Some(name) => {
+                if !config.contexts.contains_key(name) {
+                    return Err(AppError::NotFound(format!("未定义的上下文: {}（使用 context define 定义）", name)));
+                }
+                update_config(config_path, |doc| doc["context"] = toml_edit::value(name))?;
+                println!("已切换到上下文 {}。", name);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// `config get/set/unset/list`
+fn run_config(sub_matches: &ArgMatches, config: &Config, config_path: Option<&Path>) -> Result<(), AppError> {
+    let checked_key = |args: &ArgMatches| {
+        let key = args.value_of("key").unwrap().to_string();
+        config::check_key(&key).map_err(AppError::Validation)?;
+        Ok::<_, AppError>(key)
+    };
+    match sub_matches.subcommand() {
+        ("get", Some(args)) => {
+            let key = checked_key(args)?;
+            println!("{}", config.get(&key).unwrap_or_default());
+        }
+        ("set", Some(args)) => {
+            let key = checked_key(args)?;
+            let value = args.value_of("value").unwrap();
+            update_config(config_path, |doc| config::set_value(doc, &key, value))?;
+            println!("已将 {} 设置为 {}。", key, value);
+        }
+        ("unset", Some(args)) => {
+            let key = checked_key(args)?;
+            update_config(config_path, |doc| config::unset_value(doc, &key))?;
+            println!("已将 {} 恢复为默认值。", key);
+        }
+        _ => {
+            for (key, description) in config::SETTINGS {
+                println!("{} = {}    # {}", key, config.get(key).unwrap_or_default(), description);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn load_tag_registry(storage: &TodoStorage) -> Result<tags::TagRegistry, AppError> {
+    tags::TagRegistry::load(&tags::TagRegistry::path_for(storage.file_path())).map_err(AppError::io("读取标签信息时出错"))
+}
+
+/// 按标签的颜色显示，是否使用颜色由配置项 colors.mode 决定
+fn paint_tag(tag: &str, meta: &tags::TagMeta, colors: &config::Colors) -> String {
+    if colors.enabled() {
+        meta.paint(tag)
+    } else {
+        tag.to_string()
+    }
+}
+
+/// 标签的重命名、合并和设置信息，对所有事项的修改一次写入数据文件
+fn run_tag(storage: &mut TodoStorage, hooks: &Hooks, sub_matches: &ArgMatches) -> Result<(), AppError> {
+    let registry_path = tags::TagRegistry::path_for(storage.file_path());
+    let mut registry = load_tag_registry(storage)?;
+    let message = match sub_matches.subcommand() {
+        (command @ ("rename" | "merge"), Some(args)) => {
+            let (from, to) = match command {
+                "rename" => (args.value_of("old").unwrap(), args.value_of("new").unwrap()),
+                _ => (args.value_of("from").unwrap(), args.value_of("into").unwrap()),
+            };
+            let (from, to) = (tags::normalize(from), tags::normalize(to));
+            let mode = if command == "rename" { tags::RetagMode::Rename } else { tags::RetagMode::Merge };
+            let changed = checked_hook(tags::retag_storage(storage, &mut registry, hooks, &from, &to, mode))?;
+            match command {
+                "rename" => format!("已将标签 {} 重命名为 {}，修改了 {} 个待办事项。", from, to, changed),
+                _ => format!("已将标签 {} 合并到 {}，修改了 {} 个待办事项。", from, to, changed),
+            }
+        }
+        ("set", Some(args)) => {
+            let tag = tags::normalize(args.value_of("tag").unwrap());
+            let mut meta = registry.get(&tag).cloned().unwrap_or_default();
+            let value = |name| args.value_of(name).map(|v: &str| Some(v.to_string()).filter(|v| !v.is_empty()));
+            if let Some(color) = value("color") {
+                meta.color = color;
+            }
+            if let Some(description) = value("description") {
+                meta.description = description;
+            }
+            registry.set(&tag, meta);
+            format!("标签 {} 的信息已更新。", tag)
+        }
+        _ => unreachable!(),
+    };
+
+    save(storage)?;
+    registry.save(&registry_path).map_err(AppError::io("保存标签信息时出错"))?;
+    println!("{}", message);
+    Ok(())
+}
+
+/// `show` 命令的输出：待办事项的全部信息，注释按时间顺序编号列出
+fn print_details(id: usize, todo: &Todo, config: &Config) {
+    let datetime_format = config.format.datetime.as_str();
+    let status = if todo.is_completed() { "已完成" } else { "未完成" };
+    println!("{}. {}", id, todo.title());
+    println!("   状态: {}", status);
+    println!("   优先级: {}", todo.priority());
+    if let Some(desc) = todo.description() {
+        println!("   描述: {}", desc);
+    }
+    println!("   创建时间: {}", todo.created_at().format(datetime_format));
+    if let Some(completed_at) = todo.completed_at() {
+        println!("   完成时间: {}", completed_at.format(datetime_format));
+    }
+    if let Some(due) = todo.due_date() {
+        println!("   截止日期: {}", due.format(datetime_format));
+    }
+    if !todo.tags().is_empty() {
+        println!("   标签: {}", todo.tags().join(", "));
+    }
+    for (name, value) in todo.fields() {
+        println!("   {}: {}", name, value);
+    }
+    if !todo.annotations().is_empty() {
+        println!();
+        println!("注释：");
+        for (n, annotation) in todo.annotations().iter().enumerate() {
+            println!("  {}. [{}] {}", n + 1, annotation.entry.format("%Y-%m-%d %H:%M"), annotation.text);
+        }
+    }
+}
+
+/// 输出钩子的提示并返回钩子修改后的事项；钩子拒绝或出错时不保存任何修改
+fn checked_hook<T>(result: Result<hooks::Outcome<T>, impl Into<AppError>>) -> Result<T, AppError> {
+    let outcome = result.map_err(Into::into)?;
+    for message in outcome.messages {
+        println!("{}", message);
+    }
+    Ok(outcome.value)
+}
+
+/// 解析子命令的 ID 参数
+fn parse_id(sub_matches: &ArgMatches) -> Result<usize, AppError> {
+    let id = sub_matches.value_of("id").unwrap();
+    id.parse().map_err(|_| AppError::InvalidId(id.to_string()))
+}
+
+fn save(storage: &TodoStorage) -> Result<(), AppError> {
+    storage.save().map_err(AppError::storage("保存待办事项时出错"))
+}
+
+fn parse_due_date(date_str: &str, config: &Config) -> Result<chrono::DateTime<chrono::Local>, AppError> {
+    config.parse_due_date(date_str).map_err(AppError::Parse)
+}
+
+/// 设置 `--set 名称=值` 指定的自定义字段
+fn set_fields(todo: &mut Todo, sub_matches: &ArgMatches, config: &Config) -> Result<(), AppError> {
+    for assignment in sub_matches.values_of("set").into_iter().flatten() {
+        let (name, value) = fields::parse_assignment(assignment).map_err(AppError::Parse)?;
+        fields::assign(todo, &config.fields, &name, &value).map_err(AppError::Validation)?;
+    }
+    Ok(())
+}
+
+/// 解析 `--remind` 等选项中的提醒时间
+fn parse_reminders(s: &str) -> Result<Vec<todo::ReminderOffset>, AppError> {
+    todo::ReminderOffset::parse_setting(s).map_err(AppError::Parse)
+}
+
+/// 在终端中读取密码，输入内容不会回显
+fn read_passphrase(prompt: &str) -> Result<String, AppError> {
+    let passphrase = rpassword::prompt_password(prompt).map_err(AppError::io("无法读取密码"))?;
+    if passphrase.is_empty() {
+        return Err(AppError::Validation("密码不能为空".to_string()));
+    }
+    Ok(passphrase)
+}
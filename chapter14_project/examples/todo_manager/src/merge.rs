@@ -0,0 +1,488 @@
+//! 数据文件的三方合并
+//!
+//! 通过 git 或 Dropbox 同步数据文件时，两边各自修改后会产生冲突的副本。
+//! 合并以共同祖先（base）为基准，逐个事项、逐个字段地合并我方（ours）和对方（theirs）的修改：
+//!
+//! - 事项按标识符匹配；旧版本的数据文件没有标识符时，按标题和创建时间匹配
+//! - 只有一方修改的字段采用修改后的值，双方改成不同的值时保留我方的值并记录冲突；
+//!   自定义字段按名称逐个合并，规则相同
+//! - 标签和注释取并集，但任意一方删除的原有标签或注释会被删除
+//! - 完成状态的冲突按固定规则处理：已完成优先，双方都完成时取较早的完成时间
+//! - 一方删除、另一方修改的事项会被保留，并记录冲突
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Local};
+
+use crate::clock::Clock;
+use crate::crypto::KeySource;
+use crate::error::AppError;
+use crate::storage::{StorageError, StorageFormat, TodoStorage};
+use crate::todo::{Annotation, Todo};
+
+/// 合并中无法自动决定的冲突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// 冲突事项的标题
+    pub title: String,
+    pub kind: ConflictKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// 双方将同一字段改成了不同的值，已保留我方的值
+    Field {
+        field: String,
+        base: Option<String>,
+        ours: String,
+        theirs: String,
+    },
+    /// 我方删除了对方修改过的事项，已保留对方的版本
+    DeletedByUs,
+    /// 对方删除了我方修改过的事项，已保留我方的版本
+    DeletedByThem,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ConflictKind::Field { field, base, ours, theirs } => {
+                write!(f, "{}: {}", self.title, field)?;
+                if let Some(base) = base {
+                    write!(f, " — 原值: {}", base)?;
+                }
+                write!(f, "，我方: {}，对方: {}（已保留我方的修改）", ours, theirs)
+            }
+            ConflictKind::DeletedByUs => {
+                write!(f, "{}: 我方删除了对方修改过的事项（已保留对方的版本）", self.title)
+            }
+            ConflictKind::DeletedByThem => {
+                write!(f, "{}: 对方删除了我方修改过的事项（已保留我方的版本）", self.title)
+            }
+        }
+    }
+}
+
+/// 合并的结果
+#[derive(Debug, Default)]
+pub struct MergeResult {
+    pub todos: Vec<Todo>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// 三方合并数据文件的结果
+#[derive(Debug)]
+pub struct FileMerge {
+    pub result: MergeResult,
+    /// 任意一个版本是加密的时为读取时使用的密钥，合并结果也用它加密
+    pub key: Option<KeySource>,
+}
+
+impl FileMerge {
+    /// 保存合并结果的存储，以及合并中的冲突
+    pub fn into_storage(self, path: &Path, format: StorageFormat) -> (TodoStorage, Vec<Conflict>) {
+        let mut storage = TodoStorage::with_format(path, format);
+        storage.set_key(self.key);
+        storage.set_todos(self.result.todos);
+        (storage, self.result.conflicts)
+    }
+}
+
+/// 读取 base、ours、theirs 三个版本的数据文件并合并
+///
+/// 各版本按原样读取，不分配标识符。遇到加密的文件而没有密钥时通过 `ask_key` 获取密钥，
+/// 之后的文件使用同一个密钥。
+pub fn merge_files(
+    paths: [&Path; 3],
+    format: StorageFormat,
+    mut key: Option<KeySource>,
+    clock: &Arc<dyn Clock>,
+    mut ask_key: impl FnMut() -> Result<KeySource, AppError>,
+) -> Result<FileMerge, AppError> {
+    let mut versions = Vec::new();
+    let mut encrypted = false;
+    for path in paths {
+        let mut storage = TodoStorage::with_format(path, format);
+        storage.set_key(key.clone());
+        storage.set_clock(clock.clone());
+        let todos = match storage.read() {
+            Err(StorageError::KeyRequired) => {
+                key = Some(ask_key()?);
+                storage.set_key(key.clone());
+                storage.read()
+            }
+            result => result,
+        };
+        let todos = todos.map_err(AppError::storage(format!("读取 {} 时出错", path.display())))?;
+        encrypted |= storage.is_encrypted();
+        versions.push(todos);
+    }
+    Ok(FileMerge {
+        result: merge(&versions[0], &versions[1], &versions[2]),
+        key: key.filter(|_| encrypted),
+    })
+}
+
+/// 将冲突写入报告文件，每行一条
+pub fn write_report(path: &Path, conflicts: &[Conflict]) -> io::Result<()> {
+    let report: String = conflicts.iter().map(|c| format!("{}\n", c)).collect();
+    fs::write(path, report)
+}
+
+/// 三方合并待办事项列表
+///
+/// 结果中的事项按我方的顺序排列，对方新增的事项排在最后。
+pub fn merge(base: &[Todo], ours: &[Todo], theirs: &[Todo]) -> MergeResult {
+    // 每个条目依次是同一事项在 base、ours、theirs 中的版本
+    let mut entries: Vec<[Option<&Todo>; 3]> = ours.iter().map(|o| [None, Some(o), None]).collect();
+    for todo in theirs {
+        match entries.iter_mut().find(|e| e[2].is_none() && e[1].is_some_and(|o| same_todo(o, todo))) {
+            Some(entry) => entry[2] = Some(todo),
+            None => entries.push([None, None, Some(todo)]),
+        }
+    }
+    for todo in base {
+        let entry = entries
+            .iter_mut()
+            .find(|e| e[0].is_none() && e[1..].iter().flatten().any(|t| same_todo(t, todo)));
+        if let Some(entry) = entry {
+            entry[0] = Some(todo);
+        }
+        // 在双方都找不到的事项已被双方删除
+    }
+
+    let mut result = MergeResult::default();
+    for entry in entries {
+        let todo = match entry {
+            [base, Some(ours), Some(theirs)] => Some(merge_todo(base, ours, theirs, &mut result.conflicts)),
+            [Some(base), Some(ours), None] => (ours != base).then(|| {
+                result.conflicts.push(Conflict {
+                    title: ours.title().to_string(),
+                    kind: ConflictKind::DeletedByThem,
+                });
+                ours.clone()
+            }),
+            [Some(base), None, Some(theirs)] => (theirs != base).then(|| {
+                result.conflicts.push(Conflict {
+                    title: theirs.title().to_string(),
+                    kind: ConflictKind::DeletedByUs,
+                });
+                theirs.clone()
+            }),
+            [None, Some(added), None] | [None, None, Some(added)] => Some(added.clone()),
+            _ => None,
+        };
+        result.todos.extend(todo);
+    }
+    result
+}
+
+/// 判断两个版本是否是同一个事项，没有标识符时按标题和创建时间判断
+fn same_todo(a: &Todo, b: &Todo) -> bool {
+    match (a.uid(), b.uid()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.title() == b.title() && a.created_at() == b.created_at(),
+    }
+}
+
+/// 单个值的三方合并，双方改成不同的值时返回 `None`
+fn merge_value<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || base == Some(theirs) {
+        Some(ours.clone())
+    } else if base == Some(ours) {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+/// 合并单个事项的各个字段，并记录其中的冲突
+struct FieldMerger<'a> {
+    title: String,
+    conflicts: &'a mut Vec<Conflict>,
+}
+
+impl FieldMerger<'_> {
+    /// 合并一个字段，有冲突时保留我方的值
+    fn merge<T: PartialEq + Clone>(
+        &mut self,
+        field: &str,
+        base: Option<T>,
+        ours: T,
+        theirs: T,
+        show: fn(&T) -> String,
+    ) -> T {
+        merge_value(base.as_ref(), &ours, &theirs).unwrap_or_else(|| {
+            self.conflicts.push(Conflict {
+                title: self.title.clone(),
+                kind: ConflictKind::Field {
+                    field: field.to_string(),
+                    base: base.as_ref().map(show),
+                    ours: show(&ours),
+                    theirs: show(&theirs),
+                },
+            });
+            ours
+        })
+    }
+}
+
+/// 合并同一事项的两个版本，`base` 为 `None` 表示双方各自新增了该事项
+fn merge_todo(base: Option<&Todo>, ours: &Todo, theirs: &Todo, conflicts: &mut Vec<Conflict>) -> Todo {
+    let mut merged = ours.clone();
+    if merged.uid().is_none() {
+        if let Some(uid) = theirs.uid() {
+            merged.set_uid(uid.to_string());
+        }
+    }
+
+    let mut fields = FieldMerger {
+        title: ours.title().to_string(),
+        conflicts,
+    };
+    let title = fields.merge(
+        "标题",
+        base.map(|t| t.title().to_string()),
+        ours.title().to_string(),
+        theirs.title().to_string(),
+        String::clone,
+    );
+    fields.title = title.clone();
+    merged.set_title(title);
+
+    let description = fields.merge(
+        "描述",
+        base.map(|t| t.description().cloned()),
+        ours.description().cloned(),
+        theirs.description().cloned(),
+        |d| d.clone().unwrap_or_else(|| "(无)".to_string()),
+    );
+    merged.set_description(description);
+
+    let priority = fields.merge(
+        "优先级",
+        base.map(Todo::priority),
+        ours.priority(),
+        theirs.priority(),
+        |p| p.to_string(),
+    );
+    merged.set_priority(priority);
+
+    let due_date = fields.merge(
+        "截止日期",
+        base.map(|t| t.due_date().copied()),
+        ours.due_date().copied(),
+        theirs.due_date().copied(),
+        |d| d.map_or("(无)".to_string(), |d| d.format("%Y-%m-%d %H:%M").to_string()),
+    );
+    merged.set_due_date(due_date);
+
+    let names: BTreeSet<&String> = base
+        .into_iter()
+        .chain([ours, theirs])
+        .flat_map(|t| t.fields().keys())
+        .collect();
+    for name in names {
+        let field = |t: &Todo| t.field(name).map(str::to_string);
+        let value = fields.merge(
+            &format!("字段 {}", name),
+            base.map(field),
+            field(ours),
+            field(theirs),
+            |v| v.clone().unwrap_or_else(|| "(无)".to_string()),
+        );
+        merged.set_field(name, value);
+    }
+
+    merged.set_tags(merge_tags(base.map(Todo::tags), ours.tags(), theirs.tags()));
+    merged.set_annotations(merge_annotations(
+        base.map(Todo::annotations),
+        ours.annotations(),
+        theirs.annotations(),
+    ));
+
+    let completion = |t: &Todo| (t.is_completed(), t.completed_at().copied());
+    let (completed, completed_at) =
+        merge_value(base.map(completion).as_ref(), &completion(ours), &completion(theirs))
+            .unwrap_or_else(|| resolve_completion(completion(ours), completion(theirs)));
+    if (completed, completed_at) != completion(ours) {
+        if completed {
+            merged.mark_as_done_at(completed_at.unwrap_or_else(Local::now));
+        } else {
+            merged.mark_as_undone();
+        }
+    }
+    merged.set_modified_at(Some(*ours.modified_at().max(theirs.modified_at())));
+    merged
+}
+
+/// 双方都修改了完成状态时的固定规则：已完成优先，双方都完成时取较早的完成时间
+fn resolve_completion(
+    ours: (bool, Option<DateTime<Local>>),
+    theirs: (bool, Option<DateTime<Local>>),
+) -> (bool, Option<DateTime<Local>>) {
+    match (ours, theirs) {
+        ((true, a), (true, b)) => (true, a.into_iter().chain(b).min()),
+        ((true, at), _) | (_, (true, at)) => (true, at),
+        _ => (false, None),
+    }
+}
+
+/// 标签取并集，但任意一方删除的原有标签会被删除
+fn merge_tags(base: Option<&[String]>, ours: &[String], theirs: &[String]) -> Vec<String> {
+    let base = base.unwrap_or_default();
+    let removed = |tag: &String| base.contains(tag) && !(ours.contains(tag) && theirs.contains(tag));
+    ours.iter()
+        .chain(theirs)
+        .filter(|tag| !removed(tag))
+        .cloned()
+        .collect()
+}
+
+/// 注释与标签的规则相同；修改过的注释视为删除原注释并添加新注释
+fn merge_annotations(base: Option<&[Annotation]>, ours: &[Annotation], theirs: &[Annotation]) -> Vec<Annotation> {
+    let base = base.unwrap_or_default();
+    let removed = |a: &Annotation| base.contains(a) && !(ours.contains(a) && theirs.contains(a));
+    let mut merged: Vec<Annotation> = Vec::new();
+    for annotation in ours.iter().chain(theirs) {
+        if !removed(annotation) && !merged.contains(annotation) {
+            merged.push(annotation.clone());
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::Priority;
+
+    fn todo(title: &str) -> Todo {
+        Todo::new(title.to_string(), None)
+    }
+
+    #[test]
+    fn test_field_level_merge() {
+        let mut base = todo("写周报");
+        base.add_tag("工作".to_string());
+        base.add_tag("周报".to_string());
+        let mut ours = base.clone();
+        ours.set_priority(Priority::High);
+        ours.remove_tag("周报");
+        ours.add_tag("紧急".to_string());
+        let mut theirs = base.clone();
+        theirs.set_description(Some("先收集数据".to_string()));
+        theirs.add_tag("团队".to_string());
+        theirs.add_annotation("已约好会议室".to_string());
+        let added = todo("买牛奶");
+
+        let result = merge(&[base], &[ours], &[theirs, added]);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.todos.len(), 2);
+        let merged = &result.todos[0];
+        assert_eq!(merged.priority(), Priority::High);
+        assert_eq!(merged.description().map(String::as_str), Some("先收集数据"));
+        assert_eq!(merged.tags(), ["工作", "紧急", "团队"]);
+        assert_eq!(merged.annotations().len(), 1);
+        assert_eq!(result.todos[1].title(), "买牛奶");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let base = [todo("写周报"), todo("买牛奶"), todo("修水管")];
+        let mut ours = base.to_vec();
+        ours[0].set_title("写月报".to_string());
+        ours[1].mark_as_done();
+        ours.pop();
+        let mut theirs = base.to_vec();
+        theirs[0].set_title("写年报".to_string());
+        theirs.remove(1);
+        theirs[1].set_priority(Priority::Low);
+
+        let result = merge(&base, &ours, &theirs);
+        let titles: Vec<&str> = result.todos.iter().map(Todo::title).collect();
+        assert_eq!(titles, vec!["写月报", "买牛奶", "修水管"]);
+        assert_eq!(result.conflicts.len(), 3);
+        assert!(matches!(&result.conflicts[0].kind, ConflictKind::Field { field, .. } if field == "标题"));
+        assert_eq!(result.conflicts[1].kind, ConflictKind::DeletedByThem);
+        assert_eq!(result.conflicts[2].kind, ConflictKind::DeletedByUs);
+        assert_eq!(result.todos[2].priority(), Priority::Low);
+    }
+
+    #[test]
+    fn test_custom_fields() {
+        let mut base = todo("写周报");
+        base.set_field("points", Some("3".to_string()));
+        base.set_field("size", Some("M".to_string()));
+        base.set_field("owner", Some("张三".to_string()));
+        let mut ours = base.clone();
+        ours.set_field("points", Some("5".to_string()));
+        ours.set_field("size", Some("L".to_string()));
+        ours.set_field("estimate", Some("2h".to_string()));
+        let mut theirs = base.clone();
+        theirs.set_field("size", Some("S".to_string()));
+        theirs.set_field("owner", None);
+        theirs.set_field("customer", Some("ACME".to_string()));
+
+        let result = merge(&[base], &[ours], &[theirs]);
+        let merged = &result.todos[0];
+        assert_eq!(merged.field("points"), Some("5"));
+        assert_eq!(merged.field("estimate"), Some("2h"));
+        assert_eq!(merged.field("customer"), Some("ACME"));
+        assert_eq!(merged.field("owner"), None);
+        // 双方改成不同的值时保留我方的值
+        assert_eq!(merged.field("size"), Some("L"));
+        assert_eq!(
+            result.conflicts,
+            [Conflict {
+                title: "写周报".to_string(),
+                kind: ConflictKind::Field {
+                    field: "字段 size".to_string(),
+                    base: Some("M".to_string()),
+                    ours: "L".to_string(),
+                    theirs: "S".to_string(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_completion_and_matching_without_ids() {
+        let base: Todo = serde_json::from_str(
+            r#"{"title":"交报销单","description":null,"completed":false,"created_at":"2026-10-01T08:00:00+08:00","priority":"Medium","due_date":null,"tags":[]}"#,
+        )
+        .unwrap();
+        assert!(base.uid().is_none());
+        let mut ours = base.clone();
+        ours.mark_as_done_at(Local::now());
+        let mut theirs = base.clone();
+        theirs.mark_as_done_at(*base.created_at());
+        theirs.set_uid("6e5d2c2a-1111-4b5c-9a3f-0123456789ab".to_string());
+
+        let result = merge(std::slice::from_ref(&base), &[ours], std::slice::from_ref(&theirs));
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.todos.len(), 1);
+        let merged = &result.todos[0];
+        assert_eq!(merged.uid(), theirs.uid());
+        assert!(merged.is_completed());
+        assert_eq!(merged.completed_at(), theirs.completed_at());
+
+        // 一方完成、另一方改回未完成时，已完成优先
+        let mut done = base.clone();
+        done.mark_as_done_at(*base.created_at());
+        let mut reopened = done.clone();
+        reopened.mark_as_undone();
+        let result = merge(&[done.clone()], &[reopened.clone()], &[done.clone()]);
+        assert!(!result.todos[0].is_completed());
+        let mut redone = done.clone();
+        redone.mark_as_done_at(Local::now());
+        let result = merge(&[done], &[reopened], &[redone.clone()]);
+        assert!(result.todos[0].is_completed());
+        assert_eq!(result.todos[0].completed_at(), redone.completed_at());
+    }
+}
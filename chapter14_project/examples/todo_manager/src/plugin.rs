@@ -0,0 +1,108 @@
+//! 外部子命令插件
+//!
+//! 与 git 类似，未知的子命令 `foo` 会运行 PATH 中的 `todo-foo`，子命令之后的参数原样传给插件。
+//! 插件从标准输入读取全部待办事项的 JSON 数组（与数据文件中的格式相同，数组下标即事项的 ID），
+//! 环境变量 `TODO_FILE` 为数据文件路径，`TODO_CONFIG` 为配置文件路径。
+//! 插件的退出状态即命令的退出状态。
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+use crate::hooks::is_executable;
+use crate::todo::Todo;
+
+/// 插件可执行文件名的前缀
+pub const PREFIX: &str = "todo-";
+
+/// 查找 PATH 中的所有插件：名称 -> 路径，同名的插件以 PATH 中靠前的为准
+pub fn discover() -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    let Some(path) = env::var_os("PATH") else {
+        return plugins;
+    };
+    for dir in env::split_paths(&path) {
+        let Ok(entries) = dir.read_dir() else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name.to_str().and_then(plugin_name) else {
+                continue;
+            };
+            if !plugins.contains_key(name) && is_executable(&entry.path()) {
+                plugins.insert(name.to_string(), entry.path());
+            }
+        }
+    }
+    plugins
+}
+
+/// 按名称查找插件
+pub fn find(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}{}{}", PREFIX, name, env::consts::EXE_SUFFIX);
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+/// `todo help` 中列出的插件
+pub fn help(plugins: &BTreeMap<String, PathBuf>) -> String {
+    let mut help = String::from("插件（PATH 中的 todo-<名称>）:");
+    for (name, path) in plugins {
+        help.push_str(&format!("\n    {:<12} {}", name, path.display()));
+    }
+    help
+}
+
+/// 运行插件并等待其结束
+pub fn run(
+    path: &Path,
+    args: &[&str],
+    todos: &[Todo],
+    data_file: &Path,
+    config_file: Option<&Path>,
+) -> io::Result<ExitStatus> {
+    let mut command = Command::new(path);
+    command.args(args).env("TODO_FILE", data_file).stdin(Stdio::piped());
+    if let Some(config_file) = config_file {
+        command.env("TODO_CONFIG", config_file);
+    }
+    let mut child = command.spawn()?;
+    let json = serde_json::to_string_pretty(todos)?;
+    // 在单独的线程中写入，插件不读取标准输入时不会阻塞；插件提前关闭标准输入也不算错误
+    let writer = child.stdin.take().map(|mut stdin| {
+        thread::spawn(move || {
+            let _ = stdin.write_all(json.as_bytes());
+        })
+    });
+    let status = child.wait()?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    Ok(status)
+}
+
+/// 从可执行文件名中取出插件名称，如 `todo-report` 为 `report`
+fn plugin_name(file_name: &str) -> Option<&str> {
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = name.strip_suffix(env::consts::EXE_SUFFIX).unwrap_or(name);
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_name() {
+        assert_eq!(plugin_name("todo-report"), Some("report"));
+        assert_eq!(plugin_name("todo-"), None);
+        assert_eq!(plugin_name("todo_manager"), None);
+        assert_eq!(help(&BTreeMap::new()), "插件（PATH 中的 todo-<名称>）:");
+    }
+}
@@ -0,0 +1,223 @@
+//! 到期提醒
+//!
+//! 在截止日期前的若干时间点发出提醒，每个待办事项可以单独设置提醒时间。
+//! 已发出的提醒记录在数据文件旁边，重新启动后不会重复提醒；修改截止日期后会重新提醒。
+
+mod notifier;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{StorageError, TodoStorage};
+use crate::todo::{ReminderOffset, Todo};
+
+pub use self::notifier::{notifier, Notifier, NOTIFIERS};
+
+/// 一条需要发出的提醒
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    /// 待办事项在存储中的编号
+    pub id: usize,
+    pub uid: String,
+    pub title: String,
+    pub due: DateTime<Local>,
+    /// 触发这条提醒的提前时间
+    pub offset: ReminderOffset,
+    /// 这条提醒覆盖的已到时间点，发出后一并记录
+    keys: Vec<String>,
+}
+
+impl Reminder {
+    /// 提醒的正文
+    pub fn message(&self, now: &DateTime<Local>) -> String {
+        let due = self.due.format("%Y-%m-%d %H:%M");
+        if self.due <= *now {
+            format!("「{}」已于 {} 到期", self.title, due)
+        } else {
+            let left = ReminderOffset::from_minutes((self.due - *now).num_minutes().max(1));
+            format!("「{}」将在 {} 后到期（{}）", self.title, left, due)
+        }
+    }
+}
+
+/// 已发出的提醒：待办事项标识符 -> 已到的提醒时间点
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FiredLog {
+    fired: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl FiredLog {
+    /// 记录文件的路径，如 `~/.todo.json.reminders.json`
+    pub fn path_for(data_file: &Path) -> PathBuf {
+        let mut name = data_file.as_os_str().to_os_string();
+        name.push(".reminders.json");
+        PathBuf::from(name)
+    }
+
+    /// 读取记录，文件不存在时返回空记录
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(FiredLog::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn mark(&mut self, reminder: &Reminder) {
+        self.fired
+            .entry(reminder.uid.clone())
+            .or_default()
+            .extend(reminder.keys.iter().cloned());
+    }
+
+    /// 删除已不存在的待办事项的记录
+    pub fn retain(&mut self, todos: &[Todo]) {
+        self.fired.retain(|uid, _| todos.iter().any(|t| t.uid() == Some(uid)));
+    }
+
+    fn contains(&self, uid: &str, key: &str) -> bool {
+        self.fired.get(uid).is_some_and(|keys| keys.contains(key))
+    }
+}
+
+/// 找出到 `now` 为止应发出且尚未发出的提醒
+///
+/// 同一个待办事项有多个时间点同时到达时（例如守护进程停止了一段时间），
+/// 只发出最接近截止日期的一条。
+pub fn due_reminders(
+    todos: &[Todo],
+    defaults: &[ReminderOffset],
+    log: &FiredLog,
+    now: &DateTime<Local>,
+) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+    for (id, todo) in todos.iter().enumerate() {
+        let (Some(uid), Some(due)) = (todo.uid(), todo.due_date()) else {
+            continue;
+        };
+        if todo.is_completed() {
+            continue;
+        }
+        let mut passed: Vec<(ReminderOffset, String)> = todo
+            .reminders()
+            .unwrap_or(defaults)
+            .iter()
+            .filter(|offset| *due - offset.duration() <= *now)
+            .map(|offset| (*offset, format!("{}@{}", due.to_rfc3339(), offset)))
+            .filter(|(_, key)| !log.contains(uid, key))
+            .collect();
+        if passed.is_empty() {
+            continue;
+        }
+        passed.sort();
+        reminders.push(Reminder {
+            id,
+            uid: uid.to_string(),
+            title: todo.title().to_string(),
+            due: *due,
+            offset: passed[0].0,
+            keys: passed.into_iter().map(|(_, key)| key).collect(),
+        });
+    }
+    reminders
+}
+
+/// 检查一次并发出提醒，返回发出的提醒数
+///
+/// 发送失败的提醒不会被记录，下次检查时会重试。
+pub fn check(
+    storage: &mut TodoStorage,
+    defaults: &[ReminderOffset],
+    notifier: &dyn Notifier,
+) -> Result<usize, StorageError> {
+    match storage.load() {
+        Ok(()) | Err(StorageError::FileNotFound) => {}
+        Err(e) => return Err(e),
+    }
+    let log_path = FiredLog::path_for(storage.file_path());
+    let mut log = FiredLog::load(&log_path)?;
+    log.retain(storage.todos());
+
+    let now = storage.clock().now();
+    let mut sent = 0;
+    for reminder in due_reminders(storage.todos(), defaults, &log, &now) {
+        match notifier.notify(&reminder, &now) {
+            Ok(()) => {
+                log.mark(&reminder);
+                sent += 1;
+            }
+            Err(e) => eprintln!("发送提醒时出错: {}", e),
+        }
+    }
+    log.save(&log_path)?;
+    Ok(sent)
+}
+
+/// 守护进程：每隔 `interval` 重新读取数据文件并发出到期的提醒
+///
+/// 读取失败（例如其他程序正在写入数据文件）时只输出错误，下次检查时重试。
+pub fn run_daemon(
+    storage: &mut TodoStorage,
+    defaults: &[ReminderOffset],
+    notifier: &dyn Notifier,
+    interval: Duration,
+) -> ! {
+    loop {
+        if let Err(e) = check(storage, defaults, notifier) {
+            eprintln!("检查提醒时出错: {}", e);
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn offsets(s: &str) -> Vec<ReminderOffset> {
+        ReminderOffset::parse_list(s).unwrap()
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let due = Local.with_ymd_and_hms(2026, 10, 20, 18, 0, 0).unwrap();
+        let mut todo = Todo::with_due_date("写周报".to_string(), None, due);
+        let todos = vec![todo.clone()];
+        let defaults = offsets("1d,1h");
+        let mut log = FiredLog::default();
+
+        let early = due - chrono::Duration::hours(30);
+        assert!(due_reminders(&todos, &defaults, &log, &early).is_empty());
+
+        let day_before = due - chrono::Duration::hours(20);
+        let reminders = due_reminders(&todos, &defaults, &log, &day_before);
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].offset.to_string(), "1d");
+        log.mark(&reminders[0]);
+        assert!(due_reminders(&todos, &defaults, &log, &day_before).is_empty());
+
+        // 多个时间点同时到达时只提醒最近的一个
+        let mut fresh = FiredLog::default();
+        let reminders = due_reminders(&todos, &defaults, &fresh, &due);
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].offset.to_string(), "1h");
+        fresh.mark(&reminders[0]);
+        assert!(due_reminders(&todos, &defaults, &fresh, &due).is_empty());
+
+        // 单独设置的提醒时间优先于默认设置
+        todo.set_reminders(Some(Vec::new()));
+        assert!(due_reminders(&[todo], &defaults, &FiredLog::default(), &due).is_empty());
+    }
+}
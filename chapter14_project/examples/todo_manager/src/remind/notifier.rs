@@ -0,0 +1,144 @@
+//! 提醒的发送方式，每种方式对应一个 [`Notifier`] 实现，通过 [`notifier`] 按名称创建
+
+use std::io;
+use std::process::Command;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+use thiserror::Error;
+
+use super::Reminder;
+
+/// 支持的发送方式名称
+pub const NOTIFIERS: &[&str] = &["stdout", "desktop", "command", "webhook"];
+
+#[derive(Error, Debug)]
+pub enum NotifyError {
+    #[error("无法运行命令 {0}: {1}")]
+    Spawn(String, io::Error),
+    #[error("命令 {0} 执行失败: {1}")]
+    Failed(String, std::process::ExitStatus),
+    #[error("请求 webhook 失败: {0}")]
+    Webhook(String),
+    #[error("发送方式 {0} 需要指定 {1}")]
+    MissingTarget(String, &'static str),
+    #[error("不支持的发送方式: {0}")]
+    Unsupported(String),
+}
+
+/// 提醒的发送方式
+pub trait Notifier {
+    fn notify(&self, reminder: &Reminder, now: &DateTime<Local>) -> Result<(), NotifyError>;
+}
+
+/// 打印到标准输出
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    fn notify(&self, reminder: &Reminder, now: &DateTime<Local>) -> Result<(), NotifyError> {
+        println!("[{}] {}", now.format("%Y-%m-%d %H:%M"), reminder.message(now));
+        Ok(())
+    }
+}
+
+/// 桌面通知，默认使用 Linux 的 `notify-send` 或 macOS 的 `osascript`
+pub struct DesktopNotifier {
+    /// 替代默认程序的通知命令，以标题和正文作为参数调用
+    pub program: Option<String>,
+}
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, reminder: &Reminder, now: &DateTime<Local>) -> Result<(), NotifyError> {
+        let message = reminder.message(now);
+        let mut command = match &self.program {
+            Some(program) => {
+                let mut command = Command::new(program);
+                command.args(["待办事项提醒", &message]);
+                command
+            }
+            None if cfg!(target_os = "macos") => {
+                let script = format!(
+                    "display notification {:?} with title \"待办事项提醒\"",
+                    message
+                );
+                let mut command = Command::new("osascript");
+                command.args(["-e", &script]);
+                command
+            }
+            None => {
+                let mut command = Command::new("notify-send");
+                command.args(["待办事项提醒", &message]);
+                command
+            }
+        };
+        run(&mut command)
+    }
+}
+
+/// 通过 `sh -c` 运行任意命令，提醒的内容通过环境变量传递：
+/// `TODO_ID`、`TODO_UID`、`TODO_TITLE`、`TODO_DUE`（RFC 3339）、`TODO_OFFSET` 和 `TODO_MESSAGE`
+pub struct CommandNotifier {
+    pub command: String,
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&self, reminder: &Reminder, now: &DateTime<Local>) -> Result<(), NotifyError> {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", &self.command])
+            .env("TODO_ID", reminder.id.to_string())
+            .env("TODO_UID", &reminder.uid)
+            .env("TODO_TITLE", &reminder.title)
+            .env("TODO_DUE", reminder.due.to_rfc3339())
+            .env("TODO_OFFSET", reminder.offset.to_string())
+            .env("TODO_MESSAGE", reminder.message(now));
+        run(&mut command)
+    }
+}
+
+/// 将提醒以 JSON 格式 POST 到指定地址
+pub struct WebhookNotifier {
+    pub url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, reminder: &Reminder, now: &DateTime<Local>) -> Result<(), NotifyError> {
+        let body = json!({
+            "id": reminder.id,
+            "uid": reminder.uid,
+            "title": reminder.title,
+            "due_date": reminder.due.to_rfc3339(),
+            "offset": reminder.offset.to_string(),
+            "message": reminder.message(now),
+        });
+        ureq::post(&self.url)
+            .timeout(Duration::from_secs(10))
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map(|_| ())
+            .map_err(|e| NotifyError::Webhook(e.to_string()))
+    }
+}
+
+fn run(command: &mut Command) -> Result<(), NotifyError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command.status().map_err(|e| NotifyError::Spawn(program.clone(), e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(NotifyError::Failed(program, status))
+    }
+}
+
+/// 按名称创建发送方式，`target` 是桌面通知程序、shell 命令或 webhook 地址
+pub fn notifier(name: &str, target: Option<&str>) -> Result<Box<dyn Notifier>, NotifyError> {
+    let require = |what| target.map(str::to_string).ok_or_else(|| NotifyError::MissingTarget(name.to_string(), what));
+    Ok(match name {
+        "stdout" => Box::new(StdoutNotifier),
+        "desktop" => Box::new(DesktopNotifier { program: target.map(str::to_string) }),
+        "command" => Box::new(CommandNotifier { command: require("--command")? }),
+        "webhook" => Box::new(WebhookNotifier { url: require("--webhook")? }),
+        _ => return Err(NotifyError::Unsupported(name.to_string())),
+    })
+}
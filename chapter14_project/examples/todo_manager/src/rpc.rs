@@ -0,0 +1,650 @@
+//! `todo rpc`：通过标准输入输出使用 JSON-RPC 2.0，供编辑器插件调用
+//!
+//! 每行一条 JSON 消息（也支持批量请求）。参数按名称传递，
+//! 待办事项的 `id` 可以是与命令行一致的编号，也可以是稳定的标识符（`uid`）。
+//!
+//! | 方法                         | 对应的命令 |
+//! |------------------------------|------------|
+//! | `initialize`                 | 能力协商，返回支持的方法、格式和通知 |
+//! | `todo.add`                   | `add`      |
+//! | `todo.list`                  | `list`，参数与 `list` 的选项相同 |
+//! | `todo.get`                   | `show`，返回包括注释在内的全部信息 |
+//! | `todo.next`                  | `next`，没有需要做的事项时返回 `null` |
+//! | `todo.search`                | `search`，参数为 `query`、`all`、`limit`，结果带有匹配部分的字节范围 |
+//! | `todo.update`                | `edit`     |
+//! | `todo.note`                  | `note`，`text` 添加注释，`edit`/`delete` 指定从 1 开始的注释编号 |
+//! | `todo.done` / `todo.undone`  | `done` / `undone` |
+//! | `todo.remove`                | `remove`   |
+//! | `todo.export` / `todo.import`| `export` / `import`，内容通过 `content` 传递 |
+//! | `tag.list`                   | `tags`     |
+//! | `tag.rename` / `tag.merge`   | `tag rename` / `tag merge`，参数为 `from` 和 `to` |
+//! | `shutdown` / `exit`          | 结束会话   |
+//!
+//! 视图、上下文、配置、同步和合并只能通过命令行使用。
+//! `initialize` 之后，数据文件被其他程序修改时会发送 `todo.changed` 通知。
+//!
+//! 修改待办事项的方法会运行与命令行相同的钩子，
+//! 钩子拒绝时返回错误码 -32003，钩子输出的提示显示在标准错误中。
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde_json::{json, Map, Value};
+
+use crate::api;
+use crate::collate::Collation;
+use crate::error::AppError;
+use crate::export::{self, ExportOptions, Field, Record};
+use crate::fields::FieldDecls;
+use crate::filter::{Filter, SortKey};
+use crate::format::GroupBy;
+use crate::hooks::{Event, HookError, Hooks, Outcome};
+use crate::import::{self, ImportError};
+use crate::search;
+use crate::storage::{StorageError, TodoStorage};
+use crate::tags::{self, RetagMode, TagRegistry};
+use crate::todo::Todo;
+
+const PROTOCOL_VERSION: &str = "1.0";
+
+/// 支持的方法，`initialize` 时告知客户端
+const METHODS: &[&str] = &[
+    "initialize",
+    "shutdown",
+    "exit",
+    "todo.add",
+    "todo.list",
+    "todo.get",
+    "todo.next",
+    "todo.search",
+    "todo.update",
+    "todo.note",
+    "todo.done",
+    "todo.undone",
+    "todo.remove",
+    "todo.export",
+    "todo.import",
+    "tag.list",
+    "tag.rename",
+    "tag.merge",
+];
+
+/// 数据文件变化时发送的通知
+const CHANGED_NOTIFICATION: &str = "todo.changed";
+
+/// 检查数据文件是否变化的间隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// 找不到指定的待办事项
+const NOT_FOUND: i64 = -32001;
+/// 读写数据文件失败
+const STORAGE_ERROR: i64 = -32002;
+/// 钩子拒绝了这次操作或运行出错
+const HOOK_ERROR: i64 = -32003;
+/// 操作与已有的数据冲突，如重命名为已被使用的标签
+const CONFLICT: i64 = -32004;
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError { code, message: message.into() }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<StorageError> for RpcError {
+    fn from(e: StorageError) -> Self {
+        let code = match e {
+            StorageError::InvalidId(_) => NOT_FOUND,
+            _ => STORAGE_ERROR,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<HookError> for RpcError {
+    fn from(e: HookError) -> Self {
+        Self::new(HOOK_ERROR, e.to_string())
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        let code = match e.kind() {
+            "invalid_id" | "not_found" => NOT_FOUND,
+            "storage" => STORAGE_ERROR,
+            "hook" => HOOK_ERROR,
+            "conflict" => CONFLICT,
+            _ => INVALID_PARAMS,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<ImportError> for RpcError {
+    fn from(e: ImportError) -> Self {
+        Self::invalid_params(e.to_string())
+    }
+}
+
+/// 数据文件的修改时间和大小，用于发现外部修改
+type FileStamp = Option<(SystemTime, u64)>;
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// 标准输出由请求处理和文件监视共用，每条消息单独一行
+#[derive(Clone)]
+struct Output(Arc<Mutex<io::Stdout>>);
+
+impl Output {
+    fn send(&self, message: &Value) -> io::Result<()> {
+        let mut out = self.0.lock().unwrap();
+        writeln!(out, "{}", message)?;
+        out.flush()
+    }
+}
+
+struct Session {
+    storage: TodoStorage,
+    hooks: Hooks,
+    /// 自定义字段的声明，用于校验 `fields`
+    decls: FieldDecls,
+    output: Output,
+    /// 最近一次由本会话读写时数据文件的状态
+    stamp: Arc<Mutex<FileStamp>>,
+    watching: bool,
+    exiting: bool,
+}
+
+/// 处理标准输入中的请求，直到输入结束或收到 `exit`
+pub fn run(storage: TodoStorage, hooks: Hooks, decls: FieldDecls) -> io::Result<()> {
+    let stamp = file_stamp(storage.file_path());
+    let mut session = Session {
+        storage,
+        hooks,
+        decls,
+        output: Output(Arc::new(Mutex::new(io::stdout()))),
+        stamp: Arc::new(Mutex::new(stamp)),
+        watching: false,
+        exiting: false,
+    };
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(Value::Array(batch)) if !batch.is_empty() => {
+                let responses: Vec<Value> = batch.into_iter().filter_map(|m| session.handle(m)).collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(message) => session.handle(message),
+            Err(e) => Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, format!("无法解析请求: {}", e)),
+            )),
+        };
+        if let Some(response) = response {
+            session.output.send(&response)?;
+        }
+        if session.exiting {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Session {
+    /// 处理一条消息，通知（没有 `id` 的请求）不返回响应
+    fn handle(&mut self, message: Value) -> Option<Value> {
+        let Value::Object(mut message) = message else {
+            return Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, "请求必须是 JSON 对象")));
+        };
+        let id = message.remove("id");
+        let version = message.remove("jsonrpc");
+        let method = match (version, message.remove("method")) {
+            (Some(version), Some(Value::String(method))) if version == "2.0" => method,
+            _ => {
+                let error = RpcError::new(INVALID_REQUEST, "请求必须包含 \"jsonrpc\": \"2.0\" 和 method");
+                return Some(error_response(id.unwrap_or(Value::Null), error));
+            }
+        };
+        let params = match message.remove("params") {
+            None | Some(Value::Null) => Ok(Map::new()),
+            Some(Value::Object(params)) => Ok(params),
+            Some(_) => Err(RpcError::invalid_params("参数必须按名称传递")),
+        };
+
+        let result = params.and_then(|params| self.call(&method, params));
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    fn call(&mut self, method: &str, mut params: Map<String, Value>) -> Result<Value, RpcError> {
+        self.reload()?;
+        match method {
+            "initialize" => {
+                self.start_watching();
+                Ok(capabilities())
+            }
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exiting = true;
+                Ok(Value::Null)
+            }
+            "todo.add" => {
+                let todo = api::new_todo(params, self.storage.clock().now(), &self.decls).map_err(RpcError::invalid_params)?;
+                let todo = checked_hook(self.hooks.on_add(todo))?;
+                self.storage.add(todo);
+                self.save()?;
+                Ok(self.record(self.storage.todos().len() - 1))
+            }
+            "todo.list" => {
+                let filter = parse_filter(&params)?;
+                let records: Vec<Value> = filter
+                    .apply(self.storage.todos(), self.storage.clock().as_ref())
+                    .into_iter()
+                    .map(|(index, todo)| record(index, todo))
+                    .collect();
+                Ok(Value::Array(records))
+            }
+            "todo.get" => {
+                let index = self.todo_index(&mut params)?;
+                Ok(self.record(index))
+            }
+            "todo.next" => {
+                // 只在未完成的事项中按紧急度选择
+                let mut filter = parse_filter(&params)?;
+                filter.include_completed = false;
+                filter.sort = Some(SortKey::Urgency);
+                let clock = self.storage.clock();
+                let next = filter.apply(self.storage.todos(), clock.as_ref()).into_iter().next();
+                Ok(match next {
+                    Some((index, todo)) => {
+                        let mut record = record(index, todo);
+                        record["urgency"] = json!(filter.weights.score(todo, &clock.now()).total());
+                        record
+                    }
+                    None => Value::Null,
+                })
+            }
+            "todo.search" => self.search(&params),
+            "todo.note" => {
+                let index = self.todo_index(&mut params)?;
+                let before = &self.storage.todos()[index];
+                let mut todo = before.clone();
+                let missing = |n| RpcError::new(NOT_FOUND, format!("待办事项 {} 没有第 {} 条注释", index, n));
+                if let Some(n) = annotation_number(&params, "delete")? {
+                    todo.remove_annotation(n - 1).ok_or_else(|| missing(n))?;
+                } else if let Some(n) = annotation_number(&params, "edit")? {
+                    if !todo.edit_annotation(n - 1, string_param(&params, "text")?.to_string()) {
+                        return Err(missing(n));
+                    }
+                } else {
+                    todo.add_annotation_at(self.storage.clock().now(), string_param(&params, "text")?.to_string());
+                }
+                let todo = checked_hook(self.hooks.on_modify(Event::Modify, before, todo))?;
+                self.storage.update(index, todo)?;
+                self.save()?;
+                Ok(self.record(index))
+            }
+            "todo.update" => {
+                let index = self.todo_index(&mut params)?;
+                let before = &self.storage.todos()[index];
+                let mut todo = before.clone();
+                api::apply_fields(&mut todo, params, &self.decls).map_err(RpcError::invalid_params)?;
+                let todo = checked_hook(self.hooks.on_modify(Event::Modify, before, todo))?;
+                self.storage.update(index, todo)?;
+                self.save()?;
+                Ok(self.record(index))
+            }
+            "todo.done" | "todo.undone" => {
+                let index = self.todo_index(&mut params)?;
+                let before = &self.storage.todos()[index];
+                let mut todo = before.clone();
+                let event = if method == "todo.done" {
+                    todo.mark_as_done_at(self.storage.clock().now());
+                    Event::Done
+                } else {
+                    todo.mark_as_undone();
+                    Event::Modify
+                };
+                let todo = checked_hook(self.hooks.on_modify(event, before, todo))?;
+                self.storage.update(index, todo)?;
+                self.save()?;
+                Ok(self.record(index))
+            }
+            "todo.remove" => {
+                let index = self.todo_index(&mut params)?;
+                let removed = self.record(index);
+                checked_hook(self.hooks.on_remove(&self.storage.todos()[index]))?;
+                self.storage.remove(index)?;
+                self.save()?;
+                Ok(removed)
+            }
+            "todo.export" => self.export(params),
+            "todo.import" => {
+                let format = string_param(&params, "format")?;
+                let content = string_param(&params, "content")?;
+                let summary = import::import(&mut self.storage, format, content)?;
+                self.save()?;
+                let unmapped: Vec<Value> = summary
+                    .unmapped
+                    .iter()
+                    .map(|u| json!({ "task": u.task, "field": u.field, "value": u.value }))
+                    .collect();
+                Ok(json!({ "added": summary.added, "updated": summary.updated, "unmapped": unmapped }))
+            }
+            "tag.list" => self.list_tags(&params),
+            "tag.rename" | "tag.merge" => {
+                let from = tags::normalize(string_param(&params, "from")?);
+                let to = tags::normalize(string_param(&params, "to")?);
+                let mode = if method == "tag.rename" { RetagMode::Rename } else { RetagMode::Merge };
+                let registry_path = TagRegistry::path_for(self.storage.file_path());
+                let mut registry = TagRegistry::load(&registry_path).map_err(registry_error)?;
+                let changed = checked_hook(tags::retag_storage(
+                    &mut self.storage,
+                    &mut registry,
+                    &self.hooks,
+                    &from,
+                    &to,
+                    mode,
+                ))?;
+                self.save()?;
+                registry.save(&registry_path).map_err(registry_error)?;
+                Ok(json!({ "changed": changed }))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("未知的方法: {}", method))),
+        }
+    }
+
+    /// 每个请求前重新加载数据文件，以便看到其他程序所做的修改
+    fn reload(&mut self) -> Result<(), RpcError> {
+        let mut stamp = self.stamp.lock().unwrap();
+        match self.storage.load() {
+            Ok(()) => {}
+            Err(StorageError::FileNotFound) => self.storage.set_todos(Vec::new()),
+            Err(e) => return Err(e.into()),
+        }
+        *stamp = file_stamp(self.storage.file_path());
+        Ok(())
+    }
+
+    /// 保存数据文件，本会话的修改不会触发变化通知
+    fn save(&mut self) -> Result<(), RpcError> {
+        let mut stamp = self.stamp.lock().unwrap();
+        self.storage.save()?;
+        *stamp = file_stamp(self.storage.file_path());
+        Ok(())
+    }
+
+    /// 在后台定期检查数据文件，发现外部修改时发送通知
+    fn start_watching(&mut self) {
+        if self.watching {
+            return;
+        }
+        self.watching = true;
+        let path = self.storage.file_path().to_path_buf();
+        let stamp = Arc::clone(&self.stamp);
+        let output = self.output.clone();
+        thread::spawn(move || loop {
+            thread::sleep(WATCH_INTERVAL);
+            let current = file_stamp(&path);
+            let mut last = stamp.lock().unwrap();
+            if current == *last {
+                continue;
+            }
+            *last = current;
+            drop(last);
+            if output.send(&changed_notification(&path)).is_err() {
+                break;
+            }
+        });
+    }
+
+    /// 取出参数中的 `id`：数字是命令行中的编号，字符串是标识符
+    fn todo_index(&self, params: &mut Map<String, Value>) -> Result<usize, RpcError> {
+        match params.remove("id") {
+            Some(Value::Number(n)) => {
+                let index = n.as_u64().ok_or_else(|| RpcError::invalid_params(format!("无效的ID: {}", n)))? as usize;
+                if index >= self.storage.todos().len() {
+                    return Err(StorageError::InvalidId(index).into());
+                }
+                Ok(index)
+            }
+            Some(Value::String(uid)) => self
+                .storage
+                .index_of(&uid)
+                .ok_or_else(|| RpcError::new(NOT_FOUND, format!("找不到待办事项: {}", uid))),
+            Some(other) => Err(RpcError::invalid_params(format!("无效的ID: {}", other))),
+            None => Err(RpcError::invalid_params("缺少参数 id")),
+        }
+    }
+
+    fn record(&self, index: usize) -> Value {
+        record(index, &self.storage.todos()[index])
+    }
+
+    /// `todo.search`：按相关度排序的结果，`highlights` 列出各字段中匹配部分的字节范围
+    fn search(&self, params: &Map<String, Value>) -> Result<Value, RpcError> {
+        let query = string_param(params, "query")?;
+        let include_completed = params.get("all").is_some_and(|a| a == true);
+        let candidates = self
+            .storage
+            .todos()
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| include_completed || !todo.is_completed());
+        let mut hits = search::search(candidates, query);
+        match params.get("limit") {
+            None | Some(Value::Null) => {}
+            Some(limit) => {
+                let limit = limit
+                    .as_u64()
+                    .ok_or_else(|| RpcError::invalid_params(format!("参数 limit 的值无效: {}", limit)))?;
+                hits.truncate(limit as usize);
+            }
+        }
+        let results = hits
+            .iter()
+            .map(|hit| {
+                let mut record = record(hit.id, hit.todo);
+                record["score"] = json!(hit.score);
+                record["highlights"] = hit
+                    .highlights
+                    .iter()
+                    .map(|(field, ranges)| {
+                        let (name, index) = match *field {
+                            search::Field::Title => ("title", None),
+                            search::Field::Tag(i) => ("tag", Some(i)),
+                            search::Field::Description => ("description", None),
+                            search::Field::Annotation(i) => ("annotation", Some(i)),
+                        };
+                        let ranges: Vec<[usize; 2]> = ranges.iter().map(|r| [r.start, r.end]).collect();
+                        json!({ "field": name, "index": index, "ranges": ranges })
+                    })
+                    .collect();
+                record
+            })
+            .collect();
+        Ok(Value::Array(results))
+    }
+
+    /// `tag.list`：每个标签的事项数、颜色和说明，按 `collate` 指定的规则排序
+    fn list_tags(&self, params: &Map<String, Value>) -> Result<Value, RpcError> {
+        let collation: Collation = match params.get("collate").and_then(Value::as_str) {
+            Some(name) => name.parse().map_err(RpcError::invalid_params)?,
+            None => Collation::default(),
+        };
+        let collator = collation.collator();
+        let registry = TagRegistry::load(&TagRegistry::path_for(self.storage.file_path())).map_err(registry_error)?;
+        let mut counts: Vec<_> = tags::count(self.storage.todos()).into_iter().collect();
+        counts.sort_by(|(a, _), (b, _)| collator.compare(a, b));
+        let tags = counts
+            .into_iter()
+            .map(|(tag, count)| {
+                let meta = registry.get(&tag).cloned().unwrap_or_default();
+                json!({
+                    "tag": tag,
+                    "total": count.total,
+                    "pending": count.pending,
+                    "color": meta.color,
+                    "description": meta.description,
+                })
+            })
+            .collect();
+        Ok(Value::Array(tags))
+    }
+
+    /// `todo.export`：返回导出的内容，参数与 `export` 命令的选项相同
+    fn export(&self, mut params: Map<String, Value>) -> Result<Value, RpcError> {
+        let format = string_param(&params, "format")?.to_string();
+        let fields = match params.remove("fields") {
+            Some(Value::String(list)) => Some(Field::parse_list(&list).map_err(RpcError::invalid_params)?),
+            Some(Value::Array(list)) => {
+                let list: Vec<String> = list.iter().map(|f| f.as_str().unwrap_or_default().to_string()).collect();
+                Some(Field::parse_list(&list.join(",")).map_err(RpcError::invalid_params)?)
+            }
+            None | Some(Value::Null) => None,
+            Some(other) => return Err(RpcError::invalid_params(format!("参数 fields 的值无效: {}", other))),
+        };
+        let group_by = params.remove("group_by");
+        let pending = params.remove("pending").is_some_and(|p| p == true);
+        params.remove("format");
+
+        // 导出默认包含已完成的待办事项
+        let mut filter = parse_filter(&params)?;
+        filter.include_completed = !pending;
+        let group_by = match group_by.as_ref().and_then(Value::as_str) {
+            None | Some("priority") => GroupBy::Priority,
+            Some("tag") => GroupBy::Tag(filter.collation),
+            Some(other) => return Err(RpcError::invalid_params(format!("无效的分组方式: {}", other))),
+        };
+        let records: Vec<Record> = filter
+            .apply(self.storage.todos(), self.storage.clock().as_ref())
+            .into_iter()
+            .map(|(id, todo)| Record { id, todo })
+            .collect();
+
+        let exporter = export::exporter(&format, ExportOptions { fields, group_by })
+            .ok_or_else(|| RpcError::invalid_params(format!("不支持的导出格式: {}", format)))?;
+        let mut content = Vec::new();
+        exporter
+            .export(&records, &mut content)
+            .map_err(|e| RpcError::new(STORAGE_ERROR, e.to_string()))?;
+        Ok(json!({
+            "count": records.len(),
+            "content": String::from_utf8_lossy(&content),
+        }))
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "serverInfo": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+        "capabilities": {
+            "methods": METHODS,
+            "notifications": [CHANGED_NOTIFICATION],
+            "filters": api::FILTER_OPTIONS,
+            "editableFields": api::EDITABLE_FIELDS,
+            "exportFormats": export::FORMATS,
+            "importFormats": import::FORMATS,
+        },
+    })
+}
+
+/// 从参数中解析筛选条件，其他参数会被忽略
+fn parse_filter(params: &Map<String, Value>) -> Result<Filter, RpcError> {
+    let mut filter = Filter::default();
+    for key in api::FILTER_OPTIONS {
+        let value = match params.get(*key) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(s)) => s.clone(),
+            Some(v @ (Value::Bool(_) | Value::Number(_))) => v.to_string(),
+            Some(other) => return Err(RpcError::invalid_params(format!("参数 {} 的值无效: {}", key, other))),
+        };
+        api::set_filter_option(&mut filter, key, &value).map_err(RpcError::invalid_params)?;
+    }
+    Ok(filter)
+}
+
+/// 在标准错误中显示钩子的提示（标准输出只用于 JSON-RPC 消息），返回钩子修改后的事项
+fn checked_hook<T>(result: Result<Outcome<T>, impl Into<RpcError>>) -> Result<T, RpcError> {
+    let outcome = result.map_err(Into::into)?;
+    for message in outcome.messages {
+        eprintln!("{}", message);
+    }
+    Ok(outcome.value)
+}
+
+/// 从 1 开始的注释编号，没有该参数时返回 `None`
+fn annotation_number(params: &Map<String, Value>, key: &str) -> Result<Option<usize>, RpcError> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(n) if n >= 1 => Ok(Some(n as usize)),
+            _ => Err(RpcError::invalid_params(format!("参数 {} 必须是从 1 开始的注释编号", key))),
+        },
+    }
+}
+
+fn registry_error(e: io::Error) -> RpcError {
+    RpcError::new(STORAGE_ERROR, format!("读写标签信息时出错: {}", e))
+}
+
+fn string_param<'a>(params: &'a Map<String, Value>, key: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("缺少字符串参数 {}", key)))
+}
+
+/// 带编号的待办事项，编号与命令行中的 ID 一致
+fn record(index: usize, todo: &Todo) -> Value {
+    let mut record = Map::new();
+    record.insert("id".to_string(), json!(index));
+    if let Value::Object(fields) = json!(todo) {
+        record.extend(fields);
+    }
+    Value::Object(record)
+}
+
+fn changed_notification(path: &Path) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": CHANGED_NOTIFICATION,
+        "params": { "path": path },
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
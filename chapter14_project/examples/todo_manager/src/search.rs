@@ -0,0 +1,430 @@
+//! 全文搜索：在标题、标签、描述和注释中查找，按相关度排序
+//!
+//! 查询按空白以及中文和其他文字的边界切分成若干词，每个词都必须在某个字段中匹配。
+//! 一个词依次尝试以下匹配方式，越靠前的方式得分越高：
+//!
+//! - 原文包含，不区分大小写
+//! - 拼音：中文可以用全拼（`gongzuo`）、首字母（`gzbg`）或两者混合（`gongzbg`）查找
+//! - 中文按相邻两个字（bigram）切分，至少一半的二元组出现即算匹配，个别字不同也能找到
+//! - 拉丁字母的单词允许拼写错误：4 个字母以上允许 1 处，8 个字母以上允许 2 处
+//!
+//! 标题中的匹配比标签中的权重高，标签又比描述高，注释最低。拼音取每个字最常用的读音，多音字可能无法按其他读音找到。
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::todo::Todo;
+
+/// 匹配所在的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Field {
+    Title,
+    /// 第几个标签
+    Tag(usize),
+    Description,
+    /// 第几条注释
+    Annotation(usize),
+}
+
+impl Field {
+    fn weight(self) -> f64 {
+        match self {
+            Field::Title => 3.0,
+            Field::Tag(_) => 2.0,
+            Field::Description => 1.0,
+            Field::Annotation(_) => 0.5,
+        }
+    }
+}
+
+/// 各字段中匹配部分的字节范围，按位置排序且互不重叠
+pub type Highlights = BTreeMap<Field, Vec<Range<usize>>>;
+
+/// 一条搜索结果
+#[derive(Debug)]
+pub struct Hit<'a> {
+    /// 待办事项在存储中的编号
+    pub id: usize,
+    pub todo: &'a Todo,
+    /// 相关度，越大越靠前
+    pub score: f64,
+    pub highlights: Highlights,
+}
+
+/// 在待办事项中搜索，返回按相关度从高到低排序的结果；查询为空时没有结果
+pub fn search<'a>(todos: impl IntoIterator<Item = (usize, &'a Todo)>, query: &str) -> Vec<Hit<'a>> {
+    let terms = tokenize(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<Hit> = todos
+        .into_iter()
+        .filter_map(|(id, todo)| {
+            let (score, highlights) = score(todo, &terms)?;
+            Some(Hit { id, todo, score, highlights })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+    hits
+}
+
+/// 用 `paint` 显示文本中匹配的部分，范围需按位置排序且互不重叠
+pub fn highlight(text: &str, ranges: &[Range<usize>], paint: impl Fn(&str) -> String) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    for range in ranges {
+        result.push_str(&text[last..range.start]);
+        result.push_str(&paint(&text[range.clone()]));
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// 查询中的一个词，已转换为小写
+#[derive(Debug, PartialEq)]
+struct Term {
+    chars: Vec<char>,
+    cjk: bool,
+}
+
+/// 按空白切分查询，再把中文和其他文字分开；标点符号不参与搜索
+fn tokenize(query: &str) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for word in query.split_whitespace() {
+        let mut current: Vec<char> = Vec::new();
+        let mut cjk = false;
+        for c in word.chars() {
+            if !c.is_alphanumeric() {
+                push_term(&mut terms, &mut current, cjk);
+                continue;
+            }
+            if is_cjk(c) != cjk {
+                push_term(&mut terms, &mut current, cjk);
+                cjk = is_cjk(c);
+            }
+            current.push(fold(c));
+        }
+        push_term(&mut terms, &mut current, cjk);
+    }
+    terms
+}
+
+fn push_term(terms: &mut Vec<Term>, chars: &mut Vec<char>, cjk: bool) {
+    if !chars.is_empty() {
+        terms.push(Term {
+            chars: std::mem::take(chars),
+            cjk,
+        });
+    }
+}
+
+/// 汉字（CJK 统一表意文字及其扩展 A、B 区和兼容表意文字）
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{20000}'..='\u{2A6DF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// 不区分大小写时比较的字符
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// 预处理后的字段文本
+struct Text<'a> {
+    source: &'a str,
+    /// 每个字符的字节偏移
+    offsets: Vec<usize>,
+    chars: Vec<char>,
+    /// 每个汉字的拼音（小写），其他字符为 `None`
+    syllables: Vec<Option<String>>,
+}
+
+impl<'a> Text<'a> {
+    fn new(source: &'a str) -> Self {
+        let (offsets, chars): (Vec<usize>, Vec<char>) = source.char_indices().map(|(i, c)| (i, fold(c))).unzip();
+        let syllables = chars
+            .iter()
+            .map(|&c| {
+                let syllable = deunicode::deunicode_char(c).filter(|_| is_cjk(c))?.trim().to_lowercase();
+                Some(syllable).filter(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_lowercase()))
+            })
+            .collect();
+        Text { source, offsets, chars, syllables }
+    }
+
+    /// 第 `start` 到第 `end` 个字符（不含）的字节范围
+    fn range(&self, start: usize, end: usize) -> Range<usize> {
+        let byte = |i: usize| self.offsets.get(i).copied().unwrap_or(self.source.len());
+        byte(start)..byte(end)
+    }
+
+    /// 由中文以外的字母和数字组成的单词，返回字符范围
+    fn words(&self) -> Vec<Range<usize>> {
+        let mut words = Vec::new();
+        let mut start = None;
+        for (i, &c) in self.chars.iter().enumerate() {
+            let in_word = c.is_alphanumeric() && !is_cjk(c);
+            match (in_word, start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    words.push(s..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            words.push(s..self.chars.len());
+        }
+        words
+    }
+}
+
+/// 一个词在一个字段中的匹配
+struct Match {
+    /// 匹配的精确程度，0 到 1
+    quality: f64,
+    ranges: Vec<Range<usize>>,
+}
+
+/// 计算待办事项的相关度，有词没有匹配时返回 `None`
+fn score(todo: &Todo, terms: &[Term]) -> Option<(f64, Highlights)> {
+    let mut fields = vec![(Field::Title, Text::new(todo.title()))];
+    fields.extend(todo.tags().iter().enumerate().map(|(i, tag)| (Field::Tag(i), Text::new(tag))));
+    if let Some(description) = todo.description() {
+        fields.push((Field::Description, Text::new(description)));
+    }
+    fields.extend(
+        todo.annotations()
+            .iter()
+            .enumerate()
+            .map(|(i, annotation)| (Field::Annotation(i), Text::new(&annotation.text))),
+    );
+
+    let mut total = 0.0;
+    let mut highlights = Highlights::new();
+    for term in terms {
+        let mut best: Option<f64> = None;
+        for (field, text) in &fields {
+            if let Some(m) = match_term(term, text) {
+                let score = field.weight() * m.quality;
+                best = Some(best.map_or(score, |b| b.max(score)));
+                highlights.entry(*field).or_default().extend(m.ranges);
+            }
+        }
+        total += best?;
+    }
+    for ranges in highlights.values_mut() {
+        *ranges = merge_ranges(std::mem::take(ranges));
+    }
+    Some((total, highlights))
+}
+
+fn match_term(term: &Term, text: &Text) -> Option<Match> {
+    let exact = find_all(&text.chars, &term.chars);
+    if !exact.is_empty() {
+        let ranges = exact.into_iter().map(|i| text.range(i, i + term.chars.len())).collect();
+        return Some(Match { quality: 1.0, ranges });
+    }
+    if term.cjk {
+        bigrams(term, text)
+    } else {
+        pinyin(term, text).or_else(|| fuzzy(term, text))
+    }
+}
+
+/// `needle` 在 `haystack` 中互不重叠的出现位置
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while !needle.is_empty() && i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == *needle {
+            found.push(i);
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+/// 中文词按相邻两个字切分，至少一半的二元组出现在文本中
+fn bigrams(term: &Term, text: &Text) -> Option<Match> {
+    if term.chars.len() < 3 {
+        return None;
+    }
+    let pairs: Vec<&[char]> = term.chars.windows(2).collect();
+    let mut found = 0;
+    let mut ranges = Vec::new();
+    for pair in &pairs {
+        let positions = find_all(&text.chars, pair);
+        if !positions.is_empty() {
+            found += 1;
+            ranges.extend(positions.into_iter().map(|i| text.range(i, i + 2)));
+        }
+    }
+    let fraction = found as f64 / pairs.len() as f64;
+    (fraction >= 0.5).then_some(Match {
+        quality: 0.6 * fraction,
+        ranges,
+    })
+}
+
+/// 用拼音匹配连续的汉字，每个字可以写全拼或拼音的前缀（至少首字母）
+fn pinyin(term: &Term, text: &Text) -> Option<Match> {
+    if term.chars.len() < 2 || !term.chars.iter().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let term: String = term.chars.iter().collect();
+    (0..text.chars.len()).find_map(|start| {
+        let (end, full) = match_syllables(term.as_bytes(), &text.syllables, start)?;
+        Some(Match {
+            quality: if full { 0.9 } else { 0.8 },
+            ranges: vec![text.range(start, end)],
+        })
+    })
+}
+
+/// 从第 `i` 个字开始依次匹配拼音，返回匹配结束的位置以及是否每个字都写了全拼；优先尝试更长的前缀
+fn match_syllables(term: &[u8], syllables: &[Option<String>], i: usize) -> Option<(usize, bool)> {
+    if term.is_empty() {
+        return Some((i, true));
+    }
+    let syllable = syllables.get(i)?.as_deref()?.as_bytes();
+    (1..=syllable.len().min(term.len())).rev().find_map(|k| {
+        if term[..k] != syllable[..k] {
+            return None;
+        }
+        let (end, full) = match_syllables(&term[k..], syllables, i + 1)?;
+        Some((end, full && k == syllable.len()))
+    })
+}
+
+/// 允许拼写错误地匹配单词，也可以只匹配单词的开头
+fn fuzzy(term: &Term, text: &Text) -> Option<Match> {
+    let max_distance = match term.chars.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let mut best: Option<usize> = None;
+    let mut ranges = Vec::new();
+    for word in text.words() {
+        let chars = &text.chars[word.clone()];
+        let prefix = &chars[..chars.len().min(term.chars.len())];
+        let distance = edit_distance(&term.chars, chars).min(edit_distance(&term.chars, prefix));
+        if distance <= max_distance {
+            best = Some(best.map_or(distance, |b| b.min(distance)));
+            ranges.push(text.range(word.start, word.end));
+        }
+    }
+    Some(Match {
+        quality: 0.7 - 0.15 * best? as f64,
+        ranges,
+    })
+}
+
+/// 编辑距离，相邻两个字符交换位置算作一处
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// 排序并合并重叠或相邻的范围
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|r| (r.start, r.end));
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos() -> Vec<Todo> {
+        let mut report = Todo::new("提交工作报告".to_string(), Some("发给 Manager 审核".to_string()));
+        report.add_tag("工作".to_string());
+        let milk = Todo::new("买牛奶".to_string(), None);
+        let mut review = Todo::new("Review quarterly budget".to_string(), None);
+        review.add_tag("财务/报销".to_string());
+        vec![report, milk, review]
+    }
+
+    fn ids(todos: &[Todo], query: &str) -> Vec<usize> {
+        search(todos.iter().enumerate(), query).into_iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let terms = tokenize("周报API, 2026");
+        let words: Vec<String> = terms.iter().map(|t| t.chars.iter().collect()).collect();
+        assert_eq!(words, ["周报", "api", "2026"]);
+        assert_eq!(terms.iter().map(|t| t.cjk).collect::<Vec<_>>(), [true, false, false]);
+        assert!(tokenize(" ，。 ").is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let todos = todos();
+        // 原文、拼音全拼、首字母和混合写法
+        assert_eq!(ids(&todos, "工作"), [0]);
+        assert_eq!(ids(&todos, "gzbg"), [0]);
+        assert_eq!(ids(&todos, "gongzuobaogao"), [0]);
+        assert_eq!(ids(&todos, "gongzbg"), [0]);
+        assert_eq!(ids(&todos, "niunai"), [1]);
+        // 中文的二元组：中间隔了几个字，三个二元组中出现了两个
+        assert_eq!(ids(&todos, "提交报告"), [0]);
+        // 拼写错误和大小写
+        assert_eq!(ids(&todos, "quartely"), [2]);
+        assert_eq!(ids(&todos, "MANAGER"), [0]);
+        // 每个词都要匹配
+        assert!(ids(&todos, "工作 牛奶").is_empty());
+        assert!(ids(&todos, "").is_empty());
+
+        // 标题中的匹配排在标签中的前面
+        let mut todos = todos;
+        todos[1].add_tag("报告".to_string());
+        assert_eq!(ids(&todos, "报告"), [0, 1]);
+        // 注释中的匹配排在描述中的后面
+        todos[2].add_annotation("已经交给 manager".to_string());
+        assert_eq!(ids(&todos, "manager"), [0, 2]);
+        let hits = search(todos.iter().enumerate(), "交给");
+        let mark = |s: &str| format!("[{}]", s);
+        let ranges = &hits[0].highlights[&Field::Annotation(0)];
+        assert_eq!(highlight(&todos[2].annotations()[0].text, ranges, mark), "已经[交给] manager");
+    }
+
+    #[test]
+    fn test_highlight() {
+        let todos = todos();
+        let hits = search(todos.iter().enumerate(), "gzbg 审核");
+        let hit = &hits[0];
+        let mark = |s: &str| format!("[{}]", s);
+        assert_eq!(highlight(hit.todo.title(), &hit.highlights[&Field::Title], mark), "提交[工作报告]");
+        assert_eq!(
+            highlight(hit.todo.description().unwrap(), &hit.highlights[&Field::Description], mark),
+            "发给 Manager [审核]"
+        );
+        assert!(!hit.highlights.contains_key(&Field::Tag(0)));
+
+        assert_eq!(edit_distance(&['a', 'b', 'c'], &['b', 'a', 'c']), 1);
+        assert_eq!(merge_ranges(vec![4..6, 0..2, 1..3, 6..7]), [0..3, 4..7]);
+    }
+}
@@ -0,0 +1,278 @@
+//! `todo serve`：通过 JSON REST API 共享待办事项
+//!
+//! | 请求                        | 说明                                                   |
+//! |-----------------------------|--------------------------------------------------------|
+//! | `GET /todos`                | 列出待办事项，查询参数与 `list` 相同：`all`、`priority`、`tag`、`due`、`overdue`、`search`、`sort`、`collate` |
+//! | `POST /todos`               | 创建待办事项，必须提供 `title`                         |
+//! | `GET /todos/{uid}`          | 获取一个待办事项                                       |
+//! | `PATCH /todos/{uid}`        | 修改 `title`、`description`、`priority`、`due_date`、`tags`、`reminders`、`fields`（自定义字段） |
+//! | `DELETE /todos/{uid}`       | 删除待办事项                                           |
+//! | `POST /todos/{uid}/done`    | 标记为已完成（`/undone` 标记为未完成）                 |
+//!
+//! 单个待办事项的响应都带有 `ETag`。修改请求可以带上 `If-Match`，
+//! 如果事项在此期间已被其他客户端修改，请求会以 412 拒绝，而不是覆盖别人的修改。
+//! 每个请求都会重新加载数据文件，因此命令行中的修改会立即反映出来。
+//!
+//! `POST` 和 `PATCH` 请求必须使用 `Content-Type: application/json`，
+//! 这样网页无法通过不需要预检的跨域请求修改本地的待办事项。
+//!
+//! 创建、修改、完成和删除会运行与命令行相同的钩子。钩子拒绝时请求以 409 失败，
+//! 钩子输出的提示显示在服务的标准错误中。
+
+use std::io::{self, Cursor, Write};
+
+use serde_json::{json, Map, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::api;
+use crate::fields::FieldDecls;
+use crate::filter::Filter;
+use crate::hooks::{Event, HookError, Hooks, Outcome};
+use crate::storage::{StorageError, TodoStorage};
+use crate::todo::Todo;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// API 错误，以 `{"error": 错误码, "message": 说明}` 的形式返回
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError { status, code, message: message.into() }
+    }
+
+    fn validation(message: impl Into<String>) -> Self {
+        Self::new(422, "validation", message)
+    }
+
+    fn not_found(uid: &str) -> Self {
+        Self::new(404, "not_found", format!("找不到待办事项: {}", uid))
+    }
+
+    fn into_response(self) -> HttpResponse {
+        json_response(self.status, &json!({ "error": self.code, "message": self.message }))
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::InvalidId(_) => Self::new(404, "not_found", e.to_string()),
+            // 数据文件损坏或无法读写是服务端的问题，请求体的错误由 read_object 处理
+            _ => Self::new(500, "storage", e.to_string()),
+        }
+    }
+}
+
+impl From<HookError> for ApiError {
+    fn from(e: HookError) -> Self {
+        match e {
+            HookError::Rejected { .. } => Self::new(409, "hook", e.to_string()),
+            _ => Self::new(500, "hook", e.to_string()),
+        }
+    }
+}
+
+/// 在指定地址上启动服务，直到进程退出
+///
+/// 地址的端口为 0 时由系统分配，实际监听的地址会打印到标准输出。
+pub fn serve(mut storage: TodoStorage, hooks: &Hooks, decls: &FieldDecls, bind: &str) -> io::Result<()> {
+    let server = Server::http(bind).map_err(io::Error::other)?;
+    println!("正在监听 http://{}", server.server_addr());
+    io::stdout().flush()?;
+
+    for mut request in server.incoming_requests() {
+        let response = handle(&mut storage, hooks, decls, &mut request).unwrap_or_else(ApiError::into_response);
+        if let Err(e) = request.respond(response) {
+            eprintln!("发送响应时出错: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(storage: &mut TodoStorage, hooks: &Hooks, decls: &FieldDecls, request: &mut Request) -> Result<HttpResponse, ApiError> {
+    match storage.load() {
+        Ok(()) => {}
+        Err(StorageError::FileNotFound) => storage.set_todos(Vec::new()),
+        Err(e) => return Err(e.into()),
+    }
+
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let method = request.method().clone();
+    if matches!(method, Method::Post | Method::Patch) && !is_json(request) {
+        return Err(ApiError::new(415, "unsupported_media_type", "请求必须使用 Content-Type: application/json"));
+    }
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["todos"]) => list(storage, query),
+        (Method::Post, ["todos"]) => create(storage, hooks, decls, read_object(request)?),
+        (Method::Get, ["todos", uid]) => {
+            let index = storage.index_of(uid).ok_or_else(|| ApiError::not_found(uid))?;
+            Ok(todo_response(200, &storage.todos()[index]))
+        }
+        (Method::Patch, ["todos", uid]) => {
+            let index = find_for_update(storage, request, uid)?;
+            let fields = read_object(request)?;
+            let before = &storage.todos()[index];
+            let mut todo = before.clone();
+            api::apply_fields(&mut todo, fields, decls).map_err(ApiError::validation)?;
+            let todo = checked_hook(hooks.on_modify(Event::Modify, before, todo))?;
+            storage.update(index, todo)?;
+            storage.save()?;
+            Ok(todo_response(200, &storage.todos()[index]))
+        }
+        (Method::Delete, ["todos", uid]) => {
+            let index = find_for_update(storage, request, uid)?;
+            checked_hook(hooks.on_remove(&storage.todos()[index]))?;
+            storage.remove(index)?;
+            storage.save()?;
+            Ok(Response::from_data(Vec::new()).with_status_code(204))
+        }
+        (Method::Post, ["todos", uid, action @ ("done" | "undone")]) => {
+            let index = find_for_update(storage, request, uid)?;
+            let before = &storage.todos()[index];
+            let mut todo = before.clone();
+            let event = if *action == "done" {
+                todo.mark_as_done_at(storage.clock().now());
+                Event::Done
+            } else {
+                todo.mark_as_undone();
+                Event::Modify
+            };
+            let todo = checked_hook(hooks.on_modify(event, before, todo))?;
+            storage.update(index, todo)?;
+            storage.save()?;
+            Ok(todo_response(200, &storage.todos()[index]))
+        }
+        (_, ["todos"]) | (_, ["todos", _]) | (_, ["todos", _, "done" | "undone"]) => {
+            Err(ApiError::new(405, "method_not_allowed", "不支持的请求方法"))
+        }
+        _ => Err(ApiError::new(404, "not_found", format!("未知的路径: {}", path))),
+    }
+}
+
+/// `GET /todos`，查询参数与 `list` 命令的选项相同
+fn list(storage: &TodoStorage, query: &str) -> Result<HttpResponse, ApiError> {
+    let mut filter = Filter::default();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        api::set_filter_option(&mut filter, key, &percent_decode(value)).map_err(ApiError::validation)?;
+    }
+
+    let todos: Vec<&Todo> = filter.apply(storage.todos(), storage.clock().as_ref()).into_iter().map(|(_, todo)| todo).collect();
+    Ok(json_response(200, &json!(todos)))
+}
+
+/// `POST /todos`
+fn create(storage: &mut TodoStorage, hooks: &Hooks, decls: &FieldDecls, fields: Map<String, Value>) -> Result<HttpResponse, ApiError> {
+    let mut todo = api::new_todo(fields, storage.clock().now(), decls).map_err(ApiError::validation)?;
+    let uid = todo.ensure_uid().to_string();
+    let todo = checked_hook(hooks.on_add(todo))?;
+    storage.add(todo);
+    storage.save()?;
+    let index = storage.index_of(&uid).ok_or_else(|| ApiError::not_found(&uid))?;
+    let location = Header::from_bytes("Location", format!("/todos/{}", uid)).unwrap();
+    Ok(todo_response(201, &storage.todos()[index]).with_header(location))
+}
+
+/// 在标准错误中显示钩子的提示，返回钩子修改后的事项
+fn checked_hook<T>(result: Result<Outcome<T>, HookError>) -> Result<T, ApiError> {
+    let outcome = result?;
+    for message in outcome.messages {
+        eprintln!("{}", message);
+    }
+    Ok(outcome.value)
+}
+
+/// 查找要修改的待办事项，并检查 `If-Match`
+fn find_for_update(storage: &TodoStorage, request: &Request, uid: &str) -> Result<usize, ApiError> {
+    let index = storage.index_of(uid).ok_or_else(|| ApiError::not_found(uid))?;
+    if let Some(expected) = header(request, "If-Match") {
+        let current = etag(&storage.todos()[index]);
+        let matched = expected
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == current);
+        if !matched {
+            return Err(ApiError::new(412, "conflict", "待办事项已被修改，请重新获取后再试"));
+        }
+    }
+    Ok(index)
+}
+
+fn read_object(request: &mut Request) -> Result<Map<String, Value>, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::new(400, "invalid_body", format!("读取请求时出错: {}", e)))?;
+    let body = serde_json::from_str(&body)
+        .map_err(|e| ApiError::new(400, "invalid_json", format!("请求体不是有效的 JSON: {}", e)))?;
+    match body {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(ApiError::validation("请求体必须是 JSON 对象")),
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+fn is_json(request: &Request) -> bool {
+    header(request, "Content-Type")
+        .is_some_and(|t| t.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// 待办事项的实体标签，内容不变时保持不变
+fn etag(todo: &Todo) -> String {
+    format!("\"{:016x}\"", todo.fingerprint())
+}
+
+fn todo_response(status: u16, todo: &Todo) -> HttpResponse {
+    let etag = Header::from_bytes("ETag", etag(todo)).unwrap();
+    json_response(status, &json!(todo)).with_header(etag)
+}
+
+fn json_response(status: u16, body: &Value) -> HttpResponse {
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap();
+    Response::from_data(body.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+/// 解码查询参数中的 `%XX` 和 `+`
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        decoded.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
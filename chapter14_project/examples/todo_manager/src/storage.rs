@@ -1,8 +1,11 @@
+use crate::clock::{Clock, SystemClock};
+use crate::crypto::{self, KeySource};
+use crate::format::todotxt;
 use crate::todo::Todo;
-use serde_json;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -15,31 +18,117 @@ pub enum StorageError {
     SerializationError(#[from] serde_json::Error),
     #[error("无效的ID: {0}")]
     InvalidId(usize),
+    #[error("数据文件已加密，请通过 --key-file 或 TODO_PASSPHRASE 提供密钥")]
+    KeyRequired,
+    #[error("解密失败：密码错误或数据文件已被篡改")]
+    DecryptionFailed,
+    #[error("加密错误: {0}")]
+    EncryptionError(String),
+}
+
+/// 数据文件的存储格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageFormat {
+    /// JSON 数组
+    Json,
+    /// todo.txt 文本，便于其他 todo.txt 工具直接编辑
+    TodoTxt,
+}
+
+impl StorageFormat {
+    /// 根据文件扩展名推断存储格式，`.txt` 文件视为 todo.txt
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt") => StorageFormat::TodoTxt,
+            _ => StorageFormat::Json,
+        }
+    }
 }
 
 pub struct TodoStorage {
     todos: Vec<Todo>,
     file_path: PathBuf,
+    format: StorageFormat,
+    /// 加密密钥，设置后保存时写入加密信封
+    key: Option<KeySource>,
+    /// 加载的数据文件是否是加密的
+    encrypted: bool,
+    /// 记录完成和修改时间，以及解析没有创建时间的事项时使用
+    clock: Arc<dyn Clock>,
 }
 
 impl TodoStorage {
-    /// 创建一个新的待办事项存储
+    /// 创建一个新的待办事项存储，存储格式由文件扩展名决定
     pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        let format = StorageFormat::from_path(file_path.as_ref());
+        Self::with_format(file_path, format)
+    }
+
+    /// 创建一个使用指定存储格式的待办事项存储
+    pub fn with_format<P: AsRef<Path>>(file_path: P, format: StorageFormat) -> Self {
         TodoStorage {
             todos: Vec::new(),
             file_path: file_path.as_ref().to_path_buf(),
+            format,
+            key: None,
+            encrypted: false,
+            clock: Arc::new(SystemClock),
         }
     }
 
-    /// 从文件加载待办事项
+    /// 设置存储使用的时钟，默认为系统时钟
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// 存储使用的时钟
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// 设置加密密钥，设为 `None` 时以明文保存
+    pub fn set_key(&mut self, key: Option<KeySource>) {
+        self.key = key;
+    }
+
+    /// 数据文件的路径
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// 检查加载的数据文件是否是加密的
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// 从文件加载待办事项，并为没有标识符的事项分配标识符
     pub fn load(&mut self) -> Result<(), StorageError> {
+        self.todos = self.read()?;
+        for todo in &mut self.todos {
+            todo.ensure_uid();
+        }
+        Ok(())
+    }
+
+    /// 读取文件中的待办事项，保持文件中的原样，不分配标识符
+    pub fn read(&mut self) -> Result<Vec<Todo>, StorageError> {
         let file = File::open(&self.file_path);
         match file {
             Ok(mut file) => {
                 let mut contents = String::new();
                 file.read_to_string(&mut contents)?;
-                self.todos = serde_json::from_str(&contents)?;
-                Ok(())
+                self.encrypted = crypto::is_encrypted(&contents);
+                if self.encrypted {
+                    let key = self.key.as_ref().ok_or(StorageError::KeyRequired)?;
+                    let plaintext = crypto::decrypt(&contents, key)?;
+                    contents = String::from_utf8(plaintext).map_err(|_| StorageError::DecryptionFailed)?;
+                }
+                Ok(match self.format {
+                    // 空文件视为没有待办事项，例如 git 在没有共同祖先时提供的空文件
+                    StorageFormat::Json if contents.trim().is_empty() => Vec::new(),
+                    StorageFormat::Json => serde_json::from_str(&contents)?,
+                    StorageFormat::TodoTxt => todotxt::parse(&contents, self.clock.now()),
+                })
             }
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
                 Err(StorageError::FileNotFound)
@@ -49,18 +138,88 @@ impl TodoStorage {
     }
 
     /// 保存待办事项到文件
+    ///
+    /// 先写入同一目录下的临时文件，再替换原文件，写入中途出错不会损坏原有的数据。
     pub fn save(&self) -> Result<(), StorageError> {
-        let contents = serde_json::to_string_pretty(&self.todos)?;
-        let mut file = File::create(&self.file_path)?;
+        let contents = self.serialize()?;
+        let mut tmp_name = self.file_path.as_os_str().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        let mut file = File::create(&tmp_path)?;
         file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        // 保留原文件的权限
+        if let Ok(metadata) = std::fs::metadata(&self.file_path) {
+            std::fs::set_permissions(&tmp_path, metadata.permissions())?;
+        }
+        std::fs::rename(&tmp_path, &self.file_path)?;
         Ok(())
     }
 
+    /// 按存储格式序列化待办事项，设置了密钥时返回加密信封
+    pub fn serialize(&self) -> Result<String, StorageError> {
+        let mut contents = match self.format {
+            StorageFormat::Json => serde_json::to_string_pretty(&self.todos)?,
+            StorageFormat::TodoTxt => todotxt::to_string(&self.todos),
+        };
+        if let Some(key) = &self.key {
+            contents = crypto::encrypt(contents.as_bytes(), key)?;
+        }
+        Ok(contents)
+    }
+
+    /// 替换全部待办事项
+    pub fn set_todos(&mut self, todos: Vec<Todo>) {
+        self.todos = todos;
+    }
+
     /// 添加一个新的待办事项
     pub fn add(&mut self, todo: Todo) {
         self.todos.push(todo);
     }
 
+    /// 用修改后的事项替换第 `id` 个待办事项
+    ///
+    /// 事项在修改时记录了新的修改时间的，改为存储的时钟的当前时间，使 `TODO_NOW` 等固定的时钟同样适用于修改时间。
+    pub fn update(&mut self, id: usize, mut todo: Todo) -> Result<(), StorageError> {
+        let current = self.todos.get_mut(id).ok_or(StorageError::InvalidId(id))?;
+        if todo.modified_at() != current.modified_at() {
+            todo.set_modified_at(Some(self.clock.now()));
+        }
+        *current = todo;
+        Ok(())
+    }
+
+    /// 按标识符添加或替换待办事项，返回是否替换了已有的事项
+    pub fn upsert(&mut self, todo: Todo) -> bool {
+        let existing = todo
+            .uid()
+            .and_then(|uid| self.todos.iter().position(|t| t.uid() == Some(uid)));
+        match existing {
+            Some(index) => {
+                self.todos[index] = todo;
+                true
+            }
+            None => {
+                self.todos.push(todo);
+                false
+            }
+        }
+    }
+
+    /// 按标题查找待办事项的编号，标题相同时优先返回未完成的事项
+    pub fn find_by_title(&self, title: &str) -> Option<usize> {
+        self.todos
+            .iter()
+            .position(|t| t.title() == title && !t.is_completed())
+            .or_else(|| self.todos.iter().position(|t| t.title() == title))
+    }
+
+    /// 按标识符查找待办事项的编号
+    pub fn index_of(&self, uid: &str) -> Option<usize> {
+        self.todos.iter().position(|t| t.uid() == Some(uid))
+    }
+
     /// 获取所有待办事项
     pub fn todos(&self) -> &[Todo] {
         &self.todos
@@ -68,20 +227,16 @@ impl TodoStorage {
 
     /// 将待办事项标记为已完成
     pub fn mark_done(&mut self, id: usize) -> Result<(), StorageError> {
-        if id >= self.todos.len() {
-            return Err(StorageError::InvalidId(id));
-        }
-        self.todos[id].mark_as_done();
-        Ok(())
+        let mut todo = self.get_todo(id)?.clone();
+        todo.mark_as_done_at(self.clock.now());
+        self.update(id, todo)
     }
     
     /// 将待办事项标记为未完成
     pub fn mark_undone(&mut self, id: usize) -> Result<(), StorageError> {
-        if id >= self.todos.len() {
-            return Err(StorageError::InvalidId(id));
-        }
-        self.todos[id].mark_as_undone();
-        Ok(())
+        let mut todo = self.get_todo(id)?.clone();
+        todo.mark_as_undone();
+        self.update(id, todo)
     }
 
     /// 删除一个待办事项
@@ -93,6 +248,11 @@ impl TodoStorage {
         Ok(())
     }
     
+    /// 获取待办事项的引用
+    pub fn get_todo(&self, id: usize) -> Result<&Todo, StorageError> {
+        self.todos.get(id).ok_or(StorageError::InvalidId(id))
+    }
+    
     /// 获取待办事项的可变引用
     pub fn get_todo_mut(&mut self, id: usize) -> Result<&mut Todo, StorageError> {
         if id >= self.todos.len() {
@@ -0,0 +1,164 @@
+//! 与外部服务同步待办事项
+//!
+//! 同步状态保存在数据文件旁边，记录每个待办事项上次同步时的远程位置、ETag 和内容指纹，
+//! 以便下次只传输发生变化的事项。双方都修改过的事项按最后修改时间决定保留哪一方，
+//! 并追加到冲突日志中。
+
+pub mod caldav;
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::todo::Todo;
+
+/// 一个待办事项上次同步时的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedItem {
+    /// 远程资源的路径
+    pub href: String,
+    /// 远程资源的 ETag，服务器没有返回时为 `None`
+    pub etag: Option<String>,
+    /// 上次同步后本地内容的指纹，用于判断本地是否修改过
+    pub fingerprint: u64,
+}
+
+/// 与一个远程集合的同步状态
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// 远程集合的地址，地址变化时需要重新完整同步
+    pub collection: String,
+    /// 服务器提供的同步令牌，用于增量获取变化
+    pub sync_token: Option<String>,
+    /// 按待办事项标识符索引的同步状态
+    pub items: BTreeMap<String, SyncedItem>,
+}
+
+impl SyncState {
+    /// 同步状态文件的路径，如 `~/.todo.json.caldav.json`
+    pub fn path_for(data_file: &Path, service: &str) -> PathBuf {
+        let mut name = data_file.as_os_str().to_os_string();
+        name.push(format!(".{}.json", service));
+        PathBuf::from(name)
+    }
+
+    /// 读取同步状态，文件不存在或集合地址不同时返回新的状态
+    pub fn load(path: &Path, collection: &str) -> io::Result<Self> {
+        let state = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SyncState::default(),
+            Err(e) => return Err(e),
+        };
+        if state.collection == collection {
+            Ok(state)
+        } else {
+            Ok(SyncState {
+                collection: collection.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// 按远程路径查找待办事项的标识符
+    pub fn uid_for_href(&self, href: &str) -> Option<String> {
+        self.items
+            .iter()
+            .find(|(_, item)| item.href == href)
+            .map(|(uid, _)| uid.clone())
+    }
+}
+
+/// 冲突时保留的一方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+impl Side {
+    /// 按最后修改时间决定保留哪一方，时间相同时保留本地版本
+    pub fn newer(local: &Todo, remote: &Todo) -> Self {
+        if remote.modified_at() > local.modified_at() {
+            Side::Remote
+        } else {
+            Side::Local
+        }
+    }
+}
+
+/// 同步中发生的冲突
+#[derive(Debug, Clone)]
+pub struct SyncConflict {
+    pub uid: String,
+    pub title: String,
+    /// 冲突的原因
+    pub reason: &'static str,
+    pub local_modified: Option<DateTime<Local>>,
+    pub remote_modified: Option<DateTime<Local>>,
+    pub kept: Side,
+}
+
+impl std::fmt::Display for SyncConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let time = |t: &Option<DateTime<Local>>| match t {
+            Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => "已删除".to_string(),
+        };
+        let kept = match self.kept {
+            Side::Local => "本地",
+            Side::Remote => "远程",
+        };
+        write!(
+            f,
+            "{} ({}): {}，本地修改于 {}，远程修改于 {}，保留{}版本",
+            self.title,
+            self.uid,
+            self.reason,
+            time(&self.local_modified),
+            time(&self.remote_modified),
+            kept
+        )
+    }
+}
+
+/// 一次同步的结果
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// 从远程更新或新增到本地的事项数
+    pub pulled: usize,
+    /// 推送到远程的事项数
+    pub pushed: usize,
+    /// 因远程删除而在本地删除的事项数
+    pub deleted_local: usize,
+    /// 因本地删除而在远程删除的事项数
+    pub deleted_remote: usize,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// 冲突日志的路径，如 `~/.todo.json.caldav-conflicts.log`
+pub fn conflict_log_path(data_file: &Path, service: &str) -> PathBuf {
+    let mut name = data_file.as_os_str().to_os_string();
+    name.push(format!(".{}-conflicts.log", service));
+    PathBuf::from(name)
+}
+
+/// 将冲突追加到冲突日志，每行一条，带有记录时间 `now`
+pub fn append_conflict_log(path: &Path, conflicts: &[SyncConflict], now: &DateTime<Local>) -> io::Result<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let now = now.format("%Y-%m-%d %H:%M:%S");
+    for conflict in conflicts {
+        writeln!(file, "[{}] {}", now, conflict)?;
+    }
+    Ok(())
+}
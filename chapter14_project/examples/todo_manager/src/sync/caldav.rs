@@ -0,0 +1,624 @@
+//! CalDAV 同步：每个待办事项是日历集合中的一个 VTODO 资源
+//!
+//! 优先使用 `sync-collection` 报告（RFC 6578）按同步令牌增量获取远程变化；
+//! 服务器不支持时退回到 `PROPFIND` 列出全部资源的 ETag。
+//! 上传使用 `If-Match`/`If-None-Match` 条件请求，远程在此期间被修改时不会被覆盖。
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use thiserror::Error;
+
+use super::{Side, SyncConflict, SyncReport, SyncState, SyncedItem};
+use crate::format::ical;
+use crate::storage::{StorageError, TodoStorage};
+use crate::todo::Todo;
+
+const DAV_NS: &str = "DAV:";
+
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("CalDAV 请求失败: {0}")]
+    Http(String),
+    #[error("服务器返回 {status}: {method} {url}")]
+    Status { status: u16, method: &'static str, url: String },
+    #[error("无法解析服务器响应: {0}")]
+    InvalidResponse(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("读写同步状态 {0} 时出错: {1}")]
+    State(PathBuf, io::Error),
+}
+
+/// 远程的一个 VTODO 资源
+struct Remote {
+    todo: Todo,
+    etag: Option<String>,
+}
+
+/// 远程集合的变化
+struct Changes {
+    /// 变化的资源及其 ETag，`None` 表示已删除
+    items: Vec<(String, Option<String>)>,
+    sync_token: Option<String>,
+    /// 是否列出了集合中的全部资源，此时没有列出的资源视为已删除
+    complete: bool,
+}
+
+/// 条件上传的结果
+enum PutResult {
+    Stored(Option<String>),
+    PreconditionFailed,
+}
+
+/// CalDAV 日历集合的客户端
+pub struct CalDavClient {
+    agent: ureq::Agent,
+    /// 服务器地址中的协议和主机部分，如 `https://example.com`
+    origin: String,
+    /// 集合的路径，以 `/` 结尾
+    path: String,
+    authorization: Option<String>,
+}
+
+impl CalDavClient {
+    /// 创建指向 `url` 下名为 `calendar` 的日历集合的客户端
+    pub fn new(url: &str, calendar: &str, credentials: Option<(&str, &str)>) -> Self {
+        let collection = format!("{}/{}/", url.trim_end_matches('/'), calendar.trim_matches('/'));
+        let (origin, path) = match collection.find("://").and_then(|i| collection[i + 3..].find('/').map(|j| i + 3 + j)) {
+            Some(i) => (collection[..i].to_string(), collection[i..].to_string()),
+            None => (collection.trim_end_matches('/').to_string(), "/".to_string()),
+        };
+        let authorization = credentials
+            .map(|(user, password)| format!("Basic {}", BASE64.encode(format!("{}:{}", user, password))));
+        CalDavClient {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+            origin,
+            path,
+            authorization,
+        }
+    }
+
+    /// 集合的完整地址，用于识别同步状态属于哪个集合
+    pub fn collection_url(&self) -> String {
+        format!("{}{}", self.origin, self.path)
+    }
+
+    fn url(&self, href: &str) -> String {
+        if href.contains("://") {
+            href.to_string()
+        } else if href.starts_with('/') {
+            format!("{}{}", self.origin, href)
+        } else {
+            format!("{}{}", self.collection_url(), href)
+        }
+    }
+
+    /// 发送请求，4xx/5xx 响应也作为正常结果返回，由调用方检查状态码
+    fn send(
+        &self,
+        method: &'static str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<ureq::Response, SyncError> {
+        let mut request = self.agent.request(method, &self.url(href));
+        if let Some(auth) = &self.authorization {
+            request = request.set("Authorization", auth);
+        }
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        let result = match body {
+            Some(body) => request.send_string(body),
+            None => request.call(),
+        };
+        match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => Ok(response),
+            Err(e) => Err(SyncError::Http(e.to_string())),
+        }
+    }
+
+    fn status_error(&self, method: &'static str, href: &str, status: u16) -> SyncError {
+        SyncError::Status { status, method, url: self.url(href) }
+    }
+
+    /// 获取上次同步以来的变化，同步令牌失效或服务器不支持增量同步时列出全部资源
+    fn changes(&self, sync_token: Option<&str>) -> Result<Changes, SyncError> {
+        let token = match sync_token {
+            Some(token) => format!("<d:sync-token>{}</d:sync-token>", xml_escape(token)),
+            None => "<d:sync-token/>".to_string(),
+        };
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <d:sync-collection xmlns:d=\"DAV:\">{}<d:sync-level>1</d:sync-level>\
+             <d:prop><d:getetag/></d:prop></d:sync-collection>",
+            token
+        );
+        let headers = [("Content-Type", "application/xml; charset=utf-8"), ("Depth", "1")];
+        let response = self.send("REPORT", &self.path, &headers, Some(&body))?;
+        match response.status() {
+            207 => {
+                let xml = read_body(response)?;
+                // 不带令牌的请求返回集合中的全部资源
+                Ok(Changes { complete: sync_token.is_none(), ..parse_multistatus(&xml, &self.path)? })
+            }
+            // 同步令牌失效，重新完整同步
+            403 | 409 if sync_token.is_some() => self.changes(None),
+            400 | 403 | 405 | 409 | 415 | 501 => self.list_all(),
+            status => Err(self.status_error("REPORT", &self.path, status)),
+        }
+    }
+
+    /// 用 `PROPFIND` 列出集合中全部资源的 ETag
+    fn list_all(&self) -> Result<Changes, SyncError> {
+        let body = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                    <d:propfind xmlns:d=\"DAV:\"><d:prop><d:getetag/></d:prop></d:propfind>";
+        let headers = [("Content-Type", "application/xml; charset=utf-8"), ("Depth", "1")];
+        let response = self.send("PROPFIND", &self.path, &headers, Some(body))?;
+        if response.status() != 207 {
+            return Err(self.status_error("PROPFIND", &self.path, response.status()));
+        }
+        let changes = parse_multistatus(&read_body(response)?, &self.path)?;
+        Ok(Changes { sync_token: None, complete: true, ..changes })
+    }
+
+    /// 下载一个资源，不存在时返回 `None`；资源没有写出的创建、完成时间以 `now` 补齐
+    fn get(&self, href: &str, now: DateTime<Local>) -> Result<Option<Remote>, SyncError> {
+        let response = self.send("GET", href, &[], None)?;
+        match response.status() {
+            200 => {
+                let etag = response.header("ETag").map(str::to_string);
+                let todo = ical::parse(&read_body(response)?, now)
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| SyncError::InvalidResponse(format!("{} 中没有 VTODO", href)))?;
+                Ok(Some(Remote { todo, etag }))
+            }
+            404 | 410 => Ok(None),
+            status => Err(self.status_error("GET", href, status)),
+        }
+    }
+
+    /// 上传一个待办事项；`etag` 为 `None` 时只在远程不存在时创建
+    fn put(&self, href: &str, todo: &Todo, etag: Option<&str>) -> Result<PutResult, SyncError> {
+        let condition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let headers = [("Content-Type", "text/calendar; charset=utf-8"), condition];
+        let body = ical::to_string(std::iter::once(todo));
+        let response = self.send("PUT", href, &headers, Some(&body))?;
+        match response.status() {
+            200..=299 => Ok(PutResult::Stored(response.header("ETag").map(str::to_string))),
+            412 => Ok(PutResult::PreconditionFailed),
+            status => Err(self.status_error("PUT", href, status)),
+        }
+    }
+
+    /// 删除一个资源，远程在此期间被修改时返回 `false`
+    fn delete(&self, href: &str, etag: Option<&str>) -> Result<bool, SyncError> {
+        let headers: Vec<(&str, &str)> = etag.map(|etag| ("If-Match", etag)).into_iter().collect();
+        let response = self.send("DELETE", href, &headers, None)?;
+        match response.status() {
+            200..=299 | 404 | 410 => Ok(true),
+            412 => Ok(false),
+            status => Err(self.status_error("DELETE", href, status)),
+        }
+    }
+
+    /// 新的待办事项在集合中的路径
+    fn href_for(&self, uid: &str) -> String {
+        let name: String = uid
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect();
+        format!("{}{}.ics", self.path, name)
+    }
+}
+
+fn read_body(response: ureq::Response) -> Result<String, SyncError> {
+    response
+        .into_string()
+        .map_err(|e| SyncError::InvalidResponse(e.to_string()))
+}
+
+/// 解析 multistatus 响应中的资源路径、ETag 和同步令牌
+fn parse_multistatus(xml: &str, collection: &str) -> Result<Changes, SyncError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| SyncError::InvalidResponse(e.to_string()))?;
+    let dav = |node: &roxmltree::Node, name: &str| {
+        node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(DAV_NS)
+    };
+    let child_text = |node: &roxmltree::Node, name: &str| {
+        node.descendants()
+            .find(|n| dav(n, name))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let mut items = Vec::new();
+    for response in doc.root_element().children().filter(|n| dav(n, "response")) {
+        let Some(href) = child_text(&response, "href") else {
+            continue;
+        };
+        // 有的服务器返回完整地址，只保留路径部分
+        let href = match href.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]).to_string(),
+            None => href,
+        };
+        if href.trim_end_matches('/') == collection.trim_end_matches('/') {
+            continue;
+        }
+        // 直接位于 response 下的 404 状态表示资源已被删除
+        let deleted = response
+            .children()
+            .filter(|n| dav(n, "status"))
+            .filter_map(|n| n.text())
+            .any(|status| status.contains(" 404"));
+        let etag = if deleted { None } else { child_text(&response, "getetag") };
+        if deleted || etag.is_some() {
+            items.push((href, etag));
+        }
+    }
+    let sync_token = doc
+        .root_element()
+        .children()
+        .find(|n| dav(n, "sync-token"))
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string());
+    Ok(Changes { items, sync_token, complete: false })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// 同步数据文件：读取数据文件旁边的同步状态，同步后保存数据文件和同步状态
+pub fn sync_file(storage: &mut TodoStorage, client: &CalDavClient) -> Result<SyncReport, SyncError> {
+    let state_path = SyncState::path_for(storage.file_path(), "caldav");
+    let mut state = SyncState::load(&state_path, &client.collection_url())
+        .map_err(|e| SyncError::State(state_path.clone(), e))?;
+    let report = sync(storage, client, &mut state)?;
+    storage.save()?;
+    state.save(&state_path).map_err(|e| SyncError::State(state_path, e))?;
+    Ok(report)
+}
+
+/// 与 CalDAV 集合双向同步
+///
+/// 先拉取远程的变化，再推送本地的修改和删除。调用方负责保存存储和同步状态。
+pub fn sync(storage: &mut TodoStorage, client: &CalDavClient, state: &mut SyncState) -> Result<SyncReport, SyncError> {
+    let mut report = SyncReport::default();
+    let changes = client.changes(state.sync_token.as_deref())?;
+
+    let mut seen = BTreeSet::new();
+    for (href, etag) in changes.items {
+        seen.insert(href.clone());
+        let known_uid = state.uid_for_href(&href);
+        let Some(etag) = etag else {
+            if let Some(uid) = known_uid {
+                remote_deleted(storage, state, &uid, &mut report)?;
+            }
+            continue;
+        };
+        // 自己上次推送的版本
+        if known_uid.as_ref().and_then(|uid| state.items.get(uid)).is_some_and(|i| i.etag.as_ref() == Some(&etag)) {
+            continue;
+        }
+        match client.get(&href, storage.clock().now())? {
+            Some(remote) => {
+                let uid = known_uid.unwrap_or_else(|| remote.todo.uid().unwrap_or_default().to_string());
+                remote_changed(storage, state, &uid, &href, remote, &mut report)?;
+            }
+            None => {
+                if let Some(uid) = known_uid {
+                    remote_deleted(storage, state, &uid, &mut report)?;
+                }
+            }
+        }
+    }
+    if changes.complete {
+        let missing: Vec<String> = state
+            .items
+            .iter()
+            .filter(|(_, item)| !seen.contains(&item.href))
+            .map(|(uid, _)| uid.clone())
+            .collect();
+        for uid in missing {
+            remote_deleted(storage, state, &uid, &mut report)?;
+        }
+    }
+    state.sync_token = changes.sync_token;
+
+    // 推送本地新增和修改的事项
+    let uids: Vec<String> = storage.todos().iter().filter_map(|t| t.uid().map(str::to_string)).collect();
+    for uid in uids {
+        push(storage, client, state, &uid, &mut report)?;
+    }
+
+    // 删除本地已删除的事项
+    let removed: Vec<(String, SyncedItem)> = state
+        .items
+        .iter()
+        .filter(|(uid, _)| storage.index_of(uid).is_none())
+        .map(|(uid, item)| (uid.clone(), item.clone()))
+        .collect();
+    for (uid, item) in removed {
+        if client.delete(&item.href, item.etag.as_deref())? {
+            state.items.remove(&uid);
+            report.deleted_remote += 1;
+            continue;
+        }
+        // 远程在本地删除后又被修改过，恢复远程的版本
+        if let Some(remote) = client.get(&item.href, storage.clock().now())? {
+            report.conflicts.push(SyncConflict {
+                uid: uid.clone(),
+                title: remote.todo.title().to_string(),
+                reason: "本地已删除，远程有新的修改",
+                local_modified: None,
+                remote_modified: Some(*remote.todo.modified_at()),
+                kept: Side::Remote,
+            });
+            store_remote(storage, state, &uid, &item.href, remote);
+            report.pulled += 1;
+        } else {
+            state.items.remove(&uid);
+        }
+    }
+    Ok(report)
+}
+
+/// 处理远程的新增或修改
+fn remote_changed(
+    storage: &mut TodoStorage,
+    state: &mut SyncState,
+    uid: &str,
+    href: &str,
+    remote: Remote,
+    report: &mut SyncReport,
+) -> Result<(), SyncError> {
+    let synced = state.items.get(uid);
+    match (storage.index_of(uid), synced) {
+        (Some(index), Some(synced)) if storage.todos()[index].fingerprint() == synced.fingerprint => {
+            store_remote(storage, state, uid, href, remote);
+            report.pulled += 1;
+        }
+        (Some(index), None) if same_content(&storage.todos()[index], &remote.todo) => {
+            // 首次同步时双方已有相同的事项，只记录同步状态
+            state.items.insert(
+                uid.to_string(),
+                SyncedItem {
+                    href: href.to_string(),
+                    etag: remote.etag,
+                    fingerprint: storage.todos()[index].fingerprint(),
+                },
+            );
+        }
+        (Some(index), _) => {
+            // 双方都修改过，按最后修改时间决定
+            let local = &storage.todos()[index];
+            let kept = Side::newer(local, &remote.todo);
+            report.conflicts.push(SyncConflict {
+                uid: uid.to_string(),
+                title: local.title().to_string(),
+                reason: "本地和远程都修改了该事项",
+                local_modified: Some(*local.modified_at()),
+                remote_modified: Some(*remote.todo.modified_at()),
+                kept,
+            });
+            match kept {
+                Side::Remote => {
+                    store_remote(storage, state, uid, href, remote);
+                    report.pulled += 1;
+                }
+                Side::Local => {
+                    // 记下远程的 ETag，推送时以此为条件覆盖远程
+                    state.items.insert(
+                        uid.to_string(),
+                        SyncedItem {
+                            href: href.to_string(),
+                            etag: remote.etag,
+                            fingerprint: remote.todo.fingerprint(),
+                        },
+                    );
+                }
+            }
+        }
+        (None, Some(_)) => {
+            report.conflicts.push(SyncConflict {
+                uid: uid.to_string(),
+                title: remote.todo.title().to_string(),
+                reason: "本地已删除，远程有新的修改",
+                local_modified: None,
+                remote_modified: Some(*remote.todo.modified_at()),
+                kept: Side::Remote,
+            });
+            store_remote(storage, state, uid, href, remote);
+            report.pulled += 1;
+        }
+        (None, None) => {
+            store_remote(storage, state, uid, href, remote);
+            report.pulled += 1;
+        }
+    }
+    Ok(())
+}
+
+/// 本地事项与远程副本的内容是否相同
+///
+/// 本地事项先经过一次 iCalendar 的转换，忽略 iCalendar 不保存的信息和时间精度的差异；
+/// 最后修改时间不参与比较；转换后的本地事项总带有创建和完成时间，不会用到补齐的时间。
+fn same_content(local: &Todo, remote: &Todo) -> bool {
+    let fingerprint = |todo: &Todo| {
+        let mut todo = todo.clone();
+        todo.set_modified_at(None);
+        todo.fingerprint()
+    };
+    ical::parse(&ical::to_string(std::iter::once(local)), *local.created_at())
+        .first()
+        .is_some_and(|local| fingerprint(local) == fingerprint(remote))
+}
+
+/// 处理远程的删除：本地没有修改过时一并删除，否则保留本地版本并在推送时重新创建
+fn remote_deleted(
+    storage: &mut TodoStorage,
+    state: &mut SyncState,
+    uid: &str,
+    report: &mut SyncReport,
+) -> Result<(), SyncError> {
+    let Some(synced) = state.items.remove(uid) else {
+        return Ok(());
+    };
+    let Some(index) = storage.index_of(uid) else {
+        return Ok(());
+    };
+    let local = &storage.todos()[index];
+    if local.fingerprint() == synced.fingerprint {
+        storage.remove(index)?;
+        report.deleted_local += 1;
+    } else {
+        report.conflicts.push(SyncConflict {
+            uid: uid.to_string(),
+            title: local.title().to_string(),
+            reason: "远程已删除，本地有新的修改",
+            local_modified: Some(*local.modified_at()),
+            remote_modified: None,
+            kept: Side::Local,
+        });
+    }
+    Ok(())
+}
+
+/// 推送一个待办事项，远程在此期间被修改时按最后修改时间解决冲突
+fn push(
+    storage: &mut TodoStorage,
+    client: &CalDavClient,
+    state: &mut SyncState,
+    uid: &str,
+    report: &mut SyncReport,
+) -> Result<(), SyncError> {
+    let Some(index) = storage.index_of(uid) else {
+        return Ok(());
+    };
+    let local = storage.todos()[index].clone();
+    let (href, etag) = match state.items.get(uid) {
+        Some(item) if item.fingerprint == local.fingerprint() => return Ok(()),
+        Some(item) => (item.href.clone(), item.etag.clone()),
+        None => (client.href_for(uid), None),
+    };
+
+    match client.put(&href, &local, etag.as_deref())? {
+        PutResult::Stored(etag) => {
+            state.items.insert(
+                uid.to_string(),
+                SyncedItem { href, etag, fingerprint: local.fingerprint() },
+            );
+            report.pushed += 1;
+        }
+        PutResult::PreconditionFailed => {
+            let Some(remote) = client.get(&href, storage.clock().now())? else {
+                return Err(SyncError::InvalidResponse(format!("{} 的条件上传失败，但资源不存在", href)));
+            };
+            let kept = Side::newer(&local, &remote.todo);
+            report.conflicts.push(SyncConflict {
+                uid: uid.to_string(),
+                title: local.title().to_string(),
+                reason: "推送时远程已被修改",
+                local_modified: Some(*local.modified_at()),
+                remote_modified: Some(*remote.todo.modified_at()),
+                kept,
+            });
+            match kept {
+                Side::Remote => {
+                    store_remote(storage, state, uid, &href, remote);
+                    report.pulled += 1;
+                }
+                Side::Local => match client.put(&href, &local, remote.etag.as_deref())? {
+                    PutResult::Stored(etag) => {
+                        state.items.insert(
+                            uid.to_string(),
+                            SyncedItem { href, etag, fingerprint: local.fingerprint() },
+                        );
+                        report.pushed += 1;
+                    }
+                    PutResult::PreconditionFailed => {
+                        return Err(SyncError::Http(format!("{} 在同步期间被反复修改，请稍后重试", href)));
+                    }
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 用远程版本替换或新增本地的待办事项，并记录同步状态
+///
+/// 远程版本中无法表示的信息（注释、没有截止日期时的提醒时间）保留本地的值。
+fn store_remote(storage: &mut TodoStorage, state: &mut SyncState, uid: &str, href: &str, remote: Remote) {
+    let mut todo = remote.todo;
+    todo.set_uid(uid.to_string());
+    let index = storage.index_of(uid);
+    if let Some(index) = index {
+        keep_local_only(&mut todo, &storage.todos()[index]);
+    }
+    let fingerprint = todo.fingerprint();
+    match index {
+        Some(index) => *storage.get_todo_mut(index).expect("索引来自 index_of") = todo,
+        None => storage.add(todo),
+    }
+    state.items.insert(
+        uid.to_string(),
+        SyncedItem {
+            href: href.to_string(),
+            etag: remote.etag,
+            fingerprint,
+        },
+    );
+}
+
+/// 将本地待办事项中远程版本没有的信息合并到远程版本中，不改变修改时间
+fn keep_local_only(remote: &mut Todo, local: &Todo) {
+    let modified_at = *remote.modified_at();
+    // iCalendar 中不包含注释
+    remote.set_annotations(local.annotations().to_vec());
+    if remote.reminders().is_none() {
+        remote.set_reminders(local.reminders().map(<[_]>::to_vec));
+    }
+    remote.set_modified_at(Some(modified_at));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<multistatus xmlns="DAV:">
+  <response><href>/dav/todos/</href><propstat><prop><getetag>"c"</getetag></prop></propstat></response>
+  <response>
+    <href>http://example.com/dav/todos/a.ics</href>
+    <propstat><prop><getetag>"1"</getetag></prop><status>HTTP/1.1 200 OK</status></propstat>
+  </response>
+  <response><href>/dav/todos/b.ics</href><status>HTTP/1.1 404 Not Found</status></response>
+  <sync-token>http://example.com/sync/7</sync-token>
+</multistatus>"#;
+        let changes = parse_multistatus(xml, "/dav/todos/").unwrap();
+        assert_eq!(
+            changes.items,
+            vec![
+                ("/dav/todos/a.ics".to_string(), Some("\"1\"".to_string())),
+                ("/dav/todos/b.ics".to_string(), None),
+            ]
+        );
+        assert_eq!(changes.sync_token.as_deref(), Some("http://example.com/sync/7"));
+    }
+}
@@ -0,0 +1,424 @@
+//! 按显示宽度对齐的表格
+//!
+//! 宽度按字素簇计算：汉字、全角符号和大部分 emoji 占两列，组合字符不占列。
+//! 表格超出可用宽度时先收窄最宽的列，再按列的设置换行或截断其中的文本。
+//! [`todo_table`] 按 `list --columns` 的列名构建待办事项的表格。
+
+use std::io::{self, IsTerminal};
+
+use chrono::{DateTime, Local};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::Config;
+use crate::filter::Filter;
+use crate::todo::Todo;
+
+/// 列之间的分隔
+const GAP: &str = "  ";
+
+/// 收窄时每列至少保留的宽度，表头更窄时以表头为准
+const MIN_WIDTH: usize = 4;
+
+/// 文本在终端中占的列数
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
+/// 截断到指定宽度，超出时以 `…` 结尾；换行按空格处理
+pub fn truncate(text: &str, width: usize) -> String {
+    let text = text.replace('\n', " ");
+    if display_width(&text) <= width {
+        return text;
+    }
+    let mut result = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let w = grapheme.width();
+        if used + w + 1 > width {
+            break;
+        }
+        result.push_str(grapheme);
+        used += w;
+    }
+    if width > 0 {
+        result.truncate(result.trim_end().len());
+        result.push('…');
+    }
+    result
+}
+
+/// 按宽度换行：优先在空白处断开，中文等没有空白的文本在任意字素之间断开
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut used = 0;
+        // 当前行中最后一个空白之后的位置
+        let mut space = None;
+        for grapheme in paragraph.graphemes(true) {
+            let w = grapheme.width();
+            let blank = grapheme.trim().is_empty();
+            if used + w > width && !line.is_empty() {
+                // 超出的是空白时直接在此断开，否则回到最后一个空白处
+                let rest = match space.take() {
+                    Some(at) if !blank => line.split_off(at),
+                    _ => String::new(),
+                };
+                lines.push(line.trim_end().to_string());
+                line = rest;
+                used = display_width(&line);
+                if line.is_empty() && blank {
+                    continue;
+                }
+            }
+            line.push_str(grapheme);
+            used += w;
+            if blank {
+                space = Some(line.len());
+            }
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// 终端的宽度：`COLUMNS` 环境变量优先；输出不是终端时返回 `None`，不限制宽度
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).filter(|&c| c > 0) {
+        return Some(columns);
+    }
+    let stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size_of(stdout).map(|(terminal_size::Width(width), _)| usize::from(width))
+}
+
+/// 超出列宽时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// 截断为一行
+    Truncate,
+    /// 换成多行
+    Wrap,
+}
+
+/// 表格的一列
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub header: String,
+    pub overflow: Overflow,
+    /// 是否右对齐，用于数字
+    pub right: bool,
+}
+
+impl Column {
+    /// 左对齐、超出时截断的列
+    pub fn new(header: &str) -> Self {
+        Column {
+            header: header.to_string(),
+            overflow: Overflow::Truncate,
+            right: false,
+        }
+    }
+
+    pub fn wrap(mut self) -> Self {
+        self.overflow = Overflow::Wrap;
+        self
+    }
+
+    pub fn right(mut self) -> Self {
+        self.right = true;
+        self
+    }
+
+    /// 收窄时至少保留的宽度
+    fn min_width(&self) -> usize {
+        display_width(&self.header).max(MIN_WIDTH)
+    }
+}
+
+/// 单元格的文本和颜色
+#[derive(Debug, Clone, Default)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<String>,
+}
+
+impl Cell {
+    pub fn colored(text: impl Into<String>, color: &str) -> Self {
+        Cell {
+            text: text.into(),
+            color: Some(color.to_string()),
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell { text, color: None }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::from(text.to_string())
+    }
+}
+
+/// 表格，每行的单元格与列一一对应
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Table { columns, rows: Vec::new() }
+    }
+
+    /// 添加一行，缺少的单元格按空白处理
+    pub fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// 渲染为文本，每行以换行结尾；`paint` 用指定的颜色显示单元格的文本
+    pub fn render(&self, max_width: Option<usize>, paint: impl Fn(&str, &str) -> String) -> String {
+        let widths = self.widths(max_width);
+        let mut output = String::new();
+
+        let headers: Vec<Vec<String>> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, &width)| vec![truncate(&column.header, width)])
+            .collect();
+        self.render_row(&mut output, &widths, &headers, &[], &paint);
+        let rule: Vec<String> = widths.iter().map(|&width| "─".repeat(width)).collect();
+        push_line(&mut output, &rule.join(GAP));
+
+        for row in &self.rows {
+            let lines: Vec<Vec<String>> = self
+                .columns
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (column, &width))| {
+                    let text = row.get(i).map(|cell| cell.text.as_str()).unwrap_or_default();
+                    match column.overflow {
+                        Overflow::Truncate => vec![truncate(text, width)],
+                        Overflow::Wrap => wrap(text, width),
+                    }
+                })
+                .collect();
+            self.render_row(&mut output, &widths, &lines, row, &paint);
+        }
+        output
+    }
+
+    /// 各列的宽度：取内容的宽度，总宽度超出时逐次收窄最宽的列
+    fn widths(&self, max_width: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let cells = self.rows.iter().filter_map(|row| row.get(i)).map(|cell| match column.overflow {
+                    Overflow::Truncate => display_width(&cell.text.replace('\n', " ")),
+                    Overflow::Wrap => cell.text.lines().map(display_width).max().unwrap_or_default(),
+                });
+                cells.fold(display_width(&column.header), usize::max)
+            })
+            .collect();
+        let Some(max_width) = max_width else {
+            return widths;
+        };
+
+        let gaps = GAP.len() * self.columns.len().saturating_sub(1);
+        let mut total: usize = widths.iter().sum::<usize>() + gaps;
+        while total > max_width {
+            let widest = widths
+                .iter()
+                .enumerate()
+                .filter(|&(i, &width)| width > self.columns[i].min_width())
+                .max_by_key(|&(i, &width)| (width, std::cmp::Reverse(i)));
+            let Some((i, _)) = widest else {
+                break;
+            };
+            widths[i] -= 1;
+            total -= 1;
+        }
+        widths
+    }
+
+    /// 输出一行单元格，换行后的单元格占多行，较短的单元格以空白补齐
+    fn render_row(
+        &self,
+        output: &mut String,
+        widths: &[usize],
+        lines: &[Vec<String>],
+        cells: &[Cell],
+        paint: &impl Fn(&str, &str) -> String,
+    ) {
+        let height = lines.iter().map(Vec::len).max().unwrap_or(1);
+        for n in 0..height {
+            let mut line = String::new();
+            for (i, (column, &width)) in self.columns.iter().zip(widths).enumerate() {
+                if i > 0 {
+                    line.push_str(GAP);
+                }
+                let text = lines[i].get(n).map(String::as_str).unwrap_or_default();
+                let padding = " ".repeat(width.saturating_sub(display_width(text)));
+                let text = match cells.get(i).and_then(|cell| cell.color.as_deref()) {
+                    Some(color) if !text.is_empty() => paint(color, text),
+                    _ => text.to_string(),
+                };
+                if column.right {
+                    line.push_str(&padding);
+                    line.push_str(&text);
+                } else {
+                    line.push_str(&text);
+                    line.push_str(&padding);
+                }
+            }
+            push_line(output, &line);
+        }
+    }
+}
+
+/// 去掉行尾的补齐空白后加入输出
+fn push_line(output: &mut String, line: &str) {
+    output.push_str(line.trim_end_matches(' '));
+    output.push('\n');
+}
+
+/// `list` 的表格：`columns` 是要显示的列名，优先级和已过期的截止日期使用配置的颜色
+pub fn todo_table(todos: &[(usize, &Todo)], columns: &[String], filter: &Filter, config: &Config, now: &DateTime<Local>) -> Table {
+    let colors = &config.colors;
+    let datetime_format = config.format.datetime.as_str();
+    let mut table = Table::new(
+        columns
+            .iter()
+            .map(|column| match column.as_str() {
+                "id" => Column::new("ID").right(),
+                "status" => Column::new("状态"),
+                "priority" => Column::new("优先级"),
+                "title" => Column::new("标题").wrap(),
+                "description" => Column::new("描述").wrap(),
+                "created" => Column::new("创建时间"),
+                "due" => Column::new("截止日期"),
+                "tags" => Column::new("标签").wrap(),
+                "fields" => Column::new("字段").wrap(),
+                "annotations" => Column::new("注释").right(),
+                "urgency" => Column::new("紧急度").right(),
+                "reminders" => Column::new("提醒"),
+                other => Column::new(other),
+            })
+            .collect(),
+    );
+    for &(i, todo) in todos {
+        let row = columns
+            .iter()
+            .map(|column| match column.as_str() {
+                "id" => Cell::from(i.to_string()),
+                "status" if todo.is_completed() => Cell::from("已完成"),
+                "priority" => Cell::colored(todo.priority().to_string(), colors.priority(todo.priority())),
+                "title" => Cell::from(todo.title()),
+                "description" => Cell::from(todo.description().map(String::as_str).unwrap_or_default()),
+                "created" => Cell::from(todo.created_at().format(datetime_format).to_string()),
+                "due" => match todo.due_date() {
+                    Some(due) if due < now && !todo.is_completed() => {
+                        Cell::colored(due.format(datetime_format).to_string(), &colors.overdue)
+                    }
+                    Some(due) => Cell::from(due.format(datetime_format).to_string()),
+                    None => Cell::default(),
+                },
+                "tags" => Cell::from(todo.tags().join(", ")),
+                "fields" => {
+                    let fields: Vec<String> = todo.fields().iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                    Cell::from(fields.join(", "))
+                }
+                "annotations" if !todo.annotations().is_empty() => Cell::from(todo.annotations().len().to_string()),
+                "urgency" if !todo.is_completed() => Cell::from(format!("{:.1}", filter.weights.score(todo, now).total())),
+                "reminders" => match todo.reminders() {
+                    Some([]) => Cell::from("不提醒"),
+                    Some(offsets) => {
+                        let offsets: Vec<String> = offsets.iter().map(|o| o.to_string()).collect();
+                        Cell::from(format!("提前 {}", offsets.join(", ")))
+                    }
+                    None => Cell::default(),
+                },
+                _ => Cell::default(),
+            })
+            .collect();
+        table.push(row);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(_color: &str, text: &str) -> String {
+        text.to_string()
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("todo"), 4);
+        assert_eq!(display_width("写周报"), 6);
+        assert_eq!(display_width("ｔｏｄｏ，好"), 12);
+        assert_eq!(display_width("👍"), 2);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+        assert_eq!(display_width("é"), 1);
+
+        assert_eq!(truncate("提交工作报告", 12), "提交工作报告");
+        assert_eq!(truncate("提交工作报告", 7), "提交工…");
+        assert_eq!(truncate("提交工作报告", 6), "提交…");
+        assert_eq!(truncate("👨‍👩‍👧 family", 4), "👨‍👩‍👧…");
+        assert_eq!(truncate("第一行\n第二行", 20), "第一行 第二行");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("提交工作报告", 5), ["提交", "工作", "报告"]);
+        assert_eq!(wrap("Review quarterly budget", 16), ["Review quarterly", "budget"]);
+        assert_eq!(wrap("给 Alice 发邮件", 8), ["给 Alice", "发邮件"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("第一行\n第二行", 20), ["第一行", "第二行"]);
+        assert_eq!(wrap("", 10), [""]);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut table = Table::new(vec![Column::new("ID").right(), Column::new("标题").wrap(), Column::new("标签")]);
+        table.push(vec!["0".into(), "写周报".into(), "工作".into()]);
+        table.push(vec!["12".into(), "Buy milk 🥛".into(), Cell::colored("生活, 购物", "red")]);
+        assert_eq!(
+            table.render(None, plain),
+            "ID  标题         标签\n\
+             ──  ───────────  ──────────\n\
+             \u{20}0  写周报       工作\n\
+             12  Buy milk 🥛  生活, 购物\n"
+        );
+
+        // 收窄最宽的列：标题换行，标签截断
+        assert_eq!(
+            table.render(Some(20), plain),
+            "ID  标题     标签\n\
+             ──  ───────  ───────\n\
+             \u{20}0  写周报   工作\n\
+             12  Buy      生活,…\n\
+             \u{20}   milk 🥛\n"
+        );
+
+        let painted = table.render(None, |color, text| format!("<{}>{}</>", color, text));
+        assert!(painted.contains("Buy milk 🥛  <red>生活, 购物</>\n"), "{}", painted);
+    }
+}
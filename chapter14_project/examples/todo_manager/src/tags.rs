@@ -0,0 +1,470 @@
+//! 层级标签
+//!
+//! 标签用 `/` 分隔层级，如 `工作/后端/接口`。按 `工作` 筛选时也会匹配它的所有下级标签，
+//! 重命名和合并时下级标签随之移动。标签的颜色和说明记录在数据文件旁边的 `.tags.json` 中。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::collate::Collator;
+use crate::error::AppError;
+use crate::hooks::{Event, Hooks, Outcome};
+use crate::storage::TodoStorage;
+use crate::todo::Todo;
+
+/// 层级之间的分隔符
+pub const SEPARATOR: char = '/';
+
+/// 支持的颜色名称，也可以使用 `#rrggbb`
+pub const COLORS: &[&str] = &["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+/// 去掉首尾的空白和多余的分隔符，如 ` 工作//后端/ ` 规范化为 `工作/后端`
+pub fn normalize(tag: &str) -> String {
+    tag.split(SEPARATOR)
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `tag` 是否为 `ancestor` 本身或它的下级标签
+pub fn is_within(tag: &str, ancestor: &str) -> bool {
+    match tag.strip_prefix(ancestor) {
+        Some(rest) => rest.is_empty() || rest.starts_with(SEPARATOR),
+        None => false,
+    }
+}
+
+/// 将 `tag` 中的 `from` 前缀替换为 `to`，不在 `from` 之下时返回 `None`
+pub fn replace_prefix(tag: &str, from: &str, to: &str) -> Option<String> {
+    is_within(tag, from).then(|| format!("{}{}", to, &tag[from.len()..]))
+}
+
+/// 是否有事项使用 `tag` 或它的下级标签
+pub fn in_use(todos: &[Todo], tag: &str) -> bool {
+    todos.iter().any(|todo| todo.tags().iter().any(|t| is_within(t, tag)))
+}
+
+/// 将 `from` 重命名为 `to` 时是否会与已有标签重复：`from` 及其下级标签之外，
+/// 已有事项使用 `to` 或它的下级标签，此时应改用合并
+pub fn rename_conflicts(todos: &[Todo], from: &str, to: &str) -> bool {
+    todos
+        .iter()
+        .flat_map(Todo::tags)
+        .any(|tag| is_within(tag, to) && !is_within(tag, from))
+}
+
+/// 标签的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TagCount {
+    /// 直接带有该标签的事项数
+    pub total: usize,
+    /// 其中未完成的事项数
+    pub pending: usize,
+}
+
+impl TagCount {
+    fn add(&mut self, todo: &Todo) {
+        self.total += 1;
+        if !todo.is_completed() {
+            self.pending += 1;
+        }
+    }
+}
+
+/// 统计每个标签直接关联的事项数，按标签名排序
+pub fn count(todos: &[Todo]) -> BTreeMap<String, TagCount> {
+    let mut counts: BTreeMap<String, TagCount> = BTreeMap::new();
+    for todo in todos {
+        for tag in todo.tags() {
+            counts.entry(tag.clone()).or_default().add(todo);
+        }
+    }
+    counts
+}
+
+/// 标签树中的一个节点
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagNode {
+    /// 从根开始的完整标签名
+    pub path: String,
+    /// 直接带有该标签的事项
+    pub own: TagCount,
+    /// 带有该标签或其任意下级标签的事项，同一事项只计一次
+    pub total: TagCount,
+    pub children: BTreeMap<String, TagNode>,
+}
+
+/// 构建标签树，返回顶层节点；只作为上级出现、没有直接使用的标签也会出现在树中
+pub fn tree(todos: &[Todo]) -> BTreeMap<String, TagNode> {
+    let mut roots: BTreeMap<String, TagNode> = BTreeMap::new();
+    for todo in todos {
+        // 事项的所有标签及其上级，用于统计包含下级的总数
+        let mut paths: Vec<String> = Vec::new();
+        for tag in todo.tags() {
+            let mut path = String::new();
+            for part in tag.split(SEPARATOR) {
+                if !path.is_empty() {
+                    path.push(SEPARATOR);
+                }
+                path.push_str(part);
+                if !paths.contains(&path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+        for path in &paths {
+            let node = node_mut(&mut roots, path);
+            node.total.add(todo);
+            if todo.tags().contains(path) {
+                node.own.add(todo);
+            }
+        }
+    }
+    roots
+}
+
+fn node_mut<'a>(roots: &'a mut BTreeMap<String, TagNode>, path: &str) -> &'a mut TagNode {
+    let mut parts = path.split(SEPARATOR);
+    let first = parts.next().unwrap_or_default();
+    let mut node = roots.entry(first.to_string()).or_insert_with(|| TagNode {
+        path: first.to_string(),
+        ..Default::default()
+    });
+    for part in parts {
+        let child_path = format!("{}{}{}", node.path, SEPARATOR, part);
+        node = node.children.entry(part.to_string()).or_insert_with(|| TagNode {
+            path: child_path,
+            ..Default::default()
+        });
+    }
+    node
+}
+
+/// 将标签树渲染为多行文本，数量包含下级标签；标签名由 `paint` 决定如何显示
+pub fn render_tree(
+    nodes: &BTreeMap<String, TagNode>,
+    registry: &TagRegistry,
+    collator: &Collator,
+    paint: &impl Fn(&str, &TagMeta) -> String,
+) -> String {
+    let mut out = String::new();
+    render_nodes(&mut out, nodes, registry, collator, paint, "");
+    out
+}
+
+fn render_nodes(
+    out: &mut String,
+    nodes: &BTreeMap<String, TagNode>,
+    registry: &TagRegistry,
+    collator: &Collator,
+    paint: &impl Fn(&str, &TagMeta) -> String,
+    prefix: &str,
+) {
+    let mut nodes: Vec<_> = nodes.iter().collect();
+    nodes.sort_by(|(a, _), (b, _)| collator.compare(a, b));
+    let last = nodes.len().saturating_sub(1);
+    for (i, (name, node)) in nodes.into_iter().enumerate() {
+        let (branch, indent) = match (prefix.is_empty(), i == last) {
+            (true, _) => ("", ""),
+            (false, true) => ("└── ", "    "),
+            (false, false) => ("├── ", "│   "),
+        };
+        let meta = registry.get(&node.path).cloned().unwrap_or_default();
+        out.push_str(&format!("{}{}{} ({}，未完成 {})", prefix, branch, paint(name, &meta), node.total.total, node.total.pending));
+        if let Some(description) = &meta.description {
+            out.push_str(&format!(" — {}", description));
+        }
+        out.push('\n');
+        // 顶层节点的子节点从空前缀之后开始画线
+        let child_prefix = if prefix.is_empty() { " ".to_string() } else { format!("{}{}", prefix, indent) };
+        render_nodes(out, &node.children, registry, collator, paint, &child_prefix);
+    }
+}
+
+/// 重命名或合并标签时的错误
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RetagError {
+    #[error("标签不能为空")]
+    Empty,
+    #[error("没有待办事项使用标签: {0}")]
+    NotInUse(String),
+    #[error("标签 {0} 已被使用，请使用 tag merge 合并")]
+    Conflict(String),
+}
+
+/// 将标签 `from` 重命名为 `to`，返回修改的事项数；`to` 已被其他标签使用时出错
+pub fn rename(todos: &mut [Todo], from: &str, to: &str) -> Result<usize, RetagError> {
+    check_retag(todos, from, to)?;
+    if rename_conflicts(todos, from, to) {
+        return Err(RetagError::Conflict(to.to_string()));
+    }
+    Ok(retag(todos, from, to))
+}
+
+/// 将标签 `from` 合并到 `to`，返回修改的事项数
+pub fn merge(todos: &mut [Todo], from: &str, to: &str) -> Result<usize, RetagError> {
+    check_retag(todos, from, to)?;
+    Ok(retag(todos, from, to))
+}
+
+fn check_retag(todos: &[Todo], from: &str, to: &str) -> Result<(), RetagError> {
+    if from.is_empty() || to.is_empty() {
+        return Err(RetagError::Empty);
+    }
+    if !in_use(todos, from) {
+        return Err(RetagError::NotInUse(from.to_string()));
+    }
+    Ok(())
+}
+
+/// 修改标签的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetagMode {
+    /// 见 [`rename`]
+    Rename,
+    /// 见 [`merge`]
+    Merge,
+}
+
+/// 在存储中重命名或合并标签，返回修改的事项数和钩子的提示
+///
+/// 每个修改过的事项都运行 on-modify，任意一个钩子拒绝时不修改存储；
+/// 标签信息随标签在 `registry` 中移动。数据文件和标签信息由调用方保存。
+pub fn retag_storage(
+    storage: &mut TodoStorage,
+    registry: &mut TagRegistry,
+    hooks: &Hooks,
+    from: &str,
+    to: &str,
+    mode: RetagMode,
+) -> Result<Outcome<usize>, AppError> {
+    let mut todos = storage.todos().to_vec();
+    let changed = match mode {
+        RetagMode::Rename => rename(&mut todos, from, to)?,
+        RetagMode::Merge => merge(&mut todos, from, to)?,
+    };
+    let mut messages = Vec::new();
+    let mut modified = Vec::new();
+    for (index, (before, todo)) in storage.todos().iter().zip(todos).enumerate() {
+        if todo != *before {
+            let outcome = hooks.on_modify(Event::Modify, before, todo)?;
+            messages.extend(outcome.messages);
+            modified.push((index, outcome.value));
+        }
+    }
+    for (index, todo) in modified {
+        storage.update(index, todo)?;
+    }
+    registry.retag(from, to);
+    Ok(Outcome { value: changed, messages })
+}
+
+/// 将所有事项中 `from` 及其下级标签移动到 `to` 之下，返回修改的事项数
+///
+/// 移动后与已有标签重复的会被去重，因此同样适用于合并。
+pub fn retag(todos: &mut [Todo], from: &str, to: &str) -> usize {
+    let mut changed = 0;
+    for todo in todos {
+        if !todo.tags().iter().any(|tag| is_within(tag, from)) {
+            continue;
+        }
+        let tags = todo
+            .tags()
+            .iter()
+            .map(|tag| replace_prefix(tag, from, to).unwrap_or_else(|| tag.clone()))
+            .collect();
+        todo.set_tags(tags);
+        changed += 1;
+    }
+    changed
+}
+
+/// 标签的附加信息
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagMeta {
+    /// 颜色名称或 `#rrggbb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl TagMeta {
+    fn is_empty(&self) -> bool {
+        self.color.is_none() && self.description.is_none()
+    }
+
+    /// 用终端的颜色显示标签名，没有设置颜色时原样返回
+    pub fn paint(&self, text: &str) -> String {
+        match &self.color {
+            Some(color) => paint(color, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// 用终端的颜色显示文本，颜色无效时原样返回
+pub fn paint(color: &str, text: &str) -> String {
+    match ansi_color(color) {
+        Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+        None => text.to_string(),
+    }
+}
+
+/// 检查颜色是否为支持的名称或 `#rrggbb`
+pub fn validate_color(color: &str) -> Result<(), String> {
+    if ansi_color(color).is_some() {
+        Ok(())
+    } else {
+        Err(format!("无效的颜色: {}（可用颜色: {} 或 #rrggbb）", color, COLORS.join(", ")))
+    }
+}
+
+fn ansi_color(color: &str) -> Option<String> {
+    if let Some(i) = COLORS.iter().position(|c| *c == color) {
+        return Some((30 + i).to_string());
+    }
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(format!("38;2;{};{};{}", channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// 所有标签的附加信息：标签名 -> 信息
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TagRegistry {
+    tags: BTreeMap<String, TagMeta>,
+}
+
+impl TagRegistry {
+    /// 记录文件的路径，如 `~/.todo.json.tags.json`
+    pub fn path_for(data_file: &Path) -> PathBuf {
+        let mut name = data_file.as_os_str().to_os_string();
+        name.push(".tags.json");
+        PathBuf::from(name)
+    }
+
+    /// 读取记录，文件不存在时返回空记录
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(TagRegistry::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, tag: &str) -> Option<&TagMeta> {
+        self.tags.get(tag)
+    }
+
+    /// 设置标签的信息，颜色和说明都为空时删除该记录
+    pub fn set(&mut self, tag: &str, meta: TagMeta) {
+        if meta.is_empty() {
+            self.tags.remove(tag);
+        } else {
+            self.tags.insert(tag.to_string(), meta);
+        }
+    }
+
+    /// 随标签一起移动 `from` 及其下级标签的信息，目标已有信息时保留目标的
+    pub fn retag(&mut self, from: &str, to: &str) {
+        let moved: Vec<String> = self.tags.keys().filter(|tag| is_within(tag, from)).cloned().collect();
+        for tag in moved {
+            let meta = self.tags.remove(&tag).unwrap_or_default();
+            let target = replace_prefix(&tag, from, to).unwrap_or(tag);
+            self.tags.entry(target).or_insert(meta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(tags: &[&str]) -> Todo {
+        Todo::with_tags("任务".to_string(), None, tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("工作/后端/接口", "工作"));
+        assert!(is_within("工作", "工作"));
+        assert!(!is_within("工作室", "工作"));
+        assert_eq!(normalize(" 工作//后端/ "), "工作/后端");
+        assert_eq!(replace_prefix("工作/后端", "工作", "公司").as_deref(), Some("公司/后端"));
+    }
+
+    #[test]
+    fn test_rename_conflicts() {
+        let todos = vec![todo(&["工作/后端/接口"]), todo(&["家庭"])];
+        assert!(in_use(&todos, "工作"));
+        assert!(!in_use(&todos, "工作/前端"));
+        // 移到自己的上级或下级
+        assert!(!rename_conflicts(&todos, "工作/后端", "工作"));
+        assert!(!rename_conflicts(&todos, "工作", "工作/旧"));
+        assert!(!rename_conflicts(&todos, "工作/后端", "后端"));
+        assert!(rename_conflicts(&todos, "工作/后端", "家庭"));
+        assert!(rename_conflicts(&todos, "家庭", "工作"));
+
+        let mut todos = todos;
+        assert_eq!(rename(&mut todos, "", "工作"), Err(RetagError::Empty));
+        assert_eq!(merge(&mut todos, "工作/前端", "工作"), Err(RetagError::NotInUse("工作/前端".to_string())));
+        assert_eq!(rename(&mut todos, "家庭", "工作"), Err(RetagError::Conflict("工作".to_string())));
+        assert_eq!(merge(&mut todos, "家庭", "工作"), Ok(1));
+        assert_eq!(todos[1].tags(), ["工作"]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut done = todo(&["工作/后端", "工作/前端"]);
+        done.mark_as_done();
+        let todos = vec![todo(&["工作/后端/接口"]), todo(&["工作"]), done];
+        let roots = tree(&todos);
+        let work = &roots["工作"];
+        assert_eq!(work.own.total, 1);
+        assert_eq!(work.total, TagCount { total: 3, pending: 2 });
+        let backend = &work.children["后端"];
+        assert_eq!(backend.path, "工作/后端");
+        assert_eq!(backend.total.total, 2);
+        assert_eq!(backend.children["接口"].own.total, 1);
+
+        let collator = crate::collate::Collation::default().collator();
+        let rendered = render_tree(&roots, &TagRegistry::default(), &collator, &|tag, _| tag.to_string());
+        assert_eq!(
+            rendered,
+            "工作 (3，未完成 2)\n ├── 前端 (1，未完成 0)\n └── 后端 (2，未完成 1)\n     └── 接口 (1，未完成 1)\n"
+        );
+    }
+
+    #[test]
+    fn test_retag() {
+        let mut todos = vec![todo(&["工作/后端", "后端"]), todo(&["家庭"])];
+        assert_eq!(retag(&mut todos, "工作/后端", "后端"), 1);
+        assert_eq!(todos[0].tags(), ["后端"]);
+        assert_eq!(todos[1].tags(), ["家庭"]);
+
+        let mut registry = TagRegistry::default();
+        let meta = |color: &str| TagMeta {
+            color: Some(color.to_string()),
+            description: None,
+        };
+        registry.set("工作/后端", meta("red"));
+        registry.set("后端", meta("blue"));
+        registry.set("工作/后端/接口", meta("green"));
+        registry.retag("工作/后端", "后端");
+        assert_eq!(registry.get("后端"), Some(&meta("blue")));
+        assert_eq!(registry.get("后端/接口"), Some(&meta("green")));
+        assert!(registry.get("工作/后端").is_none());
+        assert!(validate_color("#ff8800").is_ok());
+        assert!(validate_color("orange").is_err());
+    }
+}
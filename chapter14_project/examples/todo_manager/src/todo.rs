@@ -1,20 +1,18 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// 待办事项的优先级
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum Priority {
     Low,
+    #[default]
     Medium,
     High,
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Priority::Medium
-    }
-}
-
 impl std::fmt::Display for Priority {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -25,37 +23,165 @@ impl std::fmt::Display for Priority {
     }
 }
 
+impl std::str::FromStr for Priority {
+    type Err = String;
+
+    /// 解析命令行中使用的优先级名称 (low, medium, high)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            _ => Err(format!("无效的优先级: {}", s)),
+        }
+    }
+}
+
+/// 提醒时间相对截止日期提前的时长，写作数字加单位：`30m`、`2h`、`1d`、`1w`，`0m` 表示到期时提醒
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ReminderOffset {
+    minutes: i64,
+}
+
+impl ReminderOffset {
+    pub fn from_minutes(minutes: i64) -> Self {
+        ReminderOffset { minutes }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.minutes)
+    }
+
+    /// 解析用逗号分隔的多个时长
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').map(|part| part.trim().parse()).collect()
+    }
+
+    /// 解析提醒的设置：逗号分隔的多个时长，`none` 表示不提醒
+    pub fn parse_setting(s: &str) -> Result<Vec<Self>, String> {
+        if s == "none" {
+            return Ok(Vec::new());
+        }
+        Self::parse_list(s)
+    }
+}
+
+impl std::fmt::Display for ReminderOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 使用能整除的最大单位
+        let (value, unit) = [(7 * 24 * 60, "w"), (24 * 60, "d"), (60, "h")]
+            .into_iter()
+            .find(|(size, _)| self.minutes != 0 && self.minutes % size == 0)
+            .map_or((self.minutes, "m"), |(size, unit)| (self.minutes / size, unit));
+        write!(f, "{}{}", value, unit)
+    }
+}
+
+impl std::str::FromStr for ReminderOffset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("无效的提醒时间: {}，应为数字加单位，如 30m、2h、1d、1w", s);
+        let unit_at = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let value: i64 = s[..unit_at].parse().map_err(|_| invalid())?;
+        let size = match &s[unit_at..] {
+            "m" => 1,
+            "h" => 60,
+            "d" => 24 * 60,
+            "w" => 7 * 24 * 60,
+            _ => return Err(invalid()),
+        };
+        value.checked_mul(size).map(ReminderOffset::from_minutes).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for ReminderOffset {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ReminderOffset> for String {
+    fn from(offset: ReminderOffset) -> Self {
+        offset.to_string()
+    }
+}
+
+/// 追加在待办事项上的带时间的注释
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// 添加注释的时间
+    pub entry: DateTime<Local>,
+    pub text: String,
+}
+
 /// 表示一个待办事项
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// 没有指定时间的操作（[`Todo::new`]、[`Todo::mark_as_done`] 和各个设置方法记录的修改时间）使用系统时间；
+/// 需要可重现的时间时使用带 `_at` 的方法，或者通过 [`TodoStorage::update`](crate::storage::TodoStorage::update)
+/// 写回存储，由存储的时钟记录修改时间。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Todo {
+    /// 待办事项的稳定标识符，旧版本的数据文件中可能没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uid: Option<String>,
     /// 待办事项的标题
     title: String,
     /// 待办事项的详细描述
     description: Option<String>,
     /// 待办事项是否已完成
     completed: bool,
+    /// 待办事项的完成时间
+    #[serde(default)]
+    completed_at: Option<DateTime<Local>>,
     /// 待办事项的创建时间
     created_at: DateTime<Local>,
+    /// 待办事项的最后修改时间，没有修改过的事项与创建时间相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified_at: Option<DateTime<Local>>,
     /// 待办事项的优先级
     priority: Priority,
     /// 待办事项的截止日期
     due_date: Option<DateTime<Local>>,
     /// 待办事项的标签
     tags: Vec<String>,
+    /// 单独设置的提醒时间，`None` 表示使用 `remind` 命令的默认设置，空列表表示不提醒
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reminders: Option<Vec<ReminderOffset>>,
+    /// 自定义字段的值，类型由配置文件中的声明决定
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    fields: BTreeMap<String, String>,
+    /// 注释，按添加时间排序
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
 }
 
 
 impl Todo {
     /// 创建一个新的待办事项
     pub fn new(title: String, description: Option<String>) -> Self {
+        Self::new_at(title, description, Local::now())
+    }
+
+    /// 创建一个在指定时间创建的待办事项
+    pub fn new_at(title: String, description: Option<String>, created_at: DateTime<Local>) -> Self {
         Todo {
+            uid: Some(Uuid::new_v4().to_string()),
             title,
             description,
             completed: false,
-            created_at: Local::now(),
+            completed_at: None,
+            created_at,
+            modified_at: None,
             priority: Priority::default(),
             due_date: None,
             tags: Vec::new(),
+            reminders: None,
+            fields: BTreeMap::new(),
+            annotations: Vec::new(),
         }
     }
     
@@ -80,6 +206,21 @@ impl Todo {
         todo
     }
 
+    /// 获取待办事项的稳定标识符
+    pub fn uid(&self) -> Option<&str> {
+        self.uid.as_deref()
+    }
+
+    /// 设置待办事项的稳定标识符（用于从其他格式导入）
+    pub fn set_uid(&mut self, uid: String) {
+        self.uid = Some(uid);
+    }
+
+    /// 为没有标识符的待办事项生成一个新的标识符
+    pub fn ensure_uid(&mut self) -> &str {
+        self.uid.get_or_insert_with(|| Uuid::new_v4().to_string())
+    }
+
     /// 获取待办事项的标题
     pub fn title(&self) -> &str {
         &self.title
@@ -87,7 +228,10 @@ impl Todo {
     
     /// 设置待办事项的标题
     pub fn set_title(&mut self, title: String) {
-        self.title = title;
+        if self.title != title {
+            self.title = title;
+            self.touch();
+        }
     }
 
     /// 获取待办事项的描述
@@ -97,7 +241,10 @@ impl Todo {
     
     /// 设置待办事项的描述
     pub fn set_description(&mut self, description: Option<String>) {
-        self.description = description;
+        if self.description != description {
+            self.description = description;
+            self.touch();
+        }
     }
 
     /// 检查待办事项是否已完成
@@ -105,10 +252,45 @@ impl Todo {
         self.completed
     }
 
+    /// 获取待办事项的完成时间
+    pub fn completed_at(&self) -> Option<&DateTime<Local>> {
+        self.completed_at.as_ref()
+    }
+
     /// 获取待办事项的创建时间
     pub fn created_at(&self) -> &DateTime<Local> {
         &self.created_at
     }
+
+    /// 设置待办事项的创建时间（用于从其他格式导入）
+    pub fn set_created_at(&mut self, created_at: DateTime<Local>) {
+        self.created_at = created_at;
+    }
+
+    /// 获取待办事项的最后修改时间
+    pub fn modified_at(&self) -> &DateTime<Local> {
+        self.modified_at.as_ref().unwrap_or(&self.created_at)
+    }
+
+    /// 设置待办事项的最后修改时间（用于导入和同步），`None` 表示与创建时间相同
+    pub fn set_modified_at(&mut self, modified_at: Option<DateTime<Local>>) {
+        self.modified_at = modified_at;
+    }
+
+    /// 记录一次修改
+    fn touch(&mut self) {
+        self.modified_at = Some(Local::now());
+    }
+
+    /// 待办事项内容的指纹，内容相同时指纹相同
+    ///
+    /// 使用 FNV-1a 散列，结果不依赖于进程或编译器版本，可以保存下来用于比较。
+    pub fn fingerprint(&self) -> u64 {
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        bytes.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+        })
+    }
     
     /// 获取待办事项的优先级
     pub fn priority(&self) -> Priority {
@@ -117,7 +299,10 @@ impl Todo {
     
     /// 设置待办事项的优先级
     pub fn set_priority(&mut self, priority: Priority) {
-        self.priority = priority;
+        if self.priority != priority {
+            self.priority = priority;
+            self.touch();
+        }
     }
     
     /// 获取待办事项的截止日期
@@ -127,7 +312,10 @@ impl Todo {
     
     /// 设置待办事项的截止日期
     pub fn set_due_date(&mut self, due_date: Option<DateTime<Local>>) {
-        self.due_date = due_date;
+        if self.due_date != due_date {
+            self.due_date = due_date;
+            self.touch();
+        }
     }
     
     /// 获取待办事项的标签
@@ -139,22 +327,138 @@ impl Todo {
     pub fn add_tag(&mut self, tag: String) {
         if !self.tags.contains(&tag) {
             self.tags.push(tag);
+            self.touch();
         }
     }
     
     /// 从待办事项中移除标签
     pub fn remove_tag(&mut self, tag: &str) {
-        self.tags.retain(|t| t != tag);
+        if self.tags.iter().any(|t| t == tag) {
+            self.tags.retain(|t| t != tag);
+            self.touch();
+        }
+    }
+
+    /// 替换待办事项的全部标签
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        let mut unique: Vec<String> = Vec::new();
+        for tag in tags {
+            if !unique.contains(&tag) {
+                unique.push(tag);
+            }
+        }
+        if self.tags != unique {
+            self.tags = unique;
+            self.touch();
+        }
+    }
+
+    /// 获取单独设置的提醒时间
+    pub fn reminders(&self) -> Option<&[ReminderOffset]> {
+        self.reminders.as_deref()
+    }
+
+    /// 设置单独的提醒时间，`None` 表示恢复使用默认设置
+    pub fn set_reminders(&mut self, reminders: Option<Vec<ReminderOffset>>) {
+        if self.reminders != reminders {
+            self.reminders = reminders;
+            self.touch();
+        }
+    }
+
+    /// 获取自定义字段的值
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+
+    /// 获取全部自定义字段
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    /// 设置自定义字段的值，`None` 表示清除
+    pub fn set_field(&mut self, name: &str, value: Option<String>) {
+        let changed = match value {
+            Some(value) => self.fields.insert(name.to_string(), value.clone()) != Some(value),
+            None => self.fields.remove(name).is_some(),
+        };
+        if changed {
+            self.touch();
+        }
+    }
+
+    /// 获取按时间排序的注释
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// 在当前时间添加一条注释
+    pub fn add_annotation(&mut self, text: String) {
+        self.add_annotation_at(Local::now(), text);
+    }
+
+    /// 添加一条指定时间的注释，已存在相同的注释时忽略
+    pub fn add_annotation_at(&mut self, entry: DateTime<Local>, text: String) {
+        let annotation = Annotation { entry, text };
+        if self.annotations.contains(&annotation) {
+            return;
+        }
+        // 插入到相同时间的注释之后，保持添加顺序
+        let index = self.annotations.partition_point(|a| a.entry <= entry);
+        self.annotations.insert(index, annotation);
+        self.touch();
+    }
+
+    /// 修改第 `index` 条注释（从 0 开始）的内容，保留原来的时间，编号无效时返回 `false`
+    pub fn edit_annotation(&mut self, index: usize, text: String) -> bool {
+        let Some(annotation) = self.annotations.get_mut(index) else {
+            return false;
+        };
+        if annotation.text != text {
+            annotation.text = text;
+            self.touch();
+        }
+        true
+    }
+
+    /// 删除第 `index` 条注释（从 0 开始），编号无效时返回 `None`
+    pub fn remove_annotation(&mut self, index: usize) -> Option<Annotation> {
+        if index >= self.annotations.len() {
+            return None;
+        }
+        let annotation = self.annotations.remove(index);
+        self.touch();
+        Some(annotation)
+    }
+
+    /// 替换全部注释，用于合并
+    pub fn set_annotations(&mut self, mut annotations: Vec<Annotation>) {
+        annotations.sort_by_key(|a| a.entry);
+        if self.annotations != annotations {
+            self.annotations = annotations;
+            self.touch();
+        }
     }
 
     /// 将待办事项标记为已完成
     pub fn mark_as_done(&mut self) {
+        self.mark_as_done_at(Local::now());
+    }
+
+    /// 将待办事项标记为在指定时间完成
+    pub fn mark_as_done_at(&mut self, completed_at: DateTime<Local>) {
         self.completed = true;
+        self.completed_at = Some(completed_at);
+        self.touch();
     }
 
     /// 将待办事项标记为未完成
     pub fn mark_as_undone(&mut self) {
-        self.completed = false;
+        if self.completed {
+            self.completed = false;
+            self.completed_at = None;
+            self.touch();
+        }
     }
 }
 
@@ -167,22 +471,25 @@ mod tests {
         let todo = Todo::new("测试待办事项".to_string(), Some("这是一个测试".to_string()));
         assert_eq!(todo.title(), "测试待办事项");
         assert_eq!(todo.description(), Some(&"这是一个测试".to_string()));
-        assert_eq!(todo.is_completed(), false);
+        assert!(!todo.is_completed());
         assert_eq!(todo.priority(), Priority::Medium);
         assert!(todo.due_date().is_none());
         assert!(todo.tags().is_empty());
+        assert!(todo.uid().is_some());
     }
 
     #[test]
     fn test_mark_as_done() {
         let mut todo = Todo::new("测试待办事项".to_string(), None);
-        assert_eq!(todo.is_completed(), false);
+        assert!(!todo.is_completed());
         
         todo.mark_as_done();
-        assert_eq!(todo.is_completed(), true);
+        assert!(todo.is_completed());
+        assert!(todo.completed_at().is_some());
         
         todo.mark_as_undone();
-        assert_eq!(todo.is_completed(), false);
+        assert!(!todo.is_completed());
+        assert!(todo.completed_at().is_none());
     }
     
     #[test]
@@ -193,6 +500,23 @@ mod tests {
         todo.set_priority(Priority::High);
         assert_eq!(todo.priority(), Priority::High);
     }
+
+    #[test]
+    fn test_modified_at() {
+        let mut todo = Todo::new("测试待办事项".to_string(), None);
+        assert_eq!(todo.modified_at(), todo.created_at());
+        let fingerprint = todo.fingerprint();
+
+        // 设置为相同的值不算修改
+        todo.set_priority(Priority::Medium);
+        todo.remove_tag("工作");
+        assert_eq!(todo.modified_at(), todo.created_at());
+        assert_eq!(todo.fingerprint(), fingerprint);
+
+        todo.set_title("新标题".to_string());
+        assert!(todo.modified_at() > todo.created_at());
+        assert_ne!(todo.fingerprint(), fingerprint);
+    }
     
     #[test]
     fn test_tags() {
@@ -208,4 +532,35 @@ mod tests {
         assert_eq!(todo.tags().len(), 1);
         assert!(!todo.tags().contains(&"工作".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_annotations() {
+        let mut todo = Todo::new("测试待办事项".to_string(), None);
+        let now = Local::now();
+        todo.add_annotation_at(now, "第二条".to_string());
+        todo.add_annotation_at(now - chrono::Duration::hours(1), "第一条".to_string());
+        todo.add_annotation_at(now, "第二条".to_string());
+        let texts: Vec<&str> = todo.annotations().iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(texts, ["第一条", "第二条"]);
+
+        assert!(todo.edit_annotation(1, "改过的第二条".to_string()));
+        assert_eq!(todo.annotations()[1].entry, now);
+        assert!(!todo.edit_annotation(2, "不存在".to_string()));
+        assert_eq!(todo.remove_annotation(0).unwrap().text, "第一条");
+        assert!(todo.remove_annotation(1).is_none());
+        assert_eq!(todo.annotations().len(), 1);
+    }
+
+    #[test]
+    fn test_reminder_offset() {
+        assert_eq!("90m".parse::<ReminderOffset>().unwrap().to_string(), "90m");
+        assert_eq!("120m".parse::<ReminderOffset>().unwrap().to_string(), "2h");
+        assert_eq!("7d".parse::<ReminderOffset>().unwrap().to_string(), "1w");
+        assert_eq!("0m".parse::<ReminderOffset>().unwrap().to_string(), "0m");
+        assert!("1x".parse::<ReminderOffset>().is_err());
+        assert!("h".parse::<ReminderOffset>().is_err());
+        assert_eq!(ReminderOffset::parse_list("1d, 2h").unwrap().len(), 2);
+        assert!(ReminderOffset::parse_setting("none").unwrap().is_empty());
+        assert!(ReminderOffset::parse_setting("never").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! 紧急度：综合优先级、截止日期、存在时间和标签计算的分数，分数越高越应该先做
+//!
+//! 每一项先换算为 0 到 1 之间的系数，再乘以对应的权重后相加，
+//! 权重可以通过 `--weight 名称=数值` 调整。
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+
+use crate::todo::{Priority, Todo};
+
+/// 存在时间达到这个天数后，存在时间系数不再增长
+const AGE_MAX_DAYS: f64 = 365.0;
+
+/// 各项的权重
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrgencyWeights {
+    pub priority_high: f64,
+    pub priority_medium: f64,
+    pub priority_low: f64,
+    /// 截止日期的权重，已过期一周以上时取满值
+    pub due: f64,
+    /// 存在时间的权重，创建满一年时取满值
+    pub age: f64,
+    /// 带有指定标签时加上的分数
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl Default for UrgencyWeights {
+    fn default() -> Self {
+        UrgencyWeights {
+            priority_high: 6.0,
+            priority_medium: 3.9,
+            priority_low: 1.8,
+            due: 12.0,
+            age: 2.0,
+            tags: BTreeMap::from([("next".to_string(), 15.0)]),
+        }
+    }
+}
+
+impl UrgencyWeights {
+    /// 设置一项权重，名称为 `priority.high`、`priority.medium`、`priority.low`、`due`、`age` 或 `tag.<标签>`
+    pub fn set(&mut self, name: &str, value: f64) -> Result<(), String> {
+        match name {
+            "priority.high" => self.priority_high = value,
+            "priority.medium" => self.priority_medium = value,
+            "priority.low" => self.priority_low = value,
+            "due" => self.due = value,
+            "age" => self.age = value,
+            _ => match name.strip_prefix("tag.") {
+                Some(tag) if !tag.is_empty() => {
+                    self.tags.insert(tag.to_string(), value);
+                }
+                _ => return Err(format!("未知的紧急度权重: {}", name)),
+            },
+        }
+        Ok(())
+    }
+
+    /// 解析并设置 `名称=数值` 形式的权重
+    pub fn set_pair(&mut self, pair: &str) -> Result<(), String> {
+        let (name, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("无效的紧急度权重: {}，应为 名称=数值", pair))?;
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的权重数值: {}", value))?;
+        self.set(name.trim(), value)
+    }
+
+    /// 计算待办事项的紧急度
+    pub fn score(&self, todo: &Todo, now: &DateTime<Local>) -> Urgency {
+        let priority = match todo.priority() {
+            Priority::High => self.priority_high,
+            Priority::Medium => self.priority_medium,
+            Priority::Low => self.priority_low,
+        };
+        let due = todo.due_date().map_or(0.0, |due| self.due * due_factor(due, now));
+        let age_days = (*now - *todo.created_at()).num_seconds() as f64 / 86400.0;
+        let age = self.age * (age_days / AGE_MAX_DAYS).clamp(0.0, 1.0);
+        let tags = todo.tags().iter().filter_map(|tag| self.tags.get(tag)).fold(0.0, |sum, w| sum + w);
+        Urgency { priority, due, age, tags }
+    }
+}
+
+/// 截止日期系数：14 天以后为 0.2，之后线性增长，过期 7 天时达到 1.0
+fn due_factor(due: &DateTime<Local>, now: &DateTime<Local>) -> f64 {
+    let days_overdue = (*now - *due).num_seconds() as f64 / 86400.0;
+    if days_overdue >= 7.0 {
+        1.0
+    } else if days_overdue >= -14.0 {
+        (days_overdue + 14.0) * 0.8 / 21.0 + 0.2
+    } else {
+        0.2
+    }
+}
+
+/// 紧急度及其组成
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Urgency {
+    pub priority: f64,
+    pub due: f64,
+    pub age: f64,
+    pub tags: f64,
+}
+
+impl Urgency {
+    pub fn total(&self) -> f64 {
+        self.priority + self.due + self.age + self.tags
+    }
+}
+
+impl std::fmt::Display for Urgency {
+    /// 显示总分及各项的组成，如 `12.3 = 优先级 6.0 + 截止日期 5.3 + 存在时间 1.0 + 标签 0.0`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} = 优先级 {:.1} + 截止日期 {:.1} + 存在时间 {:.1} + 标签 {:.1}",
+            self.total(),
+            self.priority,
+            self.due,
+            self.age,
+            self.tags
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let now = Local::now();
+        let weights = UrgencyWeights::default();
+
+        let mut todo = Todo::new("写周报".to_string(), None);
+        todo.set_priority(Priority::High);
+        let plain = weights.score(&todo, &now);
+        assert_eq!(plain.priority, 6.0);
+        assert_eq!(plain.due, 0.0);
+
+        // 截止日期越近越紧急，过期一周后不再增长
+        todo.set_due_date(Some(now + chrono::Duration::days(30)));
+        assert!((weights.score(&todo, &now).due - 12.0 * 0.2).abs() < 1e-9);
+        todo.set_due_date(Some(now + chrono::Duration::days(1)));
+        let soon = weights.score(&todo, &now).total();
+        assert!(soon > plain.total());
+        todo.set_due_date(Some(now - chrono::Duration::days(10)));
+        assert_eq!(weights.score(&todo, &now).due, 12.0);
+
+        todo.add_tag("next".to_string());
+        assert_eq!(weights.score(&todo, &now).tags, 15.0);
+    }
+
+    #[test]
+    fn test_set_pair() {
+        let mut weights = UrgencyWeights::default();
+        weights.set_pair("tag.工作=2.5").unwrap();
+        weights.set_pair("due = 8").unwrap();
+        assert_eq!(weights.tags["工作"], 2.5);
+        assert_eq!(weights.due, 8.0);
+        assert!(weights.set_pair("colour=1").is_err());
+        assert!(weights.set_pair("due").is_err());
+        assert!(weights.set_pair("due=high").is_err());
+    }
+}
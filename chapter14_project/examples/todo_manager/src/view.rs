@@ -0,0 +1,150 @@
+//! 视图和上下文
+//!
+//! 视图是一组保存下来的 `list` 选项，`todo view morning` 相当于用这些选项运行 `list`：
+//!
+//! ```toml
+//! [views.morning]
+//! args = ["--priority", "high", "--tag", "工作", "--sort", "date"]
+//! ```
+//!
+//! 上下文是一组筛选条件和默认标签。切换到某个上下文后，`list` 和 `next` 只显示满足筛选条件的事项，
+//! `add` 新建的事项自动带上默认标签：
+//!
+//! ```toml
+//! context = "work"
+//!
+//! [contexts.work]
+//! filter = ["--tag", "工作"]
+//! tags = ["工作"]
+//! ```
+
+use serde::Deserialize;
+
+use crate::cli;
+use crate::config::Config;
+use crate::filter::Filter;
+use crate::tags;
+use crate::todo::Todo;
+
+/// `view` 和 `context` 的子命令名，不能用作视图或上下文的名称
+pub const RESERVED_NAMES: &[&str] = &["save", "define", "delete", "list", "none"];
+
+/// 保存的视图
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct View {
+    /// `list` 的选项
+    pub args: Vec<String>,
+}
+
+/// 上下文的定义
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Context {
+    /// 与 `list` 相同的筛选选项
+    #[serde(default)]
+    pub filter: Vec<String>,
+    /// `add` 时自动加上的标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Context {
+    /// 给新建的事项加上默认标签
+    pub fn apply_tags(&self, todo: &mut Todo) {
+        for tag in &self.tags {
+            todo.add_tag(tags::normalize(tag));
+        }
+    }
+}
+
+/// 配置中当前的上下文及其名称
+pub fn current_context(config: &Config) -> Option<(&str, &Context)> {
+    let name = config.context.as_deref()?;
+    config.contexts.get(name).map(|context| (name, context))
+}
+
+/// 当前上下文的筛选条件，没有上下文时返回 `None`
+///
+/// 上下文只负责筛选，是否包含已完成的事项由命令本身决定。
+pub fn context_filter(config: &Config) -> Result<Option<Filter>, String> {
+    let Some((name, context)) = current_context(config) else {
+        return Ok(None);
+    };
+    let args = cli::parse_list_args(context.filter.iter().map(String::as_str))
+        .map_err(|e| format!("上下文 {} 的筛选条件无效: {}", name, e))?;
+    let mut filter = cli::parse_filter(&args, config);
+    filter.include_completed = true;
+    Ok(Some(filter))
+}
+
+/// 检查视图或上下文的名称
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('-') || name.chars().any(char::is_whitespace) {
+        Err(format!("无效的名称: {:?}", name))
+    } else if RESERVED_NAMES.contains(&name) {
+        Err(format!("{} 是保留的名称，请换一个名称", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// 将命令行中的 `view <名称>` 替换为 `list <视图的选项>`，其余参数保持不变
+///
+/// 视图的选项放在命令行中的其他选项之前，因此 `todo view morning --all` 会在视图的基础上加上 `--all`。
+pub fn expand(argv: &[String], name: &str, view: &View) -> Vec<String> {
+    let position = argv.windows(2).position(|w| w[0] == "view" && w[1] == name);
+    let Some(i) = position else {
+        return argv.to_vec();
+    };
+    let mut expanded = argv[..i].to_vec();
+    expanded.push("list".to_string());
+    expanded.extend(view.args.iter().cloned());
+    expanded.extend(argv[i + 2..].iter().cloned());
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let argv: Vec<String> = ["todo", "--file", "a.json", "view", "morning", "--all"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let view = View {
+            args: vec!["--tag".to_string(), "工作".to_string()],
+        };
+        assert_eq!(
+            expand(&argv, "morning", &view),
+            ["todo", "--file", "a.json", "list", "--tag", "工作", "--all"]
+        );
+        assert!(validate_name("save").is_err());
+        assert!(validate_name("-p").is_err());
+        assert!(validate_name("早上").is_ok());
+    }
+
+    #[test]
+    fn test_context() {
+        let mut config = Config::default();
+        assert!(context_filter(&config).unwrap().is_none());
+        let work = Context {
+            filter: vec!["--tag".to_string(), "工作".to_string()],
+            tags: vec!["工作/ 周报".to_string()],
+        };
+        config.contexts.insert("work".to_string(), work);
+        config.context = Some("work".to_string());
+
+        let filter = context_filter(&config).unwrap().unwrap();
+        assert_eq!(filter.tag.as_deref(), Some("工作"));
+        assert!(filter.include_completed);
+        let mut todo = Todo::new("写周报".to_string(), None);
+        current_context(&config).unwrap().1.apply_tags(&mut todo);
+        assert_eq!(todo.tags(), ["工作/周报"]);
+
+        config.contexts.get_mut("work").unwrap().filter = vec!["--bogus".to_string()];
+        assert!(context_filter(&config).unwrap_err().contains("上下文 work"));
+    }
+}
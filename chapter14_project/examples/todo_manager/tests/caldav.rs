@@ -0,0 +1,313 @@
+//! `todo sync caldav` 的集成测试：在测试中启动一个最小的 CalDAV 服务器作为远程集合
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tiny_http::{Header, Response, Server};
+
+mod common;
+
+use common::TestDir;
+
+const COLLECTION: &str = "/dav/todos/";
+
+/// 远程集合的内容，每次修改都会使版本号加一，同步令牌即为版本号
+#[derive(Default)]
+struct Store {
+    version: u64,
+    /// 路径 -> （ETag，内容，修改时的版本号）
+    items: BTreeMap<String, (String, String, u64)>,
+    /// 已删除资源的路径 -> 删除时的版本号
+    deleted: BTreeMap<String, u64>,
+    /// 收到的请求，格式为 `方法 路径`
+    requests: Vec<String>,
+    /// REPORT 请求中携带的同步令牌
+    tokens: Vec<Option<String>>,
+}
+
+impl Store {
+    fn put(&mut self, href: &str, data: String) -> String {
+        self.version += 1;
+        let etag = format!("\"v{}\"", self.version);
+        self.items.insert(href.to_string(), (etag.clone(), data, self.version));
+        self.deleted.remove(href);
+        etag
+    }
+
+    fn delete(&mut self, href: &str) {
+        self.version += 1;
+        self.items.remove(href);
+        self.deleted.insert(href.to_string(), self.version);
+    }
+
+    fn report(&mut self, body: &str) -> String {
+        let token = body
+            .split("<d:sync-token>")
+            .nth(1)
+            .and_then(|rest| rest.split("</d:sync-token>").next())
+            .map(str::to_string);
+        let since = token.as_deref().and_then(|t| t.strip_prefix("tok-")).and_then(|v| v.parse().ok()).unwrap_or(0);
+        self.tokens.push(token);
+
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<d:multistatus xmlns:d=\"DAV:\">");
+        for (href, (etag, _, version)) in &self.items {
+            if *version > since {
+                xml.push_str(&format!(
+                    "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:getetag>{}</d:getetag></d:prop>\
+                     <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                    href,
+                    etag.replace('"', "&quot;")
+                ));
+            }
+        }
+        for (href, version) in &self.deleted {
+            if since > 0 && *version > since {
+                xml.push_str(&format!(
+                    "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                    href
+                ));
+            }
+        }
+        xml.push_str(&format!("<d:sync-token>tok-{}</d:sync-token></d:multistatus>", self.version));
+        xml
+    }
+}
+
+/// 在系统分配的端口上运行的 CalDAV 服务器，离开作用域时停止
+struct CalDavServer {
+    server: Arc<Server>,
+    store: Arc<Mutex<Store>>,
+    url: String,
+}
+
+impl CalDavServer {
+    fn start() -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let url = format!("http://{}/dav", server.server_addr().to_ip().unwrap());
+        let store = Arc::new(Mutex::new(Store::default()));
+
+        let (server_ref, store_ref) = (Arc::clone(&server), Arc::clone(&store));
+        thread::spawn(move || {
+            for mut request in server_ref.incoming_requests() {
+                let header = |name: &str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+                        .map(|h| h.value.as_str().to_string())
+                };
+                let (if_match, if_none_match) = (header("If-Match"), header("If-None-Match"));
+                let method = request.method().as_str().to_string();
+                let href = request.url().to_string();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+
+                let mut store = store_ref.lock().unwrap();
+                store.requests.push(format!("{} {}", method, href));
+                let current = store.items.get(&href).map(|(etag, data, _)| (etag.clone(), data.clone()));
+                let precondition_failed = match (&current, &if_match, &if_none_match) {
+                    (Some(_), _, Some(_)) => true,
+                    (Some((etag, _)), Some(expected), _) => etag != expected,
+                    (None, Some(_), _) => method == "PUT",
+                    _ => false,
+                };
+
+                let response = match method.as_str() {
+                    "REPORT" if href == COLLECTION => Response::from_string(store.report(&body)).with_status_code(207),
+                    _ if precondition_failed => Response::from_string("").with_status_code(412),
+                    "GET" => match current {
+                        Some((etag, data)) => {
+                            Response::from_string(data).with_header(Header::from_bytes("ETag", etag).unwrap())
+                        }
+                        None => Response::from_string("").with_status_code(404),
+                    },
+                    "PUT" => {
+                        let etag = store.put(&href, body);
+                        Response::from_string("")
+                            .with_status_code(if current.is_some() { 204 } else { 201 })
+                            .with_header(Header::from_bytes("ETag", etag).unwrap())
+                    }
+                    "DELETE" if current.is_some() => {
+                        store.delete(&href);
+                        Response::from_string("").with_status_code(204)
+                    }
+                    "DELETE" => Response::from_string("").with_status_code(404),
+                    _ => Response::from_string("").with_status_code(405),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        CalDavServer { server, store, url }
+    }
+
+    fn store(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap()
+    }
+
+    /// 修改远程资源的标题和最后修改时间
+    fn edit_remote(&self, title: &str, new_title: &str, last_modified: &str) {
+        let mut store = self.store();
+        let (href, data) = store
+            .items
+            .iter()
+            .find(|(_, (_, data, _))| data.contains(&format!("SUMMARY:{}\r\n", title)))
+            .map(|(href, (_, data, _))| (href.clone(), data.clone()))
+            .expect("远程没有该事项");
+        let data: String = data
+            .lines()
+            .map(|line| {
+                if line.starts_with("SUMMARY:") {
+                    format!("SUMMARY:{}\r\n", new_title)
+                } else if line.starts_with("LAST-MODIFIED:") {
+                    format!("LAST-MODIFIED:{}\r\n", last_modified)
+                } else {
+                    format!("{}\r\n", line)
+                }
+            })
+            .collect();
+        store.put(&href, data);
+    }
+
+    fn remote_titles(&self) -> Vec<String> {
+        let mut titles: Vec<String> = self
+            .store()
+            .items
+            .values()
+            .filter_map(|(_, data, _)| data.lines().find_map(|l| l.strip_prefix("SUMMARY:")).map(str::to_string))
+            .collect();
+        titles.sort();
+        titles
+    }
+}
+
+impl Drop for CalDavServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+/// 与测试服务器同步，返回命令的输出
+fn sync(dir: &TestDir, server: &CalDavServer) -> String {
+    dir.run(&["sync", "caldav", "--url", &server.url, "--calendar", "todos"])
+}
+
+#[test]
+fn test_push_and_incremental_pull() {
+    let server = CalDavServer::start();
+    let dir = TestDir::new("incremental");
+    dir.run(&["add", "写周报"]);
+    dir.run(&["add", "买牛奶"]);
+
+    let output = sync(&dir, &server);
+    assert!(output.contains("推送 2 个"), "{}", output);
+    assert_eq!(server.remote_titles(), ["买牛奶", "写周报"]);
+
+    // 没有变化时只发送一次带令牌的 REPORT，自己推送的版本不会被重新下载
+    server.store().requests.clear();
+    let output = sync(&dir, &server);
+    assert!(output.contains("拉取 0 个，推送 0 个"), "{}", output);
+    assert_eq!(server.store().requests, [format!("REPORT {}", COLLECTION)]);
+    assert_eq!(server.store().tokens, [None, Some("tok-0".to_string())]);
+
+    // 远程修改和新增的事项被拉取到本地
+    server.edit_remote("买牛奶", "买豆浆", "20300101T000000Z");
+    let vtodo = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:remote-1\r\nSUMMARY:修水管\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    server.store().put("/dav/todos/remote-1.ics", vtodo.to_string());
+    let output = sync(&dir, &server);
+    assert!(output.contains("拉取 2 个，推送 0 个"), "{}", output);
+    let list = dir.run(&["list"]);
+    assert!(list.contains("买豆浆") && list.contains("修水管") && !list.contains("买牛奶"), "{}", list);
+}
+
+#[test]
+fn test_deletions() {
+    let server = CalDavServer::start();
+    let dir = TestDir::new("deletions");
+    dir.run(&["add", "写周报"]);
+    dir.run(&["add", "买牛奶"]);
+    sync(&dir, &server);
+
+    // 本地删除的事项在远程删除
+    dir.run(&["remove", "0"]);
+    let output = sync(&dir, &server);
+    assert!(output.contains("删除远程 1 个"), "{}", output);
+    assert_eq!(server.remote_titles(), ["买牛奶"]);
+
+    // 远程删除的事项在本地删除
+    let href = server.store().items.keys().next().unwrap().clone();
+    server.store().delete(&href);
+    let output = sync(&dir, &server);
+    assert!(output.contains("删除本地 1 个"), "{}", output);
+    assert!(!dir.run(&["list"]).contains("买牛奶"));
+}
+
+#[test]
+fn test_conflicts_resolved_by_last_modified() {
+    let server = CalDavServer::start();
+    let dir = TestDir::new("conflicts");
+    dir.run(&["add", "写周报"]);
+    sync(&dir, &server);
+
+    // 远程的修改更早，保留本地版本并推送到远程
+    dir.run(&["edit", "0", "--title", "写月报"]);
+    server.edit_remote("写周报", "写年报", "20000101T000000Z");
+    let output = sync(&dir, &server);
+    assert!(output.contains("冲突 1 个") && output.contains("保留本地版本"), "{}", output);
+    assert_eq!(server.remote_titles(), ["写月报"]);
+
+    // 远程的修改更晚，保留远程版本
+    dir.run(&["edit", "0", "--title", "写季报"]);
+    server.edit_remote("写月报", "写日报", "20990101T000000Z");
+    let output = sync(&dir, &server);
+    assert!(output.contains("保留远程版本"), "{}", output);
+    assert!(dir.run(&["list"]).contains("写日报"));
+    assert_eq!(server.remote_titles(), ["写日报"]);
+
+    let log = fs::read_to_string(dir.path().join("todo.json.caldav-conflicts.log")).unwrap();
+    assert_eq!(log.lines().count(), 2);
+}
+
+#[test]
+fn test_first_sync_with_identical_items() {
+    let server = CalDavServer::start();
+    let dir = TestDir::new("identical");
+    dir.run(&["add", "写周报", "--tags", "工作"]);
+    sync(&dir, &server);
+
+    // 丢失同步状态后再次同步，双方相同的事项不算冲突
+    fs::remove_file(dir.path().join("todo.json.caldav.json")).unwrap();
+    server.store().requests.clear();
+    let output = sync(&dir, &server);
+    assert!(output.contains("拉取 0 个，推送 0 个") && output.contains("冲突 0 个"), "{}", output);
+    assert!(!fs::exists(dir.path().join("todo.json.caldav-conflicts.log")).unwrap());
+
+    // 内容不同时仍然作为冲突处理
+    fs::remove_file(dir.path().join("todo.json.caldav.json")).unwrap();
+    dir.run(&["edit", "0", "--title", "写月报"]);
+    let output = sync(&dir, &server);
+    assert!(output.contains("冲突 1 个"), "{}", output);
+}
+
+#[test]
+fn test_pull_keeps_local_only_data() {
+    let server = CalDavServer::start();
+    let dir = TestDir::new("local_only");
+    dir.run(&["add", "写周报", "--remind", "1d"]);
+    dir.run(&["note", "0", "已收集数据"]);
+    sync(&dir, &server);
+
+    // 远程的修改不会清除 iCalendar 中没有的注释和提醒时间
+    server.edit_remote("写周报", "写月报", "20300101T000000Z");
+    let output = sync(&dir, &server);
+    assert!(output.contains("拉取 1 个"), "{}", output);
+    let show = dir.run(&["show", "0"]);
+    assert!(show.contains("写月报") && show.contains("已收集数据"), "{}", show);
+    let list = dir.run(&["list", "--columns", "title,reminders"]);
+    assert!(list.contains("写月报  提前 1d"), "{}", list);
+
+    // 合并后的版本不算本地修改
+    let output = sync(&dir, &server);
+    assert!(output.contains("拉取 0 个，推送 0 个"), "{}", output);
+}
@@ -0,0 +1,44 @@
+//! TODO_NOW 的集成测试：固定当前时间后，创建时间、过期判断和导出结果都可以重现
+
+use std::process::Output;
+
+mod common;
+
+use common::TestDir;
+
+/// 以 `now` 为当前时间运行
+fn output_at(dir: &TestDir, now: &str, args: &[&str]) -> Output {
+    dir.command(args).env("TODO_NOW", now).output().unwrap()
+}
+
+fn run_at(dir: &TestDir, now: &str, args: &[&str]) -> String {
+    let output = output_at(dir, now, args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_todo_now() {
+    let dir = TestDir::new("now");
+    run_at(&dir, "2026-10-19 09:00", &["add", "写周报", "--due", "2026-10-20"]);
+    run_at(&dir, "2026-10-19 09:00", &["add", "买牛奶"]);
+
+    let list = run_at(&dir, "2026-10-19 10:00", &["list"]);
+    assert!(list.contains("创建时间: 2026-10-19 09:00:00"), "{}", list);
+    assert!(list.contains("(未过期)"));
+    assert!(run_at(&dir, "2026-10-19 10:00", &["list", "--overdue"]).contains("没有符合条件"));
+
+    let list = run_at(&dir, "2026-10-21", &["list"]);
+    assert!(list.contains("(已过期)"), "{}", list);
+    let overdue = run_at(&dir, "2026-10-21", &["list", "--overdue"]);
+    assert!(overdue.contains("写周报") && !overdue.contains("买牛奶"));
+
+    run_at(&dir, "2026-10-21T08:30:00", &["done", "0"]);
+    let ics = run_at(&dir, "2026-10-22", &["export", "-f", "ics", "-o", "-"]);
+    assert_eq!(ics, run_at(&dir, "2026-10-22", &["export", "-f", "ics", "-o", "-"]));
+    assert!(run_at(&dir, "2026-10-22", &["show", "0"]).contains("完成时间: 2026-10-21 08:30:00"));
+
+    let output = output_at(&dir, "下周一", &["list"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TODO_NOW"));
+}
@@ -0,0 +1,56 @@
+//! 排序规则的集成测试：标题排序、标签列表、标签树和按标签分组的导出
+
+mod common;
+
+use common::TestDir;
+
+/// 四个中英文标题和标签的待办事项
+fn setup(name: &str) -> TestDir {
+    let dir = TestDir::new(name);
+    for (title, tag) in [("张三的生日", "张三"), ("阿姨来访", "阿姨"), ("Zoom 会议", "Zoom"), ("apple 订单", "apple")] {
+        dir.run(&["add", title, "--tags", tag]);
+    }
+    dir
+}
+
+/// 列表中的 ID，按输出的顺序
+fn ids(output: &str) -> Vec<usize> {
+    output
+        .lines()
+        .filter(|line| line.starts_with("[ ] "))
+        .map(|line| line.split('.').next().unwrap().rsplit(' ').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+/// 每行开头的名称，按输出的顺序
+fn names(output: &str) -> Vec<&str> {
+    output.lines().map(|line| line.split(' ').next().unwrap()).collect()
+}
+
+#[test]
+fn test_sort_titles() {
+    let dir = setup("titles");
+    assert_eq!(ids(&dir.run(&["list", "--sort", "title"])), [3, 2, 0, 1]);
+    assert_eq!(ids(&dir.run(&["list", "--sort", "title", "--collate", "pinyin"])), [1, 0, 3, 2]);
+    assert_eq!(ids(&dir.run(&["list", "--sort", "title", "--collate", "bytes"])), [2, 3, 0, 1]);
+
+    // 配置中的默认排序规则，命令行选项优先
+    dir.run(&["config", "set", "defaults.collate", "pinyin"]);
+    assert_eq!(ids(&dir.run(&["list", "--sort", "title"])), [1, 0, 3, 2]);
+    assert_eq!(ids(&dir.run(&["list", "--sort", "title", "--collate", "unicode"])), [3, 2, 0, 1]);
+
+    assert!(!dir.output(&["list", "--collate", "gbk"]).status.success());
+    assert!(!dir.output(&["config", "set", "defaults.collate", "gbk"]).status.success());
+}
+
+#[test]
+fn test_sort_tags() {
+    let dir = setup("tags");
+    assert_eq!(names(&dir.run(&["tags"])), ["apple", "Zoom", "张三", "阿姨"]);
+    assert_eq!(names(&dir.run(&["tags", "--collate", "pinyin"])), ["阿姨", "张三", "apple", "Zoom"]);
+    assert_eq!(names(&dir.run(&["tags", "--tree", "--collate", "bytes"])), ["Zoom", "apple", "张三", "阿姨"]);
+
+    let markdown = dir.run(&["export", "--format", "markdown", "--group-by", "tag", "--collate", "pinyin", "-o", "-"]);
+    let headings: Vec<&str> = markdown.lines().filter_map(|line| line.strip_prefix("## ")).collect();
+    assert_eq!(headings, ["阿姨", "张三", "apple", "Zoom"]);
+}
@@ -0,0 +1,133 @@
+//! 集成测试共用的测试目录
+//!
+//! 每个测试使用一个独立的临时目录，同时作为用户主目录、工作目录和数据文件所在的目录，
+//! 命令行程序不会读到运行测试的用户的配置、密钥和终端设置。
+
+// 每个测试文件只用到其中的一部分
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+pub const BIN: &str = env!("CARGO_BIN_EXE_todo_manager");
+
+/// 测试用的数据目录，离开作用域时删除
+pub struct TestDir {
+    path: PathBuf,
+    /// 通过 `--file` 指定的数据文件名，`None` 时由配置决定
+    data_file: Option<&'static str>,
+    /// 每次运行时额外设置的环境变量
+    envs: Vec<(&'static str, String)>,
+}
+
+impl TestDir {
+    /// 创建 `todo_<测试文件>_<名称>_<进程号>` 目录，已存在时先清空
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "todo_{}_{}_{}",
+            env!("CARGO_CRATE_NAME"),
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir {
+            path,
+            data_file: Some("todo.json"),
+            envs: Vec::new(),
+        }
+    }
+
+    /// 使用其他名称的数据文件，如 `todo.txt`
+    pub fn data_file(mut self, name: &'static str) -> Self {
+        self.data_file = Some(name);
+        self
+    }
+
+    /// 不指定 `--file`，数据文件由配置决定
+    pub fn without_data_file(mut self) -> Self {
+        self.data_file = None;
+        self
+    }
+
+    /// 每次运行时设置的环境变量
+    pub fn env(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.envs.push((key, value.into()));
+        self
+    }
+
+    /// 写入默认位置的配置文件
+    pub fn config(self, contents: &str) -> Self {
+        fs::create_dir_all(self.config_path().parent().unwrap()).unwrap();
+        fs::write(self.config_path(), contents).unwrap();
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 默认位置的配置文件
+    pub fn config_path(&self) -> PathBuf {
+        self.path.join(".config/todo/config.toml")
+    }
+
+    /// 在测试目录中写入可执行的 shell 脚本，如钩子和插件
+    pub fn script(&self, relative: &str, body: &str) -> PathBuf {
+        let path = self.path.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// 运行命令行的命令，调用方可以继续设置环境变量和标准输入输出
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(BIN);
+        if let Some(data_file) = self.data_file {
+            command.arg("--file").arg(self.path.join(data_file));
+        }
+        command
+            .args(args)
+            .current_dir(&self.path)
+            .env("HOME", &self.path)
+            .env_remove("TODO_PASSPHRASE")
+            .env_remove("TODO_NOW")
+            .env_remove("NO_COLOR")
+            .env_remove("COLUMNS");
+        for (key, value) in &self.envs {
+            command.env(key, value);
+        }
+        command
+    }
+
+    pub fn output(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// 运行并要求成功，返回标准输出
+    pub fn run(&self, args: &[&str]) -> String {
+        let output = self.output(args);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// 运行并要求失败，返回标准错误
+    pub fn fail(&self, args: &[&str]) -> String {
+        let output = self.output(args);
+        assert!(!output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        String::from_utf8(output.stderr).unwrap()
+    }
+
+    pub fn exit_code(&self, args: &[&str]) -> Option<i32> {
+        self.output(args).status.code()
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
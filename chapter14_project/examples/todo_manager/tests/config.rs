@@ -0,0 +1,68 @@
+//! 配置文件的集成测试：默认值、日期格式、列表布局、数据文件路径和 config 命令
+
+use std::fs;
+
+mod common;
+
+use common::TestDir;
+
+#[test]
+fn test_defaults_and_layout() {
+    let dir = TestDir::new("defaults").without_data_file();
+    fs::create_dir_all(dir.path().join("data")).unwrap();
+    dir.run(&["config", "set", "file", "~/data/todo.json"]);
+    dir.run(&["config", "set", "defaults.priority", "high"]);
+    dir.run(&["config", "set", "defaults.due_time", "18:00"]);
+    dir.run(&["config", "set", "format.date", "%d.%m.%Y"]);
+    dir.run(&["config", "set", "format.datetime", "%d.%m. %H:%M"]);
+    dir.run(&["config", "set", "list.columns", "tags,due"]);
+    assert_eq!(dir.run(&["config", "get", "defaults.priority"]), "high\n");
+
+    dir.run(&["add", "写周报", "--due", "20.10.2026", "--tags", "工作"]);
+    assert!(dir.path().join("data/todo.json").exists());
+    assert!(!dir.output(&["add", "买牛奶", "--due", "2026-10-20"]).status.success());
+
+    let list = dir.run(&["list"]);
+    assert!(list.contains("[高]"), "{}", list);
+    let tags = list.find("标签: 工作").unwrap();
+    let due = list.find("截止日期: 20.10. 18:00").unwrap();
+    assert!(tags < due, "{}", list);
+    assert!(!list.contains("创建时间") && !list.contains("紧急度"));
+
+    // --file 优先于配置文件
+    let other = dir.path().join("other.json");
+    dir.run(&["--file", other.to_str().unwrap(), "add", "其他"]);
+    assert!(!dir.run(&["list"]).contains("其他"));
+
+    dir.run(&["config", "unset", "list.columns"]);
+    assert!(dir.run(&["list"]).contains("紧急度"));
+}
+
+#[test]
+fn test_validation() {
+    let dir = TestDir::new("validation").without_data_file();
+    let config_path = dir.path().join(".config/todo/config.toml");
+
+    let output = dir.output(&["config", "set", "defaults.sort", "random"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("defaults.sort"));
+    assert!(!config_path.exists());
+    assert!(!dir.output(&["config", "set", "colors.high", "orange"]).status.success());
+    assert!(!dir.output(&["config", "get", "unknown"]).status.success());
+
+    // 手动编辑的无效配置在启动时报告出错的设置项
+    fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs::write(&config_path, "[defaults]\nexport_format = \"pdf\"\n").unwrap();
+    let output = dir.output(&["list"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("defaults.export_format"));
+
+    // --config 指定其他配置文件
+    let custom = dir.path().join("custom.toml");
+    fs::write(&custom, "[defaults]\nexport_format = \"csv\"\n").unwrap();
+    let file = dir.path().join("todo.json");
+    let custom = custom.to_str().unwrap();
+    dir.run(&["--config", custom, "--file", file.to_str().unwrap(), "add", "写周报"]);
+    let csv = dir.run(&["--config", custom, "--file", file.to_str().unwrap(), "export", "-o", "-"]);
+    assert!(csv.starts_with("ID,"), "{}", csv);
+}
@@ -0,0 +1,72 @@
+//! 错误处理的集成测试：各类错误的退出状态，以及 `--output json` 的错误对象
+
+use std::fs;
+
+mod common;
+
+use common::TestDir;
+
+#[test]
+fn test_exit_codes() {
+    let dir = TestDir::new("codes");
+    dir.run(&["add", "写周报", "--tags", "工作"]);
+    dir.run(&["add", "买牛奶", "--tags", "生活"]);
+
+    assert_eq!(dir.exit_code(&["list", "--bogus"]), Some(2));
+    assert_eq!(dir.exit_code(&["add", "订机票", "--due", "下周"]), Some(2));
+    assert_eq!(dir.exit_code(&["done", "abc"]), Some(3));
+    assert_eq!(dir.exit_code(&["show", "9"]), Some(3));
+    assert_eq!(dir.exit_code(&["view", "missing"]), Some(4));
+    assert_eq!(dir.exit_code(&["note", "0", "--delete", "5"]), Some(4));
+    assert_eq!(dir.exit_code(&["add", "订机票", "--set", "points=3"]), Some(6));
+    assert_eq!(dir.exit_code(&["tag", "rename", "工作", "生活"]), Some(7));
+
+    fs::write(dir.path().join("todo.json"), "不是 JSON").unwrap();
+    assert_eq!(dir.exit_code(&["list"]), Some(5));
+}
+
+#[test]
+fn test_json_errors() {
+    let dir = TestDir::new("json");
+    dir.run(&["add", "写周报"]);
+
+    let output = dir.output(&["--output", "json", "done", "abc"]);
+    assert_eq!(output.status.code(), Some(3));
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "invalid_id");
+    assert_eq!(error["message"], "无效的ID: abc");
+    assert_eq!(error["exit_code"], 3);
+
+    let output = dir.output(&["--output", "json", "tag", "rename", "不存在", "新标签"]);
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "not_found");
+    assert_eq!(error["exit_code"], 4);
+
+    // 参数错误和首次运行时也只输出 JSON 对象
+    let output = dir.output(&["--output", "json", "add"]);
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "usage");
+    let empty = TestDir::new("json-empty");
+    let output = empty.output(&["--output", "json", "done", "abc"]);
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "invalid_id");
+
+    // 警告也以 JSON 对象输出，每行一个
+    let output = dir.output(&[
+        "--output", "json", "export", "--format", "todotxt", "--fields", "title", "--pending", "--search", "不存在",
+        "--output", "-",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let warnings: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0]["warning"], "没有符合条件的待办事项，导出的内容为空。");
+    assert_eq!(warnings[1]["warning"], "--fields 只适用于 csv、html 和 json 格式，已忽略");
+
+    // 默认以文本输出
+    let output = dir.output(&["done", "abc"]);
+    assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "无效的ID: abc");
+}
@@ -0,0 +1,78 @@
+//! 自定义字段的集成测试：在配置文件中声明字段，设置、筛选、排序和导出
+
+mod common;
+
+use common::TestDir;
+
+const CONFIG: &str = r#"
+[fields.points]
+type = "number"
+
+[fields.size]
+type = "enum"
+values = ["S", "M", "L"]
+
+[fields.estimate]
+type = "duration"
+"#;
+
+#[test]
+fn test_set_filter_sort() {
+    let dir = TestDir::new("filter").config(CONFIG);
+    dir.run(&["add", "写周报", "--set", "points=8", "--set", "size=L"]);
+    dir.run(&["add", "买牛奶", "--set", "points=1", "--set", "estimate=90m"]);
+    dir.run(&["add", "修水管"]);
+
+    // 值按声明的类型校验，未声明的字段会被拒绝
+    assert!(dir.fail(&["add", "遛狗", "--set", "points=很多"]).contains("points"));
+    assert!(dir.fail(&["edit", "2", "--set", "size=XL"]).contains("S, M, L"));
+    assert!(dir.fail(&["add", "遛狗", "--set", "customer=ACME"]).contains("未声明"));
+
+    let list = dir.run(&["list"]);
+    assert!(list.contains("字段: estimate=1h30m, points=1"));
+
+    let list = dir.run(&["list", "--field", "points>=2"]);
+    assert!(list.contains("写周报"));
+    assert!(!list.contains("买牛奶"));
+    assert!(!list.contains("修水管"));
+
+    // 数字按数值排序，没有该字段的事项排在最后
+    let list = dir.run(&["list", "--sort", "field.points"]);
+    let positions: Vec<usize> = ["买牛奶", "写周报", "修水管"].iter().map(|t| list.find(t).unwrap()).collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", list);
+
+    // 空值清除字段
+    dir.run(&["edit", "1", "--set", "estimate="]);
+    assert!(!dir.run(&["list", "--field", "estimate"]).contains("买牛奶"));
+}
+
+#[test]
+fn test_export_columns() {
+    let dir = TestDir::new("export").config(CONFIG);
+    dir.run(&["add", "写周报", "--set", "points=8", "--set", "size=M"]);
+    dir.run(&["add", "买牛奶"]);
+
+    let csv = dir.run(&["export", "-f", "csv", "-o", "-"]);
+    let header = csv.lines().next().unwrap();
+    assert!(header.ends_with(",points,size"), "{}", header);
+    assert!(csv.lines().nth(1).unwrap().ends_with(",8,M"));
+
+    let json = dir.run(&["export", "-f", "json", "-o", "-", "--fields", "title,field.points"]);
+    assert!(json.contains("\"field.points\": \"8\""), "{}", json);
+
+    let txt = dir.run(&["export", "-f", "todotxt", "-o", "-"]);
+    assert!(txt.contains("field.points:8 field.size:M"));
+}
+
+#[test]
+fn test_export_empty() {
+    let dir = TestDir::new("export_empty").config(CONFIG);
+    dir.run(&["add", "写周报"]);
+
+    // 没有符合条件的事项时输出空的导出内容
+    let json = dir.run(&["export", "-f", "json", "-o", "-", "--tag", "不存在"]);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap(), serde_json::json!([]));
+    let csv = dir.run(&["export", "-f", "csv", "-o", "-", "--tag", "不存在"]);
+    assert!(csv.starts_with("ID,"), "{}", csv);
+    assert_eq!(csv.lines().count(), 1);
+}
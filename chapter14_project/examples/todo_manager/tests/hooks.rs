@@ -0,0 +1,76 @@
+//! 钩子的集成测试：拒绝操作、修改事项、提示信息和 --no-hooks
+#![cfg(unix)]
+
+mod common;
+
+use common::TestDir;
+
+#[test]
+fn test_veto_and_modify() {
+    let dir = TestDir::new("veto");
+    // 工作事项必须带有 ticket/ 开头的标签
+    dir.script(
+        ".config/todo/hooks/on-add-ticket",
+        "read todo\n\
+         if echo \"$todo\" | grep -q '\"工作\"' && ! echo \"$todo\" | grep -q '\"ticket/'; then\n\
+         echo '工作事项需要 ticket 标签'\n\
+         exit 1\n\
+         fi\n\
+         echo \"$todo\"",
+    );
+    // 完成时在标题后加上标记
+    dir.script(".config/todo/hooks/on-done", "read before\nread after\necho \"$after\" | sed 's/\"title\":\"\\([^\"]*\\)\"/\"title\":\"\\1（已验收）\"/'\necho 已验收");
+
+    let output = dir.output(&["add", "写接口", "--tags", "工作"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("工作事项需要 ticket 标签"));
+    assert!(!dir.path().join("todo.json").exists());
+
+    dir.run(&["add", "写接口", "--tags", "工作,ticket/42"]);
+    assert!(dir.run(&["done", "0"]).contains("已验收"));
+    assert!(dir.run(&["list", "--all"]).contains("写接口（已验收）"));
+
+    // --no-hooks 跳过钩子
+    dir.run(&["--no-hooks", "add", "开会", "--tags", "工作"]);
+    assert!(dir.run(&["list"]).contains("开会"));
+}
+
+#[test]
+fn test_modify_and_remove() {
+    let dir = TestDir::new("modify");
+    dir.script(".config/todo/hooks/on-modify", "read before\nread after\necho \"$after\"\necho \"$TODO_EVENT\"");
+    dir.script(".config/todo/hooks/on-remove", "echo 不能删除\nexit 1");
+    dir.run(&["add", "写周报"]);
+
+    let output = dir.run(&["edit", "0", "--title", "写月报"]);
+    assert!(output.contains("on-modify"), "{}", output);
+    assert!(dir.run(&["note", "0", "先收集数据"]).contains("on-modify"));
+
+    let output = dir.output(&["remove", "0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("不能删除"));
+    assert!(dir.run(&["list"]).contains("写月报"));
+
+    // 修改标识符的钩子输出无效，修改不会保存
+    dir.script(".config/todo/hooks/on-modify", "read before\nread after\necho \"$after\" | sed 's/\"uid\":\"[^\"]*\"/\"uid\":\"x\"/'");
+    assert!(!dir.output(&["edit", "0", "--title", "写年报"]).status.success());
+    assert!(dir.run(&["list"]).contains("写月报"));
+}
+
+#[test]
+fn test_tag_rename() {
+    let dir = TestDir::new("tag");
+    dir.run(&["add", "写周报", "--tags", "工作/周报"]);
+    dir.run(&["add", "写月报", "--tags", "工作/月报"]);
+    dir.script(".config/todo/hooks/on-modify", "read before\nread after\necho \"$after\"\necho 已修改");
+
+    // 每个修改过的事项运行一次 on-modify
+    let output = dir.run(&["tag", "rename", "工作", "公司"]);
+    assert_eq!(output.matches("已修改").count(), 2, "{}", output);
+
+    // 任意一个钩子拒绝时不保存任何修改
+    dir.script(".config/todo/hooks/on-modify", "read before\nread after\necho \"$after\" | grep -q 月报 && exit 1\necho \"$after\"");
+    assert!(!dir.output(&["tag", "rename", "公司", "工作"]).status.success());
+    let list = dir.run(&["list"]);
+    assert!(list.contains("公司/周报") && list.contains("公司/月报"), "{}", list);
+}
@@ -0,0 +1,90 @@
+//! 作为库使用时的集成测试：不经过命令行，直接使用数据模型、存储、筛选和导出
+
+use std::fs;
+use std::sync::Arc;
+
+use chrono::Duration;
+use todo_manager::clock::{self, FixedClock};
+use todo_manager::export::{self, ExportOptions};
+use todo_manager::import;
+use todo_manager::{Config, Field, Filter, Priority, Record, SortKey, Todo, TodoStorage};
+
+#[test]
+fn test_storage_filter_and_export() {
+    let dir = std::env::temp_dir().join(format!("todo_library_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("todo.json");
+
+    let config = Config::default();
+    let mut report = Todo::with_tags("写周报".to_string(), None, vec!["工作".to_string()]);
+    report.set_priority(Priority::High);
+    report.set_due_date(Some(config.parse_due_date("2026-10-23").unwrap()));
+    let mut storage = TodoStorage::new(&path);
+    storage.add(Todo::new("买牛奶".to_string(), None));
+    storage.add(report);
+    storage.save().unwrap();
+
+    let mut storage = TodoStorage::new(&path);
+    storage.load().unwrap();
+    let filter = Filter {
+        tag: Some("工作".to_string()),
+        sort: Some(SortKey::Priority),
+        ..Default::default()
+    };
+    let records: Vec<Record> = filter
+        .apply(storage.todos(), storage.clock().as_ref())
+        .into_iter()
+        .map(|(id, todo)| Record { id, todo })
+        .collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, 1);
+
+    let options = ExportOptions {
+        fields: Some(vec![Field::Id, Field::Title, Field::Due]),
+        ..Default::default()
+    };
+    let mut csv = Vec::new();
+    export::exporter("csv", options).unwrap().export(&records, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.contains("1,写周报,2026-10-23"), "{}", csv);
+    assert!(config.parse_due_date("23.10.2026").is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_storage_clock() {
+    let dir = std::env::temp_dir().join(format!("todo_library_clock_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("todo.json");
+
+    // 导入、完成和修改的时间都取自存储的时钟
+    let start = clock::parse_time("2026-10-19 09:00").unwrap();
+    let fixed = Arc::new(FixedClock::new(start));
+    let mut storage = TodoStorage::new(&path);
+    storage.set_clock(fixed.clone());
+    import::import(&mut storage, "markdown", "- [ ] 写周报\n- [x] 买牛奶\n").unwrap();
+    assert_eq!(*storage.todos()[1].created_at(), start);
+    assert_eq!(storage.todos()[1].completed_at(), Some(&start));
+    storage.save().unwrap();
+
+    let mut storage = TodoStorage::new(&path);
+    storage.set_clock(fixed.clone());
+    storage.load().unwrap();
+    fixed.advance(Duration::hours(3));
+    storage.mark_done(0).unwrap();
+    let todo = &storage.todos()[0];
+    assert_eq!(*todo.created_at(), start);
+    assert_eq!(todo.completed_at(), Some(&(start + Duration::hours(3))));
+    assert_eq!(*todo.modified_at(), start + Duration::hours(3));
+
+    fixed.advance(Duration::hours(1));
+    let mut todo = storage.todos()[0].clone();
+    todo.set_priority(Priority::High);
+    storage.update(0, todo).unwrap();
+    assert_eq!(*storage.todos()[0].modified_at(), start + Duration::hours(4));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
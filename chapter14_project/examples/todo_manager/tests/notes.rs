@@ -0,0 +1,56 @@
+//! 注释的集成测试：追加、修改、删除注释，以及在 show、搜索和导出中的显示
+
+mod common;
+
+use common::TestDir;
+
+#[test]
+fn test_note_show_and_search() {
+    let dir = TestDir::new("show");
+    dir.run(&["add", "写周报", "本周的工作总结"]);
+    dir.run(&["add", "买牛奶"]);
+    dir.run(&["note", "0", "已收集数据"]);
+    dir.run(&["note", "0", "等待 ACME 回复"]);
+    dir.run(&["note", "0", "草稿已发出"]);
+
+    let show = dir.run(&["show", "0"]);
+    assert!(show.contains("描述: 本周的工作总结"));
+    let first = show.find("1. [").unwrap();
+    let third = show.find("3. [").unwrap();
+    assert!(first < third);
+    assert!(show[third..].contains("草稿已发出"));
+
+    dir.run(&["note", "0", "--edit", "2", "已收到 ACME 的回复"]);
+    dir.run(&["note", "0", "--delete", "1"]);
+    let show = dir.run(&["show", "0"]);
+    assert!(!show.contains("已收集数据"));
+    assert!(show.contains("1. [") && show.contains("已收到 ACME 的回复"));
+    // 修改注释不影响描述
+    assert!(show.contains("描述: 本周的工作总结"));
+
+    assert!(!dir.output(&["note", "0", "--delete", "5"]).status.success());
+    assert!(!dir.output(&["show", "9"]).status.success());
+
+    let list = dir.run(&["list", "--search", "acme"]);
+    assert!(list.contains("写周报"));
+    assert!(!list.contains("买牛奶"));
+}
+
+#[test]
+fn test_export_annotations() {
+    let dir = TestDir::new("export");
+    dir.run(&["add", "写周报"]);
+    dir.run(&["note", "0", "<草稿> 已发出"]);
+
+    let json = dir.run(&["export", "-f", "json", "-o", "-"]);
+    assert!(json.contains("\"annotations\""));
+    assert!(json.contains("\"text\": \"<草稿> 已发出\""));
+
+    let html = dir.run(&["export", "-f", "html", "-o", "-"]);
+    assert!(html.contains("<th>注释</th>"));
+    assert!(html.contains("&lt;草稿&gt; 已发出</li>"));
+
+    let md = dir.run(&["export", "-f", "markdown", "-o", "-"]);
+    assert!(md.contains("- [ ] 写周报\n  - ["));
+    assert!(md.contains("] <草稿> 已发出\n"));
+}
@@ -0,0 +1,53 @@
+//! 插件的集成测试：运行 PATH 中的 todo-<名称>、传递参数和数据、在帮助中列出插件
+#![cfg(unix)]
+
+use std::path::PathBuf;
+
+mod common;
+
+use common::TestDir;
+
+/// 测试目录中的 bin 目录加在 PATH 前面
+fn setup(name: &str) -> TestDir {
+    let dir = TestDir::new(name);
+    let path = format!("{}:{}", dir.path().join("bin").display(), std::env::var("PATH").unwrap_or_default());
+    dir.env("PATH", path)
+}
+
+#[test]
+fn test_run_plugin() {
+    let dir = setup("run");
+    dir.script("bin/todo-dump", "cat");
+    dir.script("bin/todo-args", "echo \"$*\"\necho \"$TODO_FILE\"");
+    dir.script("bin/todo-fail", "exit 3");
+    dir.run(&["add", "写周报"]);
+    dir.run(&["add", "买牛奶"]);
+
+    let dump: serde_json::Value = serde_json::from_str(&dir.run(&["dump"])).unwrap();
+    let titles: Vec<&str> = dump.as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["写周报", "买牛奶"]);
+
+    let output = dir.run(&["args", "--week", "-n", "3"]);
+    let mut lines = output.lines();
+    assert_eq!(lines.next(), Some("--week -n 3"));
+    assert_eq!(lines.next().map(PathBuf::from), Some(dir.path().join("todo.json")));
+
+    assert_eq!(dir.output(&["fail"]).status.code(), Some(3));
+
+    let output = dir.output(&["missing"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("todo-missing"));
+}
+
+#[test]
+fn test_help_lists_plugins() {
+    let dir = setup("help");
+    dir.script("bin/todo-report", "true");
+    let help = dir.run(&["help"]);
+    assert!(help.contains("插件"), "{}", help);
+    assert!(help.contains("report"));
+    assert!(dir.run(&["--help"]).contains("report"));
+    // 内置的子命令优先于同名插件
+    dir.script("bin/todo-list", "echo 插件");
+    assert!(!dir.run(&["list"]).contains("插件"));
+}
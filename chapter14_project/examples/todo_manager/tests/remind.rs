@@ -0,0 +1,80 @@
+//! `todo remind` 的集成测试：通过 shell 命令和 webhook 接收提醒
+
+use std::fs;
+use std::process::Stdio;
+use std::time::Duration;
+
+use chrono::{Days, Local};
+use serde_json::Value;
+use tiny_http::Server;
+
+mod common;
+
+use common::TestDir;
+
+/// 今天之后第 `days` 天的日期
+fn date_after(days: u64) -> String {
+    (Local::now().date_naive() + Days::new(days)).format("%Y-%m-%d").to_string()
+}
+
+#[test]
+fn test_command_notifier_fires_once() {
+    let dir = TestDir::new("command");
+    dir.run(&["add", "写周报", "--due", &date_after(1)]);
+    dir.run(&["add", "买牛奶", "--due", &date_after(1), "--remind", "none"]);
+    dir.run(&["add", "修水管", "--due", &date_after(30)]);
+    dir.run(&["add", "交房租", "--due", &date_after(30), "--remind", "60d"]);
+
+    let remind = [
+        "remind",
+        "--offsets",
+        "3d",
+        "--notify",
+        "command",
+        "--command",
+        "echo \"$TODO_TITLE|$TODO_OFFSET\" >> fired.txt",
+    ];
+    dir.run(&remind);
+    let fired = fs::read_to_string(dir.path().join("fired.txt")).unwrap();
+    assert_eq!(fired, "写周报|3d\n交房租|60d\n");
+
+    // 重新运行不会重复提醒
+    dir.run(&remind);
+    assert_eq!(fs::read_to_string(dir.path().join("fired.txt")).unwrap(), fired);
+
+    // 修改截止日期后重新提醒
+    dir.run(&["edit", "0", "--due", &date_after(2)]);
+    dir.run(&remind);
+    let fired = fs::read_to_string(dir.path().join("fired.txt")).unwrap();
+    assert_eq!(fired.lines().count(), 3);
+    assert!(dir.run(&["list"]).contains("提醒: 不提醒"));
+}
+
+#[test]
+fn test_daemon_webhook() {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+    let dir = TestDir::new("webhook");
+    dir.run(&["add", "写周报", "--due", &date_after(1), "--remind", "2d"]);
+
+    let mut daemon = dir
+        .command(&["remind", "--daemon", "--interval", "1", "--notify", "webhook", "--webhook", &url])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let request = server.recv_timeout(Duration::from_secs(10)).unwrap();
+    let _ = daemon.kill();
+    let _ = daemon.wait();
+    let mut request = request.expect("没有收到提醒");
+    assert_eq!(request.method().as_str(), "POST");
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).unwrap();
+    let _ = request.respond(tiny_http::Response::empty(204));
+
+    let reminder: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(reminder["title"], "写周报");
+    assert_eq!(reminder["offset"], "2d");
+    assert!(reminder["message"].as_str().unwrap().contains("写周报"));
+}
@@ -0,0 +1,281 @@
+//! `todo rpc` 的集成测试：通过标准输入输出发送 JSON-RPC 请求
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+mod common;
+
+use common::TestDir;
+
+/// 运行中的 `todo rpc` 进程，离开作用域时结束进程并删除临时目录
+struct RpcClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+    dir: TestDir,
+}
+
+impl RpcClient {
+    fn start(name: &str) -> Self {
+        Self::start_in(TestDir::new(name))
+    }
+
+    /// 在准备好的目录中启动，用于需要配置文件的测试
+    fn start_in(dir: TestDir) -> Self {
+        let mut child = dir
+            .command(&["rpc"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if sender.send(serde_json::from_str(&line).unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        RpcClient { child, stdin, messages, next_id: 1, dir }
+    }
+
+    fn send_line(&mut self, line: &str) {
+        writeln!(self.stdin, "{}", line).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&self) -> Value {
+        self.messages.recv_timeout(Duration::from_secs(10)).expect("等待响应超时")
+    }
+
+    /// 发送请求并返回响应，期间收到的通知会被忽略
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.send_line(&request.to_string());
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    fn result(&mut self, method: &str, params: Value) -> Value {
+        let response = self.call(method, params);
+        assert!(response.get("error").is_none(), "{}", response);
+        response["result"].clone()
+    }
+
+    /// 运行命令行，修改同一个数据文件
+    fn cli(&self, args: &[&str]) {
+        self.dir.run(args);
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_handshake_and_commands() {
+    let mut client = RpcClient::start("commands");
+    let init = client.result("initialize", json!({}));
+    assert_eq!(init["protocolVersion"], "1.0");
+    let methods = init["capabilities"]["methods"].as_array().unwrap();
+    assert!(methods.contains(&json!("todo.update")));
+    assert_eq!(init["capabilities"]["notifications"], json!(["todo.changed"]));
+
+    let added = client.result("todo.add", json!({ "title": "写周报", "priority": "high", "tags": ["工作"] }));
+    assert_eq!(added["id"], 0);
+    let uid = added["uid"].as_str().unwrap().to_string();
+    client.result("todo.add", json!({ "title": "买牛奶", "due_date": "2026-10-20" }));
+
+    let updated = client.result("todo.update", json!({ "id": uid, "description": "先收集数据" }));
+    assert_eq!(updated["description"], "先收集数据");
+    let done = client.result("todo.done", json!({ "id": 1 }));
+    assert_eq!(done["completed"], true);
+
+    let pending = client.result("todo.list", json!({}));
+    assert_eq!(pending.as_array().unwrap().len(), 1);
+    let all = client.result("todo.list", json!({ "all": true, "sort": "title" }));
+    assert_eq!(all.as_array().unwrap().len(), 2);
+    let tagged = client.result("todo.list", json!({ "tag": "工作" }));
+    assert_eq!(tagged[0]["title"], "写周报");
+
+    let exported = client.result("todo.export", json!({ "format": "csv", "fields": ["id", "title"] }));
+    assert_eq!(exported["count"], 2);
+    assert!(exported["content"].as_str().unwrap().contains("写周报"));
+    let imported = client.result(
+        "todo.import",
+        json!({ "format": "markdown", "content": "- [x] 写周报\n- [ ] 修水管\n" }),
+    );
+    assert_eq!(imported["added"], 1);
+    assert_eq!(imported["updated"], 1);
+
+    let removed = client.result("todo.remove", json!({ "id": 2 }));
+    assert_eq!(removed["title"], "修水管");
+    let get = client.result("todo.get", json!({ "id": uid }));
+    assert_eq!(get["completed"], true);
+}
+
+#[test]
+fn test_errors() {
+    let mut client = RpcClient::start("errors");
+
+    let unknown = client.call("todo.frobnicate", json!({}));
+    assert_eq!(unknown["error"]["code"], -32601);
+    let invalid = client.call("todo.add", json!({ "priority": "high" }));
+    assert_eq!(invalid["error"]["code"], -32602);
+    assert!(invalid["error"]["message"].as_str().unwrap().contains("title"));
+    let missing = client.call("todo.done", json!({ "id": 5 }));
+    assert_eq!(missing["error"]["code"], -32001);
+    let positional = client.call("todo.get", json!([0]));
+    assert_eq!(positional["error"]["code"], -32602);
+
+    client.send_line("{not json");
+    assert_eq!(client.receive()["error"]["code"], -32700);
+    client.send_line(r#"{"jsonrpc":"1.0","id":7,"method":"todo.list"}"#);
+    let response = client.receive();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["error"]["code"], -32600);
+
+    // 批量请求中的通知没有响应
+    client.send_line(
+        r#"[{"jsonrpc":"2.0","method":"todo.add","params":{"title":"买牛奶"}},{"jsonrpc":"2.0","id":8,"method":"todo.list"}]"#,
+    );
+    let batch = client.receive();
+    assert_eq!(batch.as_array().unwrap().len(), 1);
+    assert_eq!(batch[0]["result"][0]["title"], "买牛奶");
+}
+
+#[test]
+fn test_list_collation() {
+    let mut client = RpcClient::start("collate");
+    let init = client.result("initialize", json!({}));
+    assert!(init["capabilities"]["filters"].as_array().unwrap().contains(&json!("collate")));
+
+    client.result("todo.add", json!({ "title": "中秋" }));
+    client.result("todo.add", json!({ "title": "阿姨" }));
+    let titles = |list: Value| -> Vec<String> {
+        list.as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap().to_string()).collect()
+    };
+    let pinyin = client.result("todo.list", json!({ "sort": "title", "collate": "pinyin" }));
+    assert_eq!(titles(pinyin), ["阿姨", "中秋"]);
+    let bytes = client.result("todo.list", json!({ "sort": "title", "collate": "bytes" }));
+    assert_eq!(titles(bytes), ["中秋", "阿姨"]);
+}
+
+#[test]
+fn test_change_notification() {
+    let mut client = RpcClient::start("notify");
+    client.result("initialize", json!({}));
+    client.result("todo.add", json!({ "title": "写周报" }));
+
+    // 本会话的修改不会触发通知，其他程序的修改会
+    client.cli(&["add", "买牛奶"]);
+    let notification = client.receive();
+    assert_eq!(notification["method"], "todo.changed");
+    assert!(notification.get("id").is_none());
+
+    let list = client.result("todo.list", json!({}));
+    assert_eq!(list.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_hooks() {
+    let mut client = RpcClient::start("hooks");
+    client.dir.script(".config/todo/hooks/on-add", "read todo\necho \"$todo\" | sed 's/写周报/写月报/'");
+    client.dir.script(".config/todo/hooks/on-modify", "echo 不能修改\nexit 1");
+
+    let added = client.result("todo.add", json!({ "title": "写周报" }));
+    assert_eq!(added["title"], "写月报");
+    let rejected = client.call("todo.update", json!({ "id": 0, "title": "写年报" }));
+    assert_eq!(rejected["error"]["code"], -32003);
+    assert!(rejected["error"]["message"].as_str().unwrap().contains("不能修改"), "{}", rejected);
+    assert_eq!(client.result("todo.get", json!({ "id": 0 }))["title"], "写月报");
+}
+
+#[test]
+fn test_next_search_note() {
+    let mut client = RpcClient::start("next_search");
+    let init = client.result("initialize", json!({}));
+    let methods = init["capabilities"]["methods"].as_array().unwrap();
+    assert!(methods.contains(&json!("todo.search")));
+    assert_eq!(client.result("todo.next", json!({})), Value::Null);
+
+    client.result("todo.add", json!({ "title": "买牛奶" }));
+    client.result("todo.add", json!({ "title": "写周报", "priority": "high", "due_date": "2026-10-20" }));
+    let next = client.result("todo.next", json!({}));
+    assert_eq!(next["title"], "写周报");
+    assert!(next["urgency"].as_f64().unwrap() > 0.0);
+
+    let noted = client.result("todo.note", json!({ "id": 0, "text": "要低脂的" }));
+    assert_eq!(noted["annotations"][0]["text"], "要低脂的");
+    let edited = client.result("todo.note", json!({ "id": 0, "edit": 1, "text": "要全脂的" }));
+    assert_eq!(edited["annotations"][0]["text"], "要全脂的");
+    let missing = client.call("todo.note", json!({ "id": 0, "delete": 2 }));
+    assert_eq!(missing["error"]["code"], -32001);
+
+    let hits = client.result("todo.search", json!({ "query": "全脂" }));
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["title"], "买牛奶");
+    assert_eq!(hits[0]["highlights"], json!([{ "field": "annotation", "index": 0, "ranges": [[3, 9]] }]));
+
+    let deleted = client.result("todo.note", json!({ "id": 0, "delete": 1 }));
+    assert!(deleted.get("annotations").is_none());
+    assert_eq!(client.result("todo.search", json!({ "query": "全脂" })), json!([]));
+}
+
+#[test]
+fn test_tags() {
+    let mut client = RpcClient::start("tags");
+    client.result("todo.add", json!({ "title": "写周报", "tags": ["工作"] }));
+    client.result("todo.add", json!({ "title": "修水管", "tags": ["家务"] }));
+    client.result("todo.add", json!({ "title": "买牛奶", "tags": ["购物"] }));
+
+    let tags = client.result("tag.list", json!({ "collate": "pinyin" }));
+    let names: Vec<&str> = tags.as_array().unwrap().iter().map(|t| t["tag"].as_str().unwrap()).collect();
+    assert_eq!(names, ["工作", "购物", "家务"]);
+    assert_eq!(tags[0]["pending"], 1);
+
+    let renamed = client.result("tag.rename", json!({ "from": "家务", "to": "家里" }));
+    assert_eq!(renamed["changed"], 1);
+    let conflict = client.call("tag.rename", json!({ "from": "购物", "to": "工作" }));
+    assert_eq!(conflict["error"]["code"], -32004);
+    let merged = client.result("tag.merge", json!({ "from": "购物", "to": "家里" }));
+    assert_eq!(merged["changed"], 1);
+
+    let tags = client.result("tag.list", json!({}));
+    assert_eq!(tags.as_array().unwrap().len(), 2);
+    assert_eq!(client.result("todo.get", json!({ "id": 2 }))["tags"], json!(["家里"]));
+}
+
+#[test]
+fn test_custom_fields() {
+    let dir = TestDir::new("fields").config("[fields.points]\ntype = \"number\"\n");
+    let mut client = RpcClient::start_in(dir);
+
+    let added = client.result("todo.add", json!({ "title": "写周报", "fields": { "points": 3 } }));
+    assert_eq!(added["fields"], json!({ "points": "3" }));
+    let undeclared = client.call("todo.update", json!({ "id": 0, "fields": { "customer": "ACME" } }));
+    assert_eq!(undeclared["error"]["code"], -32602);
+    let invalid = client.call("todo.update", json!({ "id": 0, "fields": { "points": "很多" } }));
+    assert_eq!(invalid["error"]["code"], -32602);
+    let cleared = client.result("todo.update", json!({ "id": 0, "fields": { "points": null } }));
+    assert!(cleared.get("fields").is_none());
+}
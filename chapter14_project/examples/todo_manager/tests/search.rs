@@ -0,0 +1,61 @@
+//! 搜索的集成测试：拼音、拼写错误、排序、已完成的事项和匹配部分的高亮
+
+mod common;
+
+use common::TestDir;
+
+/// 结果中的 ID，按输出的顺序
+fn ids(output: &str) -> Vec<usize> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("[ ] ").or_else(|| line.strip_prefix("[✓] ")))
+        .map(|line| line.split('.').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_search() {
+    let dir = TestDir::new("basic");
+    dir.run(&["add", "买牛奶", "--tags", "生活"]);
+    dir.run(&["add", "提交工作报告", "发给经理审核", "--tags", "工作"]);
+    dir.run(&["add", "Review quarterly budget", "--tags", "工作/财务"]);
+    dir.run(&["add", "整理报告模板"]);
+
+    assert_eq!(ids(&dir.run(&["search", "gzbg"])), [1]);
+    assert_eq!(ids(&dir.run(&["search", "niunai"])), [0]);
+    assert_eq!(ids(&dir.run(&["search", "quartely", "budgte"])), [2]);
+    // 标题中的匹配排在标签中的前面
+    assert_eq!(ids(&dir.run(&["search", "工作"])), [1, 2]);
+    assert_eq!(ids(&dir.run(&["search", "报告"])), [1, 3]);
+    assert_eq!(ids(&dir.run(&["search", "报告", "-n", "1"])), [1]);
+    assert!(dir.run(&["search", "机票"]).contains("没有找到匹配的待办事项"));
+
+    // 默认不包括已完成的事项
+    dir.run(&["done", "1"]);
+    assert_eq!(ids(&dir.run(&["search", "报告"])), [3]);
+    assert_eq!(ids(&dir.run(&["search", "报告", "--all"])), [1, 3]);
+}
+
+#[test]
+fn test_highlight() {
+    let dir = TestDir::new("highlight");
+    dir.run(&["add", "提交工作报告", "发给经理审核"]);
+
+    // 输出不是终端时不使用颜色
+    let output = dir.run(&["search", "bg", "审核"]);
+    assert!(output.contains("[ ] 0. 提交工作报告\n"), "{}", output);
+    assert!(!output.contains('\x1b'));
+
+    dir.run(&["config", "set", "colors.mode", "always"]);
+    dir.run(&["config", "set", "colors.highlight", "yellow"]);
+    let output = dir.run(&["search", "bg", "审核"]);
+    assert!(output.contains("提交工作\x1b[33m报告\x1b[0m"), "{}", output);
+    assert!(output.contains("描述: 发给经理\x1b[33m审核\x1b[0m"), "{}", output);
+
+    // 只显示匹配的注释
+    dir.run(&["note", "0", "经理要求周五前交"]);
+    dir.run(&["note", "0", "已经补充了数据"]);
+    let output = dir.run(&["search", "周五"]);
+    assert!(output.contains("注释: 经理要求\x1b[33m周五\x1b[0m前交"), "{}", output);
+    assert!(!output.contains("补充"), "{}", output);
+}
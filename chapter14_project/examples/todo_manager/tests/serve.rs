@@ -0,0 +1,238 @@
+//! `todo serve` 的集成测试：在系统分配的端口上启动服务，通过 HTTP 请求验证 API
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Stdio};
+
+use serde_json::{json, Value};
+
+mod common;
+
+use common::TestDir;
+
+/// 运行中的服务，离开作用域时结束进程并删除临时目录
+struct TestServer {
+    child: Child,
+    addr: String,
+    dir: TestDir,
+}
+
+impl TestServer {
+    fn start(name: &str) -> Self {
+        let dir = TestDir::new(name);
+        let mut child = dir
+            .command(&["serve", "--bind", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let addr = line.trim().rsplit("http://").next().unwrap().to_string();
+        TestServer { child, addr, dir }
+    }
+
+    /// 发送请求，返回（状态码，响应头，响应体）
+    fn request(&self, method: &str, path: &str, headers: &[(&str, &str)], body: Option<&Value>) -> Response {
+        self.send(method, path, headers, &body.map(Value::to_string).unwrap_or_default())
+    }
+
+    /// 以原样的请求体发送请求
+    fn send(&self, method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> Response {
+        let mut req = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            self.addr,
+            body.len()
+        );
+        for (name, value) in headers {
+            req.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        req.push_str("\r\n");
+        req.push_str(body);
+
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        stream.write_all(req.as_bytes()).unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let mut lines = head.lines();
+        let status = lines.next().unwrap().split(' ').nth(1).unwrap().parse().unwrap();
+        let headers = lines
+            .filter_map(|l| l.split_once(':'))
+            .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+            .collect();
+        let body = if body.is_empty() { Value::Null } else { serde_json::from_str(body).unwrap() };
+        Response { status, headers, body }
+    }
+
+    fn json(&self, method: &str, path: &str, headers: &[(&str, &str)], body: Option<&Value>) -> Response {
+        let mut all = vec![("Content-Type", "application/json")];
+        all.extend_from_slice(headers);
+        self.request(method, path, &all, body)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+#[test]
+fn test_crud_and_filters() {
+    let server = TestServer::start("crud");
+
+    let created = server.json(
+        "POST",
+        "/todos",
+        &[],
+        Some(&json!({ "title": "写周报", "priority": "high", "due_date": "2026-10-20", "tags": ["工作"] })),
+    );
+    assert_eq!(created.status, 201);
+    let uid = created.body["uid"].as_str().unwrap().to_string();
+    assert_eq!(created.header("location"), Some(format!("/todos/{}", uid).as_str()));
+    assert!(created.header("etag").is_some());
+    assert_eq!(created.body["priority"], "High");
+
+    server.json("POST", "/todos", &[], Some(&json!({ "title": "买牛奶" })));
+
+    // 命令行中的修改会立即反映在 API 中
+    server.dir.run(&["add", "修水管", "-p", "low"]);
+
+    let list = server.request("GET", "/todos?sort=priority", &[], None);
+    let titles: Vec<&str> = list.body.as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, vec!["写周报", "买牛奶", "修水管"]);
+
+    let tagged = server.request("GET", "/todos?tag=%E5%B7%A5%E4%BD%9C", &[], None);
+    assert_eq!(tagged.body.as_array().unwrap().len(), 1);
+
+    let patched = server.json(
+        "PATCH",
+        &format!("/todos/{}", uid),
+        &[],
+        Some(&json!({ "description": "先收集数据", "due_date": null })),
+    );
+    assert_eq!(patched.status, 200);
+    assert_eq!(patched.body["description"], "先收集数据");
+    assert_eq!(patched.body["due_date"], Value::Null);
+
+    let done = server.json("POST", &format!("/todos/{}/done", uid), &[], None);
+    assert_eq!(done.body["completed"], true);
+    let pending = server.request("GET", "/todos", &[], None);
+    assert_eq!(pending.body.as_array().unwrap().len(), 2);
+    let all = server.request("GET", "/todos?all=true", &[], None);
+    assert_eq!(all.body.as_array().unwrap().len(), 3);
+    let undone = server.json("POST", &format!("/todos/{}/undone", uid), &[], None);
+    assert_eq!(undone.body["completed"], false);
+
+    let deleted = server.request("DELETE", &format!("/todos/{}", uid), &[], None);
+    assert_eq!(deleted.status, 204);
+    let missing = server.request("GET", &format!("/todos/{}", uid), &[], None);
+    assert_eq!(missing.status, 404);
+    assert_eq!(missing.body["error"], "not_found");
+}
+
+#[test]
+fn test_etag_concurrency() {
+    let server = TestServer::start("etag");
+    let created = server.json("POST", "/todos", &[], Some(&json!({ "title": "写周报" })));
+    let path = format!("/todos/{}", created.body["uid"].as_str().unwrap());
+    let etag = created.header("etag").unwrap().to_string();
+
+    let fetched = server.request("GET", &path, &[], None);
+    assert_eq!(fetched.header("etag"), Some(etag.as_str()));
+
+    let first = server.json("PATCH", &path, &[("If-Match", &etag)], Some(&json!({ "priority": "low" })));
+    assert_eq!(first.status, 200);
+    let new_etag = first.header("etag").unwrap().to_string();
+    assert_ne!(new_etag, etag);
+
+    // 基于旧版本的修改会被拒绝
+    let stale = server.json("PATCH", &path, &[("If-Match", &etag)], Some(&json!({ "title": "写月报" })));
+    assert_eq!(stale.status, 412);
+    assert_eq!(stale.body["error"], "conflict");
+    let stale_delete = server.request("DELETE", &path, &[("If-Match", &etag)], None);
+    assert_eq!(stale_delete.status, 412);
+
+    let current = server.request("GET", &path, &[], None);
+    assert_eq!(current.body["title"], "写周报");
+    assert_eq!(current.body["priority"], "Low");
+    let deleted = server.request("DELETE", &path, &[("If-Match", &new_etag)], None);
+    assert_eq!(deleted.status, 204);
+}
+
+#[test]
+fn test_validation_errors() {
+    let server = TestServer::start("validation");
+
+    let cases = [
+        (json!({}), "缺少字段 title"),
+        (json!({ "title": "" }), "title"),
+        (json!({ "title": "写周报", "priority": "urgent" }), "优先级"),
+        (json!({ "title": "写周报", "due_date": "明天" }), "截止日期"),
+        (json!({ "title": "写周报", "tags": [1] }), "tags"),
+        (json!({ "title": "写周报", "completed": true }), "completed"),
+    ];
+    for (body, message) in cases {
+        let response = server.json("POST", "/todos", &[], Some(&body));
+        assert_eq!(response.status, 422, "{}", body);
+        assert_eq!(response.body["error"], "validation");
+        assert!(response.body["message"].as_str().unwrap().contains(message), "{}", response.body);
+    }
+
+    let not_json = server.request("POST", "/todos", &[("Content-Type", "text/plain")], Some(&json!({ "title": "x" })));
+    assert_eq!(not_json.status, 415);
+    let bad_query = server.request("GET", "/todos?sort=colour", &[], None);
+    assert_eq!(bad_query.status, 422);
+    let bad_method = server.request("PUT", "/todos", &[], None);
+    assert_eq!(bad_method.status, 405);
+    let bad_body = server.send("POST", "/todos", &[("Content-Type", "application/json")], "{\"title\":");
+    assert_eq!(bad_body.status, 400);
+    assert_eq!(bad_body.body["error"], "invalid_json");
+    assert_eq!(server.request("GET", "/todos", &[], None).body, json!([]));
+
+    // 数据文件损坏是服务端的错误
+    std::fs::write(server.dir.path().join("todo.json"), "不是 JSON").unwrap();
+    let corrupt = server.request("GET", "/todos", &[], None);
+    assert_eq!(corrupt.status, 500);
+    assert_eq!(corrupt.body["error"], "storage");
+}
+
+#[test]
+fn test_hooks() {
+    let server = TestServer::start("hooks");
+    server.dir.script(".config/todo/hooks/on-add", "read todo\necho \"$todo\" | sed 's/写周报/写月报/'");
+    server.dir.script(".config/todo/hooks/on-done", "echo 需要先验收\nexit 1");
+    server.dir.script(".config/todo/hooks/on-remove", "exit 1");
+
+    let created = server.json("POST", "/todos", &[], Some(&json!({ "title": "写周报" })));
+    assert_eq!(created.status, 201);
+    assert_eq!(created.body["title"], "写月报");
+    let uid = created.body["uid"].as_str().unwrap();
+
+    // 钩子拒绝时以 409 失败，数据文件不变
+    let done = server.json("POST", &format!("/todos/{}/done", uid), &[], None);
+    assert_eq!(done.status, 409);
+    assert_eq!(done.body["error"], "hook");
+    assert!(done.body["message"].as_str().unwrap().contains("需要先验收"), "{}", done.body);
+    assert_eq!(server.request("DELETE", &format!("/todos/{}", uid), &[], None).status, 409);
+    let todo = server.request("GET", &format!("/todos/{}", uid), &[], None);
+    assert_eq!(todo.body["completed"], false);
+}
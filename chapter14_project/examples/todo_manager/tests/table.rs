@@ -0,0 +1,76 @@
+//! 表格显示的集成测试：中文和 emoji 的对齐、按宽度换行和截断、列的配置以及颜色
+
+mod common;
+
+use common::TestDir;
+
+/// 三个包含中文和 emoji 的待办事项，当前时间固定
+fn setup(name: &str) -> TestDir {
+    let dir = TestDir::new(name).env("TODO_NOW", "2026-10-20T09:00:00");
+    dir.run(&["add", "提交季度工作报告", "-p", "high", "--tags", "工作", "--due", "2026-10-18"]);
+    dir.run(&["add", "Buy milk 🥛", "--tags", "生活,购物", "--due", "2026-10-25"]);
+    dir.run(&["add", "👨‍👩‍👧 家庭聚会", "-p", "low"]);
+    dir
+}
+
+#[test]
+fn test_table() {
+    let dir = setup("basic");
+
+    // 输出不是终端时不限制宽度
+    let output = dir.run(&["list", "--columns", "id,priority,title,tags"]);
+    assert_eq!(
+        output,
+        "ID  优先级  标题              标签\n\
+         ──  ──────  ────────────────  ──────────\n\
+         \u{20}0  高      提交季度工作报告  工作\n\
+         \u{20}1  中      Buy milk 🥛       生活, 购物\n\
+         \u{20}2  低      👨‍👩‍👧 家庭聚会\n"
+    );
+
+    // 按 COLUMNS 的宽度换行和截断
+    let output = dir.command(&["list", "--columns", "id,title,due"]).env("COLUMNS", "30").output().unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        output,
+        "ID  标题          截止日期\n\
+         ──  ────────────  ────────────\n\
+         \u{20}0  提交季度工作  2026-10-18…\n\
+         \u{20}   报告\n\
+         \u{20}1  Buy milk 🥛   2026-10-25…\n\
+         \u{20}2  👨‍👩‍👧 家庭聚会\n"
+    );
+
+    assert!(!dir.output(&["list", "--columns", "id,colour"]).status.success());
+}
+
+#[test]
+fn test_table_config() {
+    let dir = setup("config");
+    dir.run(&["config", "set", "list.style", "table"]);
+    dir.run(&["config", "set", "list.table_columns", "title, priority"]);
+
+    let output = dir.run(&["list"]);
+    assert!(output.starts_with("标题              优先级\n"), "{}", output);
+    assert!(dir.run(&["list", "--style", "lines"]).starts_with("待办事项列表：\n"));
+    assert!(dir.run(&["list", "--columns", "id"]).starts_with("ID\n"));
+
+    assert!(!dir.output(&["config", "set", "list.style", "grid"]).status.success());
+    assert!(!dir.output(&["config", "set", "list.table_columns", "id,id"]).status.success());
+}
+
+#[test]
+fn test_table_colors() {
+    let dir = setup("colors");
+    dir.run(&["done", "2"]);
+
+    // 输出不是终端时不使用颜色
+    assert!(!dir.run(&["list", "--style", "table"]).contains('\x1b'));
+
+    dir.run(&["config", "set", "colors.mode", "always"]);
+    let output = dir.run(&["list", "--all", "--columns", "id,status,priority,due"]);
+    // 优先级使用对应的颜色，只有未完成且已过期的截止日期使用 colors.overdue 的颜色
+    assert!(output.contains(" 0          \x1b[31m高\x1b[0m      \x1b[31m2026-10-18 23:59:59\x1b[0m\n"), "{}", output);
+    assert!(output.contains(" 1          \x1b[33m中\x1b[0m      2026-10-25 23:59:59\n"), "{}", output);
+    assert!(output.contains(" 2  已完成  \x1b[32m低\x1b[0m\n"), "{}", output);
+}
@@ -0,0 +1,59 @@
+//! 层级标签的集成测试：按上级标签筛选、标签统计、重命名和合并
+
+mod common;
+
+use common::TestDir;
+
+fn setup(name: &str) -> TestDir {
+    let dir = TestDir::new(name);
+    dir.run(&["add", "写接口文档", "--tags", "工作/后端/接口"]);
+    dir.run(&["add", "修复样式", "--tags", "工作/前端"]);
+    dir.run(&["add", "买牛奶", "--tags", "家庭"]);
+    dir.run(&["done", "1"]);
+    dir
+}
+
+#[test]
+fn test_filter_and_stats() {
+    let dir = setup("stats");
+
+    let list = dir.run(&["list", "--all", "--tag", "工作"]);
+    assert!(list.contains("写接口文档") && list.contains("修复样式"));
+    assert!(!list.contains("买牛奶"));
+    assert!(!dir.run(&["list", "--tag", "工作/后端"]).contains("修复样式"));
+
+    let flat = dir.run(&["tags"]);
+    assert!(flat.contains("工作/前端 1 项（未完成 0 项）"), "{}", flat);
+
+    let tree = dir.run(&["tags", "--tree"]);
+    assert!(tree.contains("工作 (2，未完成 1)"), "{}", tree);
+    assert!(tree.contains("├── 前端 (1，未完成 0)"), "{}", tree);
+    assert!(tree.contains("└── 后端 (1，未完成 1)"), "{}", tree);
+    assert!(tree.contains("    └── 接口 (1，未完成 1)"), "{}", tree);
+}
+
+#[test]
+fn test_rename_and_merge() {
+    let dir = setup("rename");
+    dir.run(&["tag", "set", "工作/后端/接口", "--color", "red", "--description", "对外接口"]);
+
+    dir.run(&["tag", "rename", "工作/后端", "后端"]);
+    let tags = dir.run(&["tags"]);
+    assert!(tags.contains("后端/接口 1 项（未完成 1 项） — 对外接口"), "{}", tags);
+    assert!(!tags.contains("工作/后端"));
+
+    // 上级标签只被自己使用时，可以重命名为上级
+    dir.run(&["tag", "rename", "后端/接口", "后端"]);
+    let tags = dir.run(&["tags"]);
+    assert!(tags.contains("后端 1 项（未完成 1 项） — 对外接口"), "{}", tags);
+
+    // 目标已存在时不能重命名，需要合并
+    assert!(!dir.output(&["tag", "rename", "工作/前端", "家庭"]).status.success());
+    dir.run(&["tag", "merge", "工作/前端", "家庭"]);
+    let tags = dir.run(&["tags"]);
+    assert!(tags.contains("家庭 2 项（未完成 1 项）"), "{}", tags);
+    assert!(!tags.contains("工作"));
+
+    assert!(!dir.output(&["tag", "merge", "不存在", "家庭"]).status.success());
+    assert!(!dir.output(&["tag", "set", "家庭", "--color", "orange"]).status.success());
+}
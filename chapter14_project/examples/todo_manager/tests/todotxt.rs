@@ -0,0 +1,52 @@
+//! todo.txt 存储的集成测试：标识符和提醒时间在多次加载之间保持不变
+
+use std::fs;
+use std::io::Write;
+use std::process::Stdio;
+
+mod common;
+
+use common::TestDir;
+
+/// 通过 `rpc` 发送一个请求，返回响应中的结果
+fn rpc(dir: &TestDir, method: &str) -> serde_json::Value {
+    let mut child = dir.command(&["rpc"]).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    let request = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": {} });
+    writeln!(child.stdin.take().unwrap(), "{}", request).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let response = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|message| message["id"] == 1)
+        .unwrap();
+    response["result"].clone()
+}
+
+#[test]
+fn test_stable_uid() {
+    let dir = TestDir::new("uid").data_file("todo.txt");
+    dir.run(&["add", "写周报"]);
+
+    let uid = rpc(&dir, "todo.list")[0]["uid"].clone();
+    assert!(uid.is_string(), "{}", uid);
+    assert_eq!(rpc(&dir, "todo.list")[0]["uid"], uid);
+
+    let contents = fs::read_to_string(dir.path().join("todo.txt")).unwrap();
+    assert!(contents.contains(&format!(" id:{}", uid.as_str().unwrap())), "{}", contents);
+}
+
+#[test]
+fn test_reminders() {
+    let dir = TestDir::new("remind").data_file("todo.txt");
+    dir.run(&["add", "写周报", "--due", "2026-10-20", "--remind", "1d,2h"]);
+    dir.run(&["add", "买牛奶", "--remind", "none"]);
+
+    let contents = fs::read_to_string(dir.path().join("todo.txt")).unwrap();
+    assert!(contents.contains(" remind:1d,2h "), "{}", contents);
+    assert!(contents.contains(" remind:none "), "{}", contents);
+
+    let output = dir.run(&["list", "--columns", "title,reminders"]);
+    assert!(output.contains("写周报  提前 1d, 2h"), "{}", output);
+    assert!(output.contains("买牛奶  不提醒"), "{}", output);
+}
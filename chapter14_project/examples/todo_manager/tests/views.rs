@@ -0,0 +1,71 @@
+//! 视图和上下文的集成测试：保存和应用视图、切换上下文、保留配置文件中的注释
+
+use std::fs;
+
+mod common;
+
+use common::TestDir;
+
+fn setup(name: &str) -> TestDir {
+    let dir = TestDir::new(name);
+    dir.run(&["add", "写周报", "--priority", "high", "--tags", "工作"]);
+    dir.run(&["add", "买牛奶", "--priority", "high", "--tags", "家庭"]);
+    dir.run(&["add", "整理书架", "--priority", "low", "--tags", "家庭"]);
+    dir.run(&["done", "0"]);
+    dir
+}
+
+#[test]
+fn test_views() {
+    let dir = setup("views");
+    fs::create_dir_all(dir.config_path().parent().unwrap()).unwrap();
+    fs::write(dir.config_path(), "# 我的配置\n").unwrap();
+
+    dir.run(&["view", "save", "urgent", "--priority", "high"]);
+    let config = fs::read_to_string(dir.config_path()).unwrap();
+    assert!(config.starts_with("# 我的配置\n"), "{}", config);
+    assert!(config.contains("[views.urgent]"), "{}", config);
+    assert!(dir.run(&["view", "list"]).contains("urgent"));
+
+    let list = dir.run(&["view", "urgent"]);
+    assert!(list.contains("买牛奶"));
+    assert!(!list.contains("写周报") && !list.contains("整理书架"));
+    // 命令行中的选项加在视图的选项之后
+    assert!(dir.run(&["view", "urgent", "--all"]).contains("写周报"));
+
+    // 无效的选项不会被保存
+    assert!(!dir.output(&["view", "save", "broken", "--priority", "urgent"]).status.success());
+    assert!(!dir.output(&["view", "save", "list", "--all"]).status.success());
+    assert!(!dir.output(&["view", "missing"]).status.success());
+
+    dir.run(&["view", "delete", "urgent"]);
+    let config = fs::read_to_string(dir.config_path()).unwrap();
+    assert!(!config.contains("views"), "{}", config);
+    assert!(!dir.output(&["view", "urgent"]).status.success());
+}
+
+#[test]
+fn test_contexts() {
+    let dir = setup("contexts");
+    dir.run(&["context", "define", "home", "--default-tags", "家庭", "--tag", "家庭"]);
+    dir.run(&["context", "home"]);
+    assert!(dir.run(&["context", "list"]).contains("home"));
+
+    dir.run(&["add", "修水管"]);
+    let list = dir.run(&["list"]);
+    assert!(list.contains("当前上下文: home"));
+    assert!(list.contains("修水管") && list.contains("买牛奶"));
+    assert!(!list.contains("写周报"));
+    assert!(dir.run(&["list", "--all", "--no-context"]).contains("写周报"));
+
+    // --no-context 时不加默认标签
+    dir.run(&["add", "开会", "--no-context"]);
+    assert!(!dir.run(&["list"]).contains("开会"));
+
+    dir.run(&["context", "none"]);
+    let list = dir.run(&["list"]);
+    assert!(!list.contains("当前上下文"));
+    assert!(list.contains("开会"));
+
+    assert!(!dir.output(&["context", "missing"]).status.success());
+}